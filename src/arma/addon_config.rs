@@ -0,0 +1,448 @@
+//! Reads the `CfgPatches` of an addon config, either rapified as `config.bin` or as plain
+//! `config.cpp` text. Only classes and arrays are kept, that is all the dependency check needs
+use anyhow::{Result, anyhow, bail};
+
+/// Signature at the start of a rapified config
+const RAP_SIGNATURE: &[u8] = b"\0raP";
+/// The body of the root class follows the signature and the header fields
+const RAP_ROOT_OFFSET: usize = 16;
+
+/// An addon a pbo declares in `CfgPatches`, with the addons it needs loaded before it
+#[derive(Debug, Clone, PartialEq)]
+pub struct CfgPatch {
+    pub name: String,
+    pub required: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum ConfigEntry {
+    Class {
+        name: String,
+        entries: Vec<ConfigEntry>,
+    },
+    Array {
+        name: String,
+        items: Vec<String>,
+    },
+    Other,
+}
+
+/// Read the patches of a `config.bin` or `config.cpp`, whichever the data is
+pub fn parse_config(data: &[u8]) -> Result<Vec<CfgPatch>> {
+    let root = match data.starts_with(RAP_SIGNATURE) {
+        true => rap_entries(data, RAP_ROOT_OFFSET, Some("CfgPatches"))?,
+        false => text_entries(&tokenize(&String::from_utf8_lossy(data)), &mut 0),
+    };
+    Ok(patches(&root))
+}
+
+fn patches(root: &[ConfigEntry]) -> Vec<CfgPatch> {
+    let Some(cfg_patches) = find_class(root, "CfgPatches") else {
+        return Vec::new();
+    };
+
+    cfg_patches
+        .iter()
+        .filter_map(|entry| match entry {
+            ConfigEntry::Class { name, entries } => Some(CfgPatch {
+                name: name.clone(),
+                required: entries
+                    .iter()
+                    .find_map(|entry| match entry {
+                        ConfigEntry::Array { name, items }
+                            if name.eq_ignore_ascii_case("requiredAddons") =>
+                        {
+                            Some(items.clone())
+                        }
+                        _ => None,
+                    })
+                    .unwrap_or_default(),
+            }),
+            _ => None,
+        })
+        .collect()
+}
+
+fn find_class<'a>(entries: &'a [ConfigEntry], wanted: &str) -> Option<&'a [ConfigEntry]> {
+    entries.iter().find_map(|entry| match entry {
+        ConfigEntry::Class { name, entries } if name.eq_ignore_ascii_case(wanted) => {
+            Some(entries.as_slice())
+        }
+        _ => None,
+    })
+}
+
+/// Read the entries of a rapified class body. With `only` set, just the classes with that name are
+/// read further down, the others are left empty
+fn rap_entries(data: &[u8], offset: usize, only: Option<&str>) -> Result<Vec<ConfigEntry>> {
+    let mut reader = RapReader { data, pos: offset };
+    let _parent = reader.string()?;
+    let count = reader.compressed_int()?;
+
+    let mut entries = Vec::new();
+    for _ in 0..count {
+        let entry = match reader.byte()? {
+            0 => {
+                let name = reader.string()?;
+                let body = reader.u32()? as usize;
+                let entries = match only {
+                    Some(only) if !name.eq_ignore_ascii_case(only) => Vec::new(),
+                    _ => rap_entries(data, body, None)?,
+                };
+                ConfigEntry::Class { name, entries }
+            }
+            1 => {
+                let kind = reader.byte()?;
+                reader.string()?;
+                match kind {
+                    0 => {
+                        reader.string()?;
+                    }
+                    1 | 2 => reader.skip(4)?,
+                    6 => reader.skip(8)?,
+                    kind => bail!("Unknown value type {} in rapified config", kind),
+                }
+                ConfigEntry::Other
+            }
+            2 => ConfigEntry::Array {
+                name: reader.string()?,
+                items: reader.array()?,
+            },
+            3 | 4 => {
+                reader.string()?;
+                ConfigEntry::Other
+            }
+            5 => {
+                let _flag = reader.u32()?;
+                ConfigEntry::Array {
+                    name: reader.string()?,
+                    items: reader.array()?,
+                }
+            }
+            kind => bail!("Unknown entry type {} in rapified config", kind),
+        };
+        entries.push(entry);
+    }
+    Ok(entries)
+}
+
+struct RapReader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl RapReader<'_> {
+    fn take(&mut self, len: usize) -> Result<&[u8]> {
+        let bytes = self
+            .data
+            .get(self.pos..self.pos + len)
+            .ok_or_else(|| anyhow!("Rapified config ends early"))?;
+        self.pos += len;
+        Ok(bytes)
+    }
+
+    fn skip(&mut self, len: usize) -> Result<()> {
+        self.take(len).map(|_| ())
+    }
+
+    fn byte(&mut self) -> Result<u8> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u32(&mut self) -> Result<u32> {
+        let bytes = self.take(4)?;
+        Ok(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+
+    /// 7 bits per byte, the top bit is set while more bytes follow
+    fn compressed_int(&mut self) -> Result<u32> {
+        let mut value = 0;
+        for shift in (0..32).step_by(7) {
+            let byte = self.byte()?;
+            value |= ((byte & 0x7F) as u32) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        bail!("Invalid number in rapified config")
+    }
+
+    fn string(&mut self) -> Result<String> {
+        let len = self.data[self.pos.min(self.data.len())..]
+            .iter()
+            .position(|b| *b == 0)
+            .ok_or_else(|| anyhow!("Rapified config ends early"))?;
+        let string = String::from_utf8_lossy(self.take(len)?).to_string();
+        self.pos += 1;
+        Ok(string)
+    }
+
+    /// The strings of an array, nested arrays are flattened and numbers left out
+    fn array(&mut self) -> Result<Vec<String>> {
+        let count = self.compressed_int()?;
+        let mut items = Vec::new();
+        for _ in 0..count {
+            match self.byte()? {
+                0 | 4 => items.push(self.string()?),
+                1 | 2 => self.skip(4)?,
+                3 => items.extend(self.array()?),
+                6 => self.skip(8)?,
+                kind => bail!("Unknown array item type {} in rapified config", kind),
+            }
+        }
+        Ok(items)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Word(String),
+    Str(String),
+    Symbol(char),
+}
+
+/// Split a config.cpp into words, strings and symbols. Comments and preprocessor lines are
+/// dropped, macros are not expanded
+fn tokenize(raw: &str) -> Vec<Token> {
+    let mut tokens = Vec::new();
+    let mut chars = raw.chars().peekable();
+    let mut line_start = true;
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\n' => {
+                line_start = true;
+                continue;
+            }
+            c if c.is_whitespace() => continue,
+            '#' if line_start => {
+                // directives continue on the next line after a backslash
+                let mut last = c;
+                for c in chars.by_ref() {
+                    if c == '\n' && last != '\\' {
+                        break;
+                    }
+                    if !c.is_whitespace() || c == '\n' {
+                        last = c;
+                    }
+                }
+                line_start = true;
+                continue;
+            }
+            '/' if chars.peek() == Some(&'/') => {
+                for c in chars.by_ref() {
+                    if c == '\n' {
+                        break;
+                    }
+                }
+                line_start = true;
+                continue;
+            }
+            '/' if chars.peek() == Some(&'*') => {
+                chars.next();
+                let mut last = ' ';
+                for c in chars.by_ref() {
+                    if last == '*' && c == '/' {
+                        break;
+                    }
+                    last = c;
+                }
+            }
+            '"' => {
+                let mut string = String::new();
+                while let Some(c) = chars.next() {
+                    if c == '"' {
+                        // a doubled quote is a quote inside the string
+                        if chars.peek() != Some(&'"') {
+                            break;
+                        }
+                        chars.next();
+                    }
+                    string.push(c);
+                }
+                tokens.push(Token::Str(string));
+            }
+            '{' | '}' | '[' | ']' | '(' | ')' | '=' | ';' | ':' | ',' | '+' => {
+                tokens.push(Token::Symbol(c))
+            }
+            _ => {
+                let mut word = c.to_string();
+                while let Some(&c) = chars.peek() {
+                    if c.is_whitespace() || "{}[]()=;:,+\"".contains(c) {
+                        break;
+                    }
+                    word.push(c);
+                    chars.next();
+                }
+                tokens.push(Token::Word(word));
+            }
+        }
+        line_start = false;
+    }
+    tokens
+}
+
+/// Read the entries of a class body, up to its closing brace
+fn text_entries(tokens: &[Token], pos: &mut usize) -> Vec<ConfigEntry> {
+    let mut entries = Vec::new();
+
+    while let Some(token) = tokens.get(*pos) {
+        *pos += 1;
+        let Token::Word(word) = token else {
+            if *token == Token::Symbol('}') {
+                break;
+            }
+            continue;
+        };
+
+        if word == "class" {
+            let Some(Token::Word(name)) = tokens.get(*pos) else {
+                continue;
+            };
+            let name = name.clone();
+            *pos += 1;
+            if tokens.get(*pos) == Some(&Token::Symbol(':')) {
+                *pos += 2;
+            }
+            if tokens.get(*pos) == Some(&Token::Symbol('{')) {
+                *pos += 1;
+                let class = text_entries(tokens, pos);
+                entries.push(ConfigEntry::Class {
+                    name,
+                    entries: class,
+                });
+            }
+            continue;
+        }
+
+        let is_array = tokens.get(*pos) == Some(&Token::Symbol('['))
+            && tokens.get(*pos + 1) == Some(&Token::Symbol(']'))
+            && matches!(tokens.get(*pos + 2), Some(Token::Symbol('=' | '+')));
+        if is_array {
+            *pos += 2;
+            // `+=` adds to the inherited array, for dependencies that is the same
+            while matches!(tokens.get(*pos), Some(Token::Symbol('=' | '+'))) {
+                *pos += 1;
+            }
+            entries.push(ConfigEntry::Array {
+                name: word.clone(),
+                items: text_array(tokens, pos),
+            });
+            continue;
+        }
+
+        // values and macros, up to the end of the statement
+        while let Some(token) = tokens.get(*pos) {
+            match token {
+                Token::Symbol(';') => {
+                    *pos += 1;
+                    break;
+                }
+                Token::Symbol('}') => break,
+                _ => *pos += 1,
+            }
+        }
+        entries.push(ConfigEntry::Other);
+    }
+    entries
+}
+
+/// The strings and words of an array, nested arrays are flattened
+fn text_array(tokens: &[Token], pos: &mut usize) -> Vec<String> {
+    let mut items = Vec::new();
+    if tokens.get(*pos) != Some(&Token::Symbol('{')) {
+        return items;
+    }
+
+    let mut depth = 0;
+    while let Some(token) = tokens.get(*pos) {
+        *pos += 1;
+        match token {
+            Token::Symbol('{') => depth += 1,
+            Token::Symbol('}') => {
+                depth -= 1;
+                if depth == 0 {
+                    break;
+                }
+            }
+            Token::Str(item) | Token::Word(item) => items.push(item.clone()),
+            Token::Symbol(_) => {}
+        }
+    }
+    items
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_config_cpp() {
+        let raw = "#include \"script_component.hpp\"\n\
+                   #define LONG_MACRO(a) \\\n    a\n\
+                   class CfgPatches {\n\
+                       // the addon\n\
+                       class ace_common {\n\
+                           units[] = {};\n\
+                           requiredVersion = 2.10;\n\
+                           requiredAddons[] = {\"cba_main\", \"A3_Data_F\"};\n\
+                           author = \"ACE \"\"Team\"\"\";\n\
+                       };\n\
+                       /* no dependencies */\n\
+                       class ace_fonts { requiredAddons[] = {}; };\n\
+                   };\n\
+                   class CfgVehicles { class Man; class CAManBase: Man {}; };\n";
+
+        assert_eq!(
+            parse_config(raw.as_bytes()).unwrap(),
+            vec![
+                CfgPatch {
+                    name: "ace_common".to_string(),
+                    required: vec!["cba_main".to_string(), "A3_Data_F".to_string()],
+                },
+                CfgPatch {
+                    name: "ace_fonts".to_string(),
+                    required: Vec::new(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_rapified() {
+        let mut rap = RAP_SIGNATURE.to_vec();
+        rap.extend(0u32.to_le_bytes());
+        rap.extend(8u32.to_le_bytes());
+        // enum offset, not read
+        rap.extend(0u32.to_le_bytes());
+
+        // root: a value, then CfgPatches with its body at offset 46
+        rap.extend(b"\0\x02");
+        rap.extend(b"\x01\x00version\0\x33\0");
+        rap.extend(b"\x00CfgPatches\0");
+        rap.extend(46u32.to_le_bytes());
+        assert_eq!(rap.len(), 46);
+
+        // CfgPatches: one class with its body at offset 62
+        rap.extend(b"\0\x01");
+        rap.extend(b"\x00cba_main\0");
+        rap.extend(62u32.to_le_bytes());
+        assert_eq!(rap.len(), 62);
+
+        // cba_main: a number, then requiredAddons with two strings and a number
+        rap.extend(b"\0\x02");
+        rap.extend(b"\x01\x01requiredVersion\0");
+        rap.extend(2.1f32.to_le_bytes());
+        rap.extend(b"\x02requiredAddons\0\x03");
+        rap.extend(b"\x00cba_common\0\x00A3_Data_F\0\x02");
+        rap.extend(1u32.to_le_bytes());
+
+        assert_eq!(
+            parse_config(&rap).unwrap(),
+            vec![CfgPatch {
+                name: "cba_main".to_string(),
+                required: vec!["cba_common".to_string(), "A3_Data_F".to_string()],
+            }]
+        );
+    }
+}
@@ -0,0 +1,243 @@
+//! Checks that the addons the selected mods need are loaded, from the `requiredAddons` in the
+//! `CfgPatches` of their pbos
+use crate::arma::addon_config::{CfgPatch, parse_config};
+use crate::arma::mod_paths::{resolve_case_insensitive, resolve_mod_path};
+use crate::arma::pbo::Pbo;
+use anyhow::Result;
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{LazyLock, Mutex};
+use std::time::SystemTime;
+
+/// Addons of the base game that are not `a3_` prefixed
+const VANILLA_ADDONS: [&str; 3] = ["a3data", "3den", "core"];
+/// Prefixes of the addons of the creator DLCs, by DLC. Servers load them without a mod, like the
+/// `a3_` addons of the game
+const DLC_PREFIXES: [(&str, &[&str]); 6] = [
+    ("Global Mobilization", &["gm_"]),
+    ("S.O.G. Prairie Fire", &["vn_"]),
+    ("Western Sahara", &["lxws_", "ws_"]),
+    ("Spearhead 1944", &["spe_", "ww2_spe_"]),
+    ("CSLA Iron Curtain", &["csla", "us85"]),
+    ("Reaction Forces", &["rf_"]),
+];
+
+/// Patches read from a pbo, with the modification time and size of the pbo they were read at
+struct CachedPatches {
+    modified: SystemTime,
+    len: u64,
+    patches: Vec<CfgPatch>,
+}
+
+/// Reading the configs of big mods takes a while, so it only happens again once a pbo changes
+static PATCH_CACHE: LazyLock<Mutex<HashMap<PathBuf, CachedPatches>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+/// The patches declared by the pbos in the addons folder of a mod. A pbo that can not be read
+/// counts as providing an addon named like the pbo, with a warning
+pub fn mod_patches(mod_folder: &Path) -> (Vec<CfgPatch>, Vec<String>) {
+    let mut patches = Vec::new();
+    let mut warnings = Vec::new();
+
+    let Some(addons) = resolve_case_insensitive(mod_folder, Path::new("addons")) else {
+        return (patches, warnings);
+    };
+    let Ok(entries) = fs::read_dir(mod_folder.join(addons)) else {
+        return (patches, warnings);
+    };
+
+    let mut pbos: Vec<PathBuf> = entries
+        .filter_map(|e| e.ok())
+        .map(|e| e.path())
+        .filter(|p| {
+            p.extension()
+                .is_some_and(|ext| ext.eq_ignore_ascii_case("pbo"))
+        })
+        .collect();
+    pbos.sort();
+
+    for pbo in pbos {
+        match cached_pbo_patches(&pbo) {
+            Ok(found) => patches.extend(found),
+            Err(err) => {
+                warnings.push(format!(
+                    "Could not read the config of {}: {}",
+                    pbo.to_string_lossy(),
+                    err
+                ));
+                if let Some(stem) = pbo.file_stem() {
+                    patches.push(CfgPatch {
+                        name: stem.to_string_lossy().to_string(),
+                        required: Vec::new(),
+                    });
+                }
+            }
+        }
+    }
+    (patches, warnings)
+}
+
+/// [`pbo_patches`] from the cache, read again if the pbo changed since
+fn cached_pbo_patches(path: &Path) -> Result<Vec<CfgPatch>> {
+    let metadata = fs::metadata(path)?;
+    let modified = metadata.modified()?;
+    if let Some(cached) = PATCH_CACHE.lock().unwrap().get(path)
+        && cached.modified == modified
+        && cached.len == metadata.len()
+    {
+        return Ok(cached.patches.clone());
+    }
+
+    let patches = pbo_patches(path)?;
+    PATCH_CACHE.lock().unwrap().insert(
+        path.to_path_buf(),
+        CachedPatches {
+            modified,
+            len: metadata.len(),
+            patches: patches.clone(),
+        },
+    );
+    Ok(patches)
+}
+
+/// Read the configs of the mod into the cache, so the dependency check of a launch does not have to
+pub fn cache_patches(mod_folder: &Path) {
+    mod_patches(mod_folder);
+}
+
+/// The patches in the `config.bin`, or else the `config.cpp`, at the root of a pbo. Pbos with only
+/// data in them have neither
+fn pbo_patches(path: &Path) -> Result<Vec<CfgPatch>> {
+    let pbo = Pbo::open(path)?;
+    match pbo.entry("config.bin").or_else(|| pbo.entry("config.cpp")) {
+        Some(config) => parse_config(&pbo.read(config)?),
+        None => Ok(Vec::new()),
+    }
+}
+
+fn is_vanilla(addon: &str) -> bool {
+    let addon = addon.to_lowercase();
+    addon.starts_with("a3_")
+        || VANILLA_ADDONS.contains(&addon.as_str())
+        || DLC_PREFIXES
+            .iter()
+            .flat_map(|(_, prefixes)| prefixes.iter())
+            .any(|prefix| addon.starts_with(prefix))
+}
+
+/// Warnings for the addons the mods need that neither they nor the game provide, one per mod.
+/// Mod paths are relative to the A3 root
+pub fn missing_dependencies(a3root: &Path, mods: &[PathBuf]) -> Vec<String> {
+    let mut warnings = Vec::new();
+    let loaded: Vec<(&PathBuf, Vec<CfgPatch>)> = mods
+        .iter()
+        .map(|mod_path| {
+            let (patches, read_warnings) =
+                mod_patches(&a3root.join(resolve_mod_path(a3root, mod_path)));
+            warnings.extend(read_warnings);
+            (mod_path, patches)
+        })
+        .collect();
+
+    let provided: HashSet<String> = loaded
+        .iter()
+        .flat_map(|(_, patches)| patches.iter().map(|p| p.name.to_lowercase()))
+        .collect();
+
+    for (mod_path, patches) in &loaded {
+        let missing: BTreeSet<&str> = patches
+            .iter()
+            .flat_map(|p| p.required.iter())
+            .filter(|addon| !is_vanilla(addon) && !provided.contains(&addon.to_lowercase()))
+            .map(String::as_str)
+            .collect();
+        if !missing.is_empty() {
+            warnings.push(format!(
+                "{} needs {}, which none of the selected mods provide",
+                mod_path.to_string_lossy(),
+                missing.into_iter().collect::<Vec<_>>().join(", ")
+            ));
+        }
+    }
+
+    for warning in &warnings {
+        log::warn!("{}", warning);
+    }
+    warnings
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Write a pbo holding only a `config.cpp` that declares the patch
+    fn write_pbo(path: &Path, patch: &str, required: &[&str]) {
+        let required: Vec<String> = required
+            .iter()
+            .map(|addon| format!("\"{}\"", addon))
+            .collect();
+        let config = format!(
+            "class CfgPatches {{ class {} {{ requiredAddons[] = {{{}}}; }}; }};",
+            patch,
+            required.join(", ")
+        );
+
+        let mut pbo = b"config.cpp\0".to_vec();
+        for value in [0, 0, 0, 0, config.len() as u32] {
+            pbo.extend(value.to_le_bytes());
+        }
+        pbo.extend([0; 21]);
+        pbo.extend(config.as_bytes());
+
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, pbo).unwrap();
+    }
+
+    #[test]
+    fn test_missing_dependencies() {
+        let a3root =
+            std::env::temp_dir().join(format!("corvus_test_dependencies_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&a3root);
+        write_pbo(
+            &a3root.join("mods/@cba/addons/cba_main.pbo"),
+            "cba_main",
+            &["A3_Data_F", "core"],
+        );
+        write_pbo(
+            &a3root.join("mods/@ace/Addons/ace_common.pbo"),
+            "ace_common",
+            &["CBA_Main", "gm_core", "vn_weapons", "lxWS_Core", "rhs_main"],
+        );
+        let cba = PathBuf::from("mods/@cba");
+        let ace = PathBuf::from("mods/@ace");
+
+        // vanilla and creator DLC addons are always there
+        assert!(missing_dependencies(&a3root, std::slice::from_ref(&cba)).is_empty());
+        assert_eq!(
+            missing_dependencies(&a3root, &[cba.clone(), ace.clone()]),
+            vec!["mods/@ace needs rhs_main, which none of the selected mods provide"]
+        );
+        assert_eq!(
+            missing_dependencies(&a3root, std::slice::from_ref(&ace)),
+            vec!["mods/@ace needs CBA_Main, rhs_main, which none of the selected mods provide"]
+        );
+
+        // a changed pbo is read again instead of coming from the cache
+        write_pbo(
+            &a3root.join("mods/@ace/Addons/ace_common.pbo"),
+            "ace_common",
+            &["cba_main"],
+        );
+        assert!(missing_dependencies(&a3root, &[cba, ace]).is_empty());
+
+        // a pbo that can not be read still provides its name
+        fs::write(a3root.join("mods/@cba/addons/cba_xeh.pbo"), b"broken").unwrap();
+        let (patches, warnings) = mod_patches(&a3root.join("mods/@cba"));
+        assert_eq!(patches.len(), 2);
+        assert_eq!(patches[1].name, "cba_xeh");
+        assert_eq!(warnings.len(), 1);
+
+        fs::remove_dir_all(&a3root).unwrap();
+    }
+}
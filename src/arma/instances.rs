@@ -0,0 +1,89 @@
+//! Status of named instances, and what they share with each other
+use crate::Config;
+use crate::arma::launch_plan::LaunchPlan;
+use crate::arma::processes::{ProcessKind, ProcessRecord, ProcessRegistry, RunningServer};
+use crate::arma::server_launch::ServerLaunch;
+use anyhow::Result;
+use std::path::PathBuf;
+
+/// What is running for an instance, like "Server on port 2302, 2 HCs"
+pub fn instance_status(registry: &ProcessRegistry, instance: Option<&str>) -> String {
+    let running: Vec<&ProcessRecord> = registry
+        .records
+        .iter()
+        .filter(|r| r.is_running() && r.instance.as_deref() == instance)
+        .collect();
+    let hcs = running
+        .iter()
+        .filter(|r| matches!(r.kind, ProcessKind::HeadlessClient(_)))
+        .count();
+
+    match running.iter().find(|r| r.kind == ProcessKind::Server) {
+        Some(server) => format!("Server on port {}, {} HCs", server.port, hcs),
+        None if hcs > 0 => format!("No server, {} HCs", hcs),
+        None => "Not running".to_string(),
+    }
+}
+
+/// Other configured instances on the same A3 root, they share its keys folder
+pub fn sharing_a3_root(config: &Config, instance: Option<&str>) -> Vec<String> {
+    let a3_root = config.instance_a3_root(instance);
+    config
+        .instances
+        .iter()
+        .filter(|i| Some(i.name.as_str()) != instance)
+        .filter(|i| config.instance_a3_root(Some(&i.name)) == a3_root)
+        .map(|i| i.name.clone())
+        .collect()
+}
+
+/// The keys folder of the A3 root is used by running servers. Changing it while they run breaks
+/// signature checks for the players on them
+#[derive(Debug)]
+pub struct KeysInUse {
+    pub keys_folder: PathBuf,
+    pub servers: Vec<RunningServer>,
+}
+
+impl std::fmt::Display for KeysInUse {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let servers: Vec<String> = self.servers.iter().map(RunningServer::to_string).collect();
+        write!(
+            f,
+            "The launch changes the keys in {}, which is in use by:\n{}",
+            self.keys_folder.to_string_lossy(),
+            servers.join("\n")
+        )
+    }
+}
+
+impl std::error::Error for KeysInUse {}
+
+/// Fails if the launch changes the keys folder while a server on the same A3 root is running.
+/// Servers in `replacing` are about to be stopped for this launch, so they do not count. `scanned`
+/// are the servers [`scan_servers`](crate::arma::processes::scan_servers) found on the A3 root
+pub fn check_keys_in_use(
+    registry: &ProcessRegistry,
+    launch: &ServerLaunch,
+    plan: &LaunchPlan,
+    replacing: &[u32],
+    scanned: &[u32],
+) -> Result<()> {
+    if !plan.keys.changes_folder() {
+        return Ok(());
+    }
+
+    let servers: Vec<RunningServer> = registry
+        .servers_on_root(&launch.a3_root, scanned)
+        .into_iter()
+        .filter(|server| !replacing.contains(&server.pid))
+        .collect();
+    match servers.is_empty() {
+        true => Ok(()),
+        false => Err(KeysInUse {
+            keys_folder: plan.keys_folder.clone(),
+            servers,
+        }
+        .into()),
+    }
+}
@@ -0,0 +1,547 @@
+//! Synchronisation of the A3 keys folder. The launcher records which keys it installed in a
+//! manifest, so it only removes its own keys and leaves keys placed by admins alone
+use crate::configs::keys::{KeyConflict, KeySettings};
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+static KEY_MANIFEST_FILE: &str = "corvuslauncher_keys.toml";
+
+/// A bikey found in a mod, and the filename it gets in the keys folder
+#[derive(Debug, Clone, PartialEq)]
+pub struct ModKey {
+    /// the mod as given in the modlist
+    pub mod_path: PathBuf,
+    /// full path to the key inside the mod
+    pub source: PathBuf,
+    /// filename in the keys folder
+    pub name: String,
+    /// contents of the key, to tell keys with the same name apart
+    pub contents: Vec<u8>,
+}
+
+impl ModKey {
+    pub fn new(mod_path: &Path, source: PathBuf) -> Result<ModKey> {
+        Ok(ModKey {
+            mod_path: mod_path.to_path_buf(),
+            name: source
+                .file_name()
+                .unwrap_or_default()
+                .to_string_lossy()
+                .to_string(),
+            contents: fs::read(&source)?,
+            source,
+        })
+    }
+}
+
+/// Launch was halted because mods ship different keys with the same filename
+#[derive(Debug)]
+pub struct KeyConflicts(pub Vec<String>);
+
+impl std::fmt::Display for KeyConflicts {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0.join("\n"))
+    }
+}
+
+impl std::error::Error for KeyConflicts {}
+
+/// Keys to install after resolving filename conflicts
+#[derive(Debug, Clone, Default)]
+pub struct ResolvedKeys {
+    pub keys: Vec<ModKey>,
+    /// mods that contributed each key, by filename in the keys folder
+    pub sources: BTreeMap<String, Vec<PathBuf>>,
+    /// keys that were renamed to avoid a conflict
+    pub warnings: Vec<String>,
+}
+
+/// Make sure no two different keys end up with the same filename in the keys folder. Keys with the
+/// same name and contents are installed once. `foreign` are the keys the launcher did not install,
+/// by lowercase name as from [`foreign_keys`]. They keep their name, so a mod key that differs from
+/// one is a conflict as well, and renamed keys do not take their names
+pub fn resolve_conflicts(
+    keys: Vec<ModKey>,
+    on_conflict: KeyConflict,
+    foreign: &BTreeMap<String, PathBuf>,
+) -> Result<ResolvedKeys> {
+    // the keys folder is case insensitive on windows, so group by lowercase name
+    let mut by_name: BTreeMap<String, Vec<ModKey>> = BTreeMap::new();
+    for key in keys {
+        by_name
+            .entry(key.name.to_lowercase())
+            .or_default()
+            .push(key);
+    }
+
+    let mut taken: BTreeSet<String> = foreign.keys().chain(by_name.keys()).cloned().collect();
+    let mut resolved = ResolvedKeys::default();
+    let mut conflicts: Vec<String> = Vec::new();
+
+    for (lowercase, group) in by_name {
+        // what has the name in the keys folder: a key the launcher did not install, or else the
+        // first of the mods
+        let mut holder: Option<(String, Vec<u8>)> = match foreign.get(&lowercase) {
+            Some(path) => Some(("the keys folder".to_string(), fs::read(path)?)),
+            None => None,
+        };
+        let mut conflicting: Vec<String> = Vec::new();
+
+        // distinct contents, in the order the mods were given
+        let mut distinct: Vec<ModKey> = Vec::new();
+        for key in &group {
+            match distinct.iter().find(|k| k.contents == key.contents) {
+                Some(first) => resolved
+                    .sources
+                    .entry(first.name.clone())
+                    .or_default()
+                    .push(key.mod_path.clone()),
+                None => {
+                    let mut key = key.clone();
+                    match &holder {
+                        None => {
+                            holder = Some((
+                                key.mod_path.to_string_lossy().to_string(),
+                                key.contents.clone(),
+                            ))
+                        }
+                        Some((_, contents)) if *contents == key.contents => {}
+                        Some((held_by, _)) => {
+                            conflicting.push(key.mod_path.to_string_lossy().to_string());
+                            if on_conflict == KeyConflict::Rename {
+                                key.name = renamed_key(&key, &taken);
+                                taken.insert(key.name.to_lowercase());
+                                resolved.warnings.push(format!(
+                                    "{} from {} differs from {}, installed as {}",
+                                    group[0].name,
+                                    key.mod_path.to_string_lossy(),
+                                    held_by,
+                                    key.name
+                                ));
+                            }
+                        }
+                    }
+                    resolved
+                        .sources
+                        .entry(key.name.clone())
+                        .or_default()
+                        .push(key.mod_path.clone());
+                    distinct.push(key);
+                }
+            }
+        }
+
+        if !conflicting.is_empty() && on_conflict == KeyConflict::Error {
+            let held_by = holder.map(|(held_by, _)| held_by).unwrap_or_default();
+            conflicts.push(format!(
+                "Conflicting keys named {} in: {}, {}",
+                group[0].name,
+                held_by,
+                conflicting.join(", ")
+            ));
+        }
+
+        resolved.keys.extend(distinct);
+    }
+
+    if !conflicts.is_empty() {
+        for e in &conflicts {
+            log::error!("{}", e);
+        }
+        return Err(KeyConflicts(conflicts).into());
+    }
+
+    Ok(resolved)
+}
+
+/// name for a conflicting key, with the mod folder appended: `mod.bikey` -> `mod_@ace.bikey`. A
+/// number is added if that name is taken as well: `mod_@ace_2.bikey`
+fn renamed_key(key: &ModKey, taken: &BTreeSet<String>) -> String {
+    let stem = Path::new(&key.name)
+        .file_stem()
+        .unwrap_or_default()
+        .to_string_lossy()
+        .to_string();
+    let mod_name: String = key
+        .mod_path
+        .to_string_lossy()
+        .replace('\\', "/")
+        .rsplit('/')
+        .find(|part| !part.is_empty())
+        .unwrap_or_default()
+        .chars()
+        .map(|c| match c.is_alphanumeric() || c == '@' || c == '-' {
+            true => c,
+            false => '_',
+        })
+        .collect();
+    let base = format!("{}_{}", stem, mod_name);
+    std::iter::once(format!("{}.bikey", base))
+        .chain((2..).map(|n| format!("{}_{}.bikey", base, n)))
+        .find(|name| !taken.contains(&name.to_lowercase()))
+        .unwrap_or_default()
+}
+
+/// Keys in the keys folder that the launcher did not install, by lowercase name
+pub fn foreign_keys(a3root: &Path, keys_folder: &Path) -> Result<BTreeMap<String, PathBuf>> {
+    let manifest = KeyManifest::load(a3root)?.unwrap_or_default();
+    let installed: BTreeSet<String> = manifest
+        .installed
+        .keys()
+        .map(|name| name.to_lowercase())
+        .collect();
+
+    Ok(fs::read_dir(keys_folder)?
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.path().is_file())
+        .map(|entry| {
+            (
+                entry.file_name().to_string_lossy().to_lowercase(),
+                entry.path(),
+            )
+        })
+        .filter(|(name, _)| !installed.contains(name))
+        .collect())
+}
+
+/// Keys installed by the launcher, by filename in the keys folder and the mod key it was copied from
+#[derive(Deserialize, Serialize, Debug, Clone, Default, PartialEq)]
+pub struct KeyManifest {
+    #[serde(default)]
+    pub installed: BTreeMap<String, PathBuf>,
+}
+
+impl KeyManifest {
+    pub fn path(a3root: &Path) -> PathBuf {
+        a3root.join(KEY_MANIFEST_FILE)
+    }
+
+    /// Load the manifest for an A3 root. `None` if the launcher has not written one yet
+    pub fn load(a3root: &Path) -> Result<Option<KeyManifest>> {
+        let path = KeyManifest::path(a3root);
+        if !path.exists() {
+            return Ok(None);
+        }
+        let raw = fs::read_to_string(path)?;
+        Ok(Some(toml::from_str(&raw)?))
+    }
+
+    pub fn save(&self, a3root: &Path) -> Result<()> {
+        fs::write(KeyManifest::path(a3root), toml::to_string(self)?)?;
+        Ok(())
+    }
+}
+
+/// The changes needed to get the keys folder to the desired set of keys
+#[derive(Debug, Clone, Default)]
+pub struct KeySync {
+    pub a3_root: PathBuf,
+    pub keys_folder: PathBuf,
+    /// keys in the keys folder that the launcher installed and are no longer needed
+    pub remove: Vec<PathBuf>,
+    /// mod keys that are missing or outdated in the keys folder
+    pub copy: Vec<ModKey>,
+    /// keys that are already in place with the right contents
+    pub unchanged: Vec<ModKey>,
+    /// manifest after the sync has been applied
+    pub manifest: KeyManifest,
+}
+
+impl KeySync {
+    /// Diff the wanted mod keys against what is in the keys folder
+    pub fn plan(
+        a3root: &Path,
+        keys_folder: &Path,
+        wanted: &[ModKey],
+        settings: &KeySettings,
+    ) -> Result<KeySync> {
+        // conflicts are resolved already, so names are unique
+        let wanted: BTreeMap<String, &ModKey> =
+            wanted.iter().map(|key| (key.name.clone(), key)).collect();
+
+        let current: BTreeMap<String, PathBuf> = fs::read_dir(keys_folder)?
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| entry.path().is_file())
+            .map(|entry| {
+                (
+                    entry.file_name().to_string_lossy().to_string(),
+                    entry.path(),
+                )
+            })
+            .collect();
+
+        // Without a manifest the launcher has not synced this folder yet, so nothing in it is known
+        // to be ours. Nothing is removed, and the wanted keys already in place are recorded
+        let (previous, first_run) = match KeyManifest::load(a3root)? {
+            Some(manifest) => (manifest, false),
+            None => (KeyManifest::default(), true),
+        };
+
+        let mut sync = KeySync {
+            a3_root: a3root.to_path_buf(),
+            keys_folder: keys_folder.to_path_buf(),
+            ..Default::default()
+        };
+
+        for (name, key) in &wanted {
+            // the keys folder is case insensitive on windows
+            let existing = current.get_key_value(name).or_else(|| {
+                current
+                    .iter()
+                    .find(|(current_name, _)| current_name.eq_ignore_ascii_case(name))
+            });
+            let in_place = match existing {
+                Some((_, path)) => same_contents(path, &key.source)?,
+                None => false,
+            };
+
+            // resolve_conflicts renames keys that differ from one the launcher did not install,
+            // the key of the admin is never overwritten
+            if let Some((existing_name, _)) = existing
+                && !in_place
+                && !previous.installed.contains_key(existing_name)
+            {
+                return Err(KeyConflicts(vec![format!(
+                    "{} in the keys folder was not installed by the launcher, and differs from the key of {}",
+                    existing_name,
+                    key.mod_path.to_string_lossy()
+                )])
+                .into());
+            }
+
+            match in_place {
+                true => sync.unchanged.push((*key).clone()),
+                false => sync.copy.push((*key).clone()),
+            }
+
+            // keys an admin placed themselves with the same content stay theirs
+            if !in_place || first_run || previous.installed.contains_key(name) {
+                sync.manifest
+                    .installed
+                    .insert(name.clone(), key.source.clone());
+            }
+        }
+
+        for (name, path) in &current {
+            if previous.installed.contains_key(name)
+                && !wanted.contains_key(name)
+                && !settings.is_always_kept(name)
+            {
+                sync.remove.push(path.clone());
+            }
+        }
+
+        Ok(sync)
+    }
+
+    /// Whether applying changes anything in the keys folder
+    pub fn changes_folder(&self) -> bool {
+        !self.copy.is_empty() || !self.remove.is_empty()
+    }
+
+    /// Copy new keys before removing old ones, so the folder is never left empty
+    pub fn apply(&self) -> Result<()> {
+        for key in &self.copy {
+            fs::copy(&key.source, self.keys_folder.join(&key.name))?;
+        }
+        for key in &self.remove {
+            fs::remove_file(key)?;
+        }
+        self.manifest.save(&self.a3_root)?;
+
+        log::info!(
+            "Synchronised keys folder, copied {} and removed {} keys",
+            self.copy.len(),
+            self.remove.len()
+        );
+        Ok(())
+    }
+}
+
+fn same_contents(a: &Path, b: &Path) -> Result<bool> {
+    if fs::metadata(a)?.len() != fs::metadata(b)?.len() {
+        return Ok(false);
+    }
+    Ok(fs::read(a)? == fs::read(b)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(mod_path: &str, name: &str, contents: &str) -> ModKey {
+        ModKey {
+            mod_path: PathBuf::from(mod_path),
+            source: PathBuf::from(mod_path).join("keys").join(name),
+            name: name.to_string(),
+            contents: contents.as_bytes().to_vec(),
+        }
+    }
+
+    #[test]
+    fn test_resolve_key_conflicts() {
+        let keys = vec![
+            key("mods\\@cba", "cba.bikey", "cba"),
+            key("mods\\@a", "mod.bikey", "a"),
+            key("mods\\@b", "mod.bikey", "b"),
+            key("mods\\@c", "MOD.bikey", "a"),
+        ];
+
+        // same name and contents is installed once, different contents is renamed
+        let resolved =
+            resolve_conflicts(keys.clone(), KeyConflict::Rename, &BTreeMap::new()).unwrap();
+        let names: Vec<&str> = resolved.keys.iter().map(|k| k.name.as_str()).collect();
+        assert_eq!(names, vec!["cba.bikey", "mod.bikey", "mod_@b.bikey"]);
+        assert_eq!(
+            resolved.sources["mod.bikey"],
+            vec![PathBuf::from("mods\\@a"), PathBuf::from("mods\\@c")]
+        );
+
+        // a name taken in the keys folder, or by another key, gets a number
+        let mut renamed = keys.clone();
+        renamed.push(key("mods\\@b", "mod_@b.bikey", "other"));
+        let taken = BTreeMap::from([(
+            "mod_@b_2.bikey".to_string(),
+            PathBuf::from("keys/mod_@b_2.bikey"),
+        )]);
+        let resolved = resolve_conflicts(renamed, KeyConflict::Rename, &taken).unwrap();
+        let names: Vec<&str> = resolved.keys.iter().map(|k| k.name.as_str()).collect();
+        assert_eq!(
+            names,
+            vec!["cba.bikey", "mod.bikey", "mod_@b_3.bikey", "mod_@b.bikey"]
+        );
+
+        let err = resolve_conflicts(keys, KeyConflict::Error, &BTreeMap::new()).unwrap_err();
+        assert!(err.downcast_ref::<KeyConflicts>().is_some());
+    }
+
+    fn names(keys: &[ModKey]) -> Vec<&str> {
+        keys.iter().map(|k| k.name.as_str()).collect()
+    }
+
+    fn file_names(paths: &[PathBuf]) -> Vec<String> {
+        paths
+            .iter()
+            .map(|p| p.file_name().unwrap().to_string_lossy().to_string())
+            .collect()
+    }
+
+    #[test]
+    fn test_key_sync() {
+        let root = std::env::temp_dir().join(format!("corvus_test_keys_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        let keys_folder = root.join("keys");
+        fs::create_dir_all(&keys_folder).unwrap();
+        for (name, contents) in [
+            ("cba", "cba"),
+            ("ace", "ace"),
+            ("rhs", "rhs"),
+            ("old", "new"),
+        ] {
+            let folder = root.join(format!("mods/@{}/keys", name));
+            fs::create_dir_all(&folder).unwrap();
+            fs::write(folder.join(format!("{}.bikey", name)), contents).unwrap();
+        }
+        let mod_key = |name: &str| {
+            let mod_path = PathBuf::from(format!("mods/@{}", name));
+            ModKey::new(
+                &mod_path,
+                root.join(&mod_path).join(format!("keys/{}.bikey", name)),
+            )
+            .unwrap()
+        };
+        let settings = KeySettings::default();
+
+        // keys of an older launcher or the admin, before there is a manifest
+        fs::write(keys_folder.join("a3.bikey"), "a3").unwrap();
+        fs::write(keys_folder.join("old.bikey"), "old").unwrap();
+        fs::write(keys_folder.join("cba.bikey"), "cba").unwrap();
+
+        // first run: nothing is removed, the wanted keys are recorded
+        let sync = KeySync::plan(
+            &root,
+            &keys_folder,
+            &[mod_key("cba"), mod_key("ace")],
+            &settings,
+        )
+        .unwrap();
+        assert_eq!(names(&sync.copy), vec!["ace.bikey"]);
+        assert_eq!(names(&sync.unchanged), vec!["cba.bikey"]);
+        assert!(sync.remove.is_empty());
+        assert_eq!(
+            sync.manifest.installed.keys().collect::<Vec<_>>(),
+            vec!["ace.bikey", "cba.bikey"]
+        );
+        sync.apply().unwrap();
+        assert!(keys_folder.join("ace.bikey").exists());
+        assert_eq!(KeyManifest::load(&root).unwrap(), Some(sync.manifest));
+
+        // a mod key named like one the launcher did not install is renamed, never copied over it
+        let foreign = foreign_keys(&root, &keys_folder).unwrap();
+        assert_eq!(
+            foreign.keys().collect::<Vec<_>>(),
+            vec!["a3.bikey", "old.bikey"]
+        );
+        assert!(KeySync::plan(&root, &keys_folder, &[mod_key("old")], &settings).is_err());
+        let err =
+            resolve_conflicts(vec![mod_key("old")], KeyConflict::Error, &foreign).unwrap_err();
+        assert!(err.downcast_ref::<KeyConflicts>().is_some());
+        let resolved =
+            resolve_conflicts(vec![mod_key("old")], KeyConflict::Rename, &foreign).unwrap();
+        assert_eq!(names(&resolved.keys), vec!["old_@old.bikey"]);
+        assert_eq!(
+            resolved.warnings,
+            vec![
+                "old.bikey from mods/@old differs from the keys folder, installed as old_@old.bikey"
+            ]
+        );
+        let sync = KeySync::plan(&root, &keys_folder, &resolved.keys, &settings).unwrap();
+        assert_eq!(names(&sync.copy), vec!["old_@old.bikey"]);
+        assert!(!file_names(&sync.remove).contains(&"old.bikey".to_string()));
+
+        // an admin places a key that a mod also ships
+        fs::write(keys_folder.join("rhs.bikey"), "rhs").unwrap();
+        // and a mod updates its key
+        fs::write(root.join("mods/@ace/keys/ace.bikey"), "ace 2").unwrap();
+
+        let sync = KeySync::plan(
+            &root,
+            &keys_folder,
+            &[mod_key("ace"), mod_key("rhs")],
+            &settings,
+        )
+        .unwrap();
+        assert_eq!(names(&sync.copy), vec!["ace.bikey"]);
+        assert_eq!(names(&sync.unchanged), vec!["rhs.bikey"]);
+        assert_eq!(file_names(&sync.remove), vec!["cba.bikey"]);
+        // the admin's key stays theirs
+        assert!(!sync.manifest.installed.contains_key("rhs.bikey"));
+        sync.apply().unwrap();
+        assert_eq!(fs::read(keys_folder.join("ace.bikey")).unwrap(), b"ace 2");
+        assert!(!keys_folder.join("cba.bikey").exists());
+
+        // keys in always_keep are not removed, even when the launcher installed them
+        let mut manifest = KeyManifest::load(&root).unwrap().unwrap();
+        manifest
+            .installed
+            .insert("a3.bikey".to_string(), keys_folder.join("a3.bikey"));
+        manifest.save(&root).unwrap();
+
+        let sync = KeySync::plan(&root, &keys_folder, &[], &settings).unwrap();
+        assert!(sync.copy.is_empty());
+        assert_eq!(file_names(&sync.remove), vec!["ace.bikey"]);
+        sync.apply().unwrap();
+
+        let mut left: Vec<String> = fs::read_dir(&keys_folder)
+            .unwrap()
+            .map(|e| e.unwrap().file_name().to_string_lossy().to_string())
+            .collect();
+        left.sort();
+        assert_eq!(left, vec!["a3.bikey", "old.bikey", "rhs.bikey"]);
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -0,0 +1,407 @@
+//! Everything a server launch is going to do, worked out before anything is changed on disk.
+//! Lets admins review the command line and key changes before committing to them
+use crate::arma::dependencies::missing_dependencies;
+use crate::arma::keys::{KeySync, ModKey, foreign_keys, resolve_conflicts};
+use crate::arma::ports::{PortsInUse, check_port_block, parse_port, ports_in_use};
+use crate::arma::server_config::ServerConfig;
+use crate::arma::server_launch::{
+    MissingBikeys, ServerLaunch, find_basic_config, find_bikey, find_config, mods_launch_string,
+    par_file, pretty_cmd,
+};
+use anyhow::Result;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+use std::process::{Child, Command, Stdio};
+
+#[derive(Clone, Debug)]
+pub struct LaunchPlan {
+    pub executable: PathBuf,
+    pub args: Vec<String>,
+    /// game port, the server uses the block of ports from here
+    pub port: u16,
+    /// parameter file with the -mod= line
+    pub par_file: PathBuf,
+    pub par_contents: String,
+    /// server.cfg passed with -config
+    pub config_file: PathBuf,
+    /// `None` if the launcher could not read it, the server may still be able to
+    pub server_config: Option<ServerConfig>,
+    pub keys_folder: PathBuf,
+    /// changes to the keys folder
+    pub keys: KeySync,
+    /// mods that contributed each key, by filename in the keys folder
+    pub key_sources: BTreeMap<String, Vec<PathBuf>>,
+    /// things that will not stop the launch, but the admin should know about
+    pub warnings: Vec<String>,
+}
+
+impl LaunchPlan {
+    /// Work out what launching the server will do. `reserved` are the game ports of the running
+    /// servers of the launcher. Fails if the port is invalid or any of the mods is missing its bikey
+    pub fn build(launch: &ServerLaunch, reserved: &[u16]) -> Result<LaunchPlan> {
+        let a3root = &launch.a3_root;
+        let keys_folder = a3root.join("keys");
+        let par_file = par_file(a3root, launch.instance.as_deref());
+        let mut warnings: Vec<String> = Vec::new();
+
+        // the ports are checked again right before launching, they might be freed until then
+        let port = parse_port(&launch.port)?;
+        let used = ports_in_use(port, reserved);
+        if !used.is_empty() {
+            warnings.push(PortsInUse { port, used }.to_string());
+        }
+
+        if launch.mods.is_empty() {
+            warnings.push("No modpack selected, server will run without mods".to_string());
+        }
+
+        // find list of bikeys for all mods and clientsides
+        let mut mod_keys: Vec<ModKey> = Vec::new();
+        let mut missing_keys: Vec<String> = Vec::new();
+        for modpath in launch.mods.iter().chain(launch.clientsides.iter()) {
+            // make absolute path from relative
+            match find_bikey(&a3root.join(modpath), &launch.key_settings) {
+                Ok(keys) => {
+                    for key in keys {
+                        mod_keys.push(ModKey::new(modpath, key)?);
+                    }
+                }
+                Err(e) => missing_keys.push(e.to_string()),
+            }
+        }
+
+        // if err vec is not empty, we halt the launch and displays errors to users instead of missing bikeys
+        if !missing_keys.is_empty() {
+            for e in &missing_keys {
+                log::error!("{}", e);
+            }
+            return Err(MissingBikeys(missing_keys).into());
+        }
+
+        // two mods shipping different keys with the same filename would overwrite each other
+        let resolved = resolve_conflicts(
+            mod_keys,
+            launch.key_settings.on_conflict,
+            &foreign_keys(a3root, &keys_folder)?,
+        )?;
+        warnings.extend(resolved.warnings);
+
+        // clientsides are not loaded by the server, so they can not provide addons
+        let loaded: Vec<PathBuf> = launch
+            .mods
+            .iter()
+            .chain(launch.server_mods.iter())
+            .cloned()
+            .collect();
+        warnings.extend(missing_dependencies(a3root, &loaded));
+
+        let keys = KeySync::plan(a3root, &keys_folder, &resolved.keys, &launch.key_settings)?;
+
+        // build string for server mods
+        let server_mod_string_vec: Vec<String> = launch
+            .server_mods
+            .iter()
+            .map(|entry| String::from(entry.to_string_lossy()))
+            .collect();
+
+        let profile_folder = a3root.join(&launch.profile);
+        if !profile_folder.exists() {
+            warnings.push(format!(
+                "Profile folder {} does not exist, the server will create it",
+                profile_folder.to_string_lossy()
+            ));
+        }
+
+        // a config the server cannot read would only show up in its log, so check it here. The
+        // parser does not know the preprocessor, so configs using it are launched anyway
+        let config_file = find_config(a3root, launch.server_config.as_deref())?;
+        let server_config = match ServerConfig::load(&config_file) {
+            Ok(server_config) => Some(server_config),
+            Err(err) => {
+                warnings.push(err.to_string());
+                None
+            }
+        };
+
+        let mut args = vec![format!("-port={}", port)];
+        args.extend(launch.parameters.to_args());
+        args.extend(["-name=server".to_string(), "-world=empty".to_string()]);
+        args.push(format!("-profiles={}", profile_folder.to_string_lossy()));
+        args.push(format!("-config={}", config_file.to_string_lossy()));
+        let basic_config =
+            find_basic_config(a3root, &launch.profile, launch.basic_config.as_deref())?;
+        args.push(format!("-cfg={}", basic_config.to_string_lossy()));
+        args.push(format!("-serverMod={}", server_mod_string_vec.join(";")));
+        args.push(format!("-par={}", par_file.to_string_lossy()));
+
+        Ok(LaunchPlan {
+            executable: launch.a3_executable.clone(),
+            args,
+            port,
+            par_contents: mods_launch_string(&launch.mods),
+            par_file,
+            config_file,
+            server_config,
+            keys_folder,
+            keys,
+            key_sources: resolved.sources,
+            warnings,
+        })
+    }
+
+    /// The command that launches the server
+    pub fn command(&self) -> Command {
+        let mut cmd = Command::new(&self.executable);
+        cmd.args(&self.args);
+        cmd
+    }
+
+    /// The command line as it would be typed in a terminal
+    pub fn command_line(&self) -> String {
+        std::iter::once(self.executable.to_string_lossy().to_string())
+            .chain(self.args.iter().cloned())
+            .map(|arg| match arg.contains(' ') {
+                true => format!("\"{}\"", arg),
+                false => arg,
+            })
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    /// Hostname, player slots and missions from the server config
+    pub fn config_summary(&self) -> String {
+        let Some(config) = &self.server_config else {
+            return "could not be read".to_string();
+        };
+        let missions = config.missions();
+        format!(
+            "hostname: {}, max players: {}, missions: {}",
+            config.hostname().unwrap_or("not set"),
+            config
+                .max_players()
+                .map_or("not set".to_string(), |n| n.to_string()),
+            match missions.is_empty() {
+                true => "none".to_string(),
+                false => missions.join(", "),
+            }
+        )
+    }
+
+    /// Update keys folder and parameter file, and launch the server. `reserved` are the game ports
+    /// of the running servers of the launcher
+    pub fn execute(&self, reserved: &[u16]) -> Result<Child> {
+        // a server on taken ports starts, but nobody can join it
+        check_port_block(self.port, reserved)?;
+
+        // no keys are missing, we can continue by updating the a3root/keys folder
+        self.keys.apply()?;
+
+        // save parameter file for server mods
+        fs::write(&self.par_file, &self.par_contents)?;
+        log::info!("Build server modlist and saved to parameter file");
+        log::debug!(
+            "Build server modlist successfully and saved to: {:?}",
+            self.par_file
+        );
+
+        // launch server and null stdin, out and error, to fork and disown process. We should be able to close launcher without killing server
+        let mut launch_cmd = self.command();
+        log::debug!("launch server: {}", pretty_cmd(&launch_cmd));
+
+        let child = launch_cmd
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()?;
+
+        Ok(child)
+    }
+}
+
+impl std::fmt::Display for LaunchPlan {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "Command:\n  {}", self.command_line())?;
+        writeln!(
+            f,
+            "Parameter file {}:\n  {}",
+            self.par_file.to_string_lossy(),
+            self.par_contents
+        )?;
+        writeln!(
+            f,
+            "Server config {}:\n  {}",
+            self.config_file.to_string_lossy(),
+            self.config_summary()
+        )?;
+
+        writeln!(
+            f,
+            "Keys to remove from {}:",
+            self.keys_folder.to_string_lossy()
+        )?;
+        for key in &self.keys.remove {
+            writeln!(f, "  {}", key.to_string_lossy())?;
+        }
+
+        writeln!(f, "Keys to copy:")?;
+        for key in &self.keys.copy {
+            writeln!(f, "  {} -> {}", key.source.to_string_lossy(), key.name)?;
+        }
+        writeln!(f, "Keys already in place: {}", self.keys.unchanged.len())?;
+
+        writeln!(f, "Key sources:")?;
+        for (name, mods) in &self.key_sources {
+            writeln!(f, "  {}: {}", name, mod_list(mods))?;
+        }
+
+        if !self.warnings.is_empty() {
+            writeln!(f, "Warnings:")?;
+            for warning in &self.warnings {
+                writeln!(f, "  {}", warning)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// comma separated list of mods
+pub fn mod_list(mods: &[PathBuf]) -> String {
+    mods.iter()
+        .map(|m| m.to_string_lossy().to_string())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::arma::server_launch::LOADED_MODS_FILE;
+    use std::path::Path;
+
+    fn a3root(name: &str) -> PathBuf {
+        let root =
+            std::env::temp_dir().join(format!("corvus_test_{}_{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(root.join("keys")).unwrap();
+        fs::create_dir_all(root.join("mods/@cba/keys")).unwrap();
+        fs::write(root.join("mods/@cba/keys/cba.bikey"), "cba").unwrap();
+        fs::create_dir_all(root.join("mods/@nokey/addons")).unwrap();
+        fs::write(
+            root.join("server.cfg"),
+            "hostname = \"Test Server\";\nmaxPlayers = 40;\npassword = \"secret\";\n",
+        )
+        .unwrap();
+        root
+    }
+
+    fn launch(root: &Path, mods: &[&str]) -> ServerLaunch {
+        ServerLaunch::builder()
+            .a3_root(root.to_path_buf())
+            .a3_executable(root.join("arma3server_x64"))
+            .port("42302".to_string())
+            .profile("ServerNormal".to_string())
+            .mods(mods.iter().map(PathBuf::from).collect())
+            .build()
+    }
+
+    #[test]
+    fn test_build() {
+        let root = a3root("plan_build");
+        let plan = LaunchPlan::build(&launch(&root, &["mods/@cba"]), &[]).unwrap();
+
+        assert_eq!(plan.port, 42302);
+        assert_eq!(plan.args[0], "-port=42302");
+        assert!(plan.args.contains(&format!(
+            "-config={}",
+            root.join("server.cfg").to_string_lossy()
+        )));
+        assert!(plan.args.contains(&format!(
+            "-par={}",
+            root.join(LOADED_MODS_FILE).to_string_lossy()
+        )));
+        assert_eq!(plan.par_contents, "-mod=mods/@cba;");
+        assert_eq!(
+            plan.server_config.as_ref().unwrap().hostname(),
+            Some("Test Server")
+        );
+        assert_eq!(plan.keys.copy.len(), 1);
+        assert_eq!(plan.keys.copy[0].name, "cba.bikey");
+        assert_eq!(
+            plan.key_sources.get("cba.bikey"),
+            Some(&vec![PathBuf::from("mods/@cba")])
+        );
+        assert!(
+            plan.warnings
+                .iter()
+                .any(|w| w.starts_with("Profile folder"))
+        );
+
+        // nothing is changed on disk by planning
+        assert!(!root.join("keys/cba.bikey").exists());
+        assert!(!root.join(LOADED_MODS_FILE).exists());
+
+        let err =
+            LaunchPlan::build(&launch(&root, &["mods/@cba", "mods/@nokey"]), &[]).unwrap_err();
+        assert!(err.downcast_ref::<MissingBikeys>().is_some());
+
+        // a config the launcher can not parse is left to the server
+        fs::write(root.join("server.cfg"), "#include \"common.cfg\"\n").unwrap();
+        let plan = LaunchPlan::build(&launch(&root, &["mods/@cba"]), &[]).unwrap();
+        assert!(plan.server_config.is_none());
+        assert_eq!(plan.config_summary(), "could not be read");
+        assert!(
+            plan.warnings
+                .iter()
+                .any(|w| w.contains("preprocessor directives are not supported"))
+        );
+
+        let err = LaunchPlan::build(
+            &ServerLaunch {
+                port: "80".to_string(),
+                ..launch(&root, &["mods/@cba"])
+            },
+            &[],
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("out of range"));
+
+        // servers of the launcher hold their ports, even before they bind them
+        let plan = LaunchPlan::build(&launch(&root, &["mods/@cba"]), &[42300]).unwrap();
+        assert!(plan.warnings.iter().any(|w| w.starts_with(
+            "Ports 42302-42306 are needed by the server, but these are in use: 42302, 42303, 42304"
+        )));
+        let err = plan.execute(&[42300]).unwrap_err();
+        assert!(err.downcast_ref::<PortsInUse>().is_some());
+        assert!(!root.join("keys/cba.bikey").exists());
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_dry_run_output() {
+        let root = a3root("plan_output");
+        let plan = LaunchPlan::build(&launch(&root, &["mods/@cba"]), &[]).unwrap();
+        let output = plan.to_string();
+
+        assert!(output.starts_with(&format!(
+            "Command:\n  {} -port=42302 ",
+            root.join("arma3server_x64").to_string_lossy()
+        )));
+        assert!(output.contains(&format!(
+            "Parameter file {}:\n  -mod=mods/@cba;\n",
+            root.join(LOADED_MODS_FILE).to_string_lossy()
+        )));
+        assert!(output.contains("  hostname: Test Server, max players: 40, missions: none\n"));
+        assert!(output.contains(&format!(
+            "Keys to remove from {}:\nKeys to copy:\n  {} -> cba.bikey\nKeys already in place: 0\n",
+            root.join("keys").to_string_lossy(),
+            root.join("mods/@cba/keys/cba.bikey").to_string_lossy()
+        )));
+        assert!(output.contains("Key sources:\n  cba.bikey: mods/@cba\n"));
+        assert!(output.contains("Warnings:\n  Profile folder "));
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -0,0 +1,161 @@
+//! Puts the combined mods of the selected modlists in the order the load order rules ask for,
+//! before they are written to the parameter file
+use crate::arma::mod_conflicts::SelectedMods;
+use crate::configs::load_order::LoadOrderSettings;
+use anyhow::Result;
+use glob::{MatchOptions, Pattern};
+use std::path::{Path, PathBuf};
+
+/// Order the mods by the rules. Mods no rule is about keep the order of the modlists. Returns the
+/// ordered mods, with a warning if the rules contradict each other
+pub fn order_mods(
+    settings: &LoadOrderSettings,
+    mut mods: Vec<PathBuf>,
+) -> Result<(Vec<PathBuf>, Vec<String>)> {
+    let first = patterns(&settings.first)?;
+    let last = patterns(&settings.last)?;
+
+    // first and last mods in the order of their patterns, the rest in between
+    mods.sort_by_key(|m| {
+        match (
+            first.iter().position(|p| matches(p, m)),
+            last.iter().position(|p| matches(p, m)),
+        ) {
+            (Some(i), _) => (0, i),
+            (None, Some(i)) => (2, i),
+            (None, None) => (1, 0),
+        }
+    });
+
+    let rules = settings
+        .after
+        .iter()
+        .map(|rule| Ok((pattern(&rule.mods)?, patterns(&rule.after)?)))
+        .collect::<Result<Vec<_>>>()?;
+
+    // move each mod behind the last of the mods it has to load after, until nothing moves. Rules
+    // that contradict each other would move mods forever
+    for _ in 0..=mods.len() {
+        let mut moved = false;
+        for (rule, parents) in &rules {
+            for i in 0..mods.len() {
+                if !matches(rule, &mods[i]) {
+                    continue;
+                }
+                // the mods of a rule can match its parents too, like `@ace_compat*` and `@ace*`
+                let last_parent = mods
+                    .iter()
+                    .rposition(|m| !matches(rule, m) && parents.iter().any(|p| matches(p, m)));
+                if let Some(parent) = last_parent.filter(|parent| *parent > i) {
+                    let entry = mods.remove(i);
+                    mods.insert(parent, entry);
+                    moved = true;
+                    break;
+                }
+            }
+        }
+        if !moved {
+            return Ok((mods, Vec::new()));
+        }
+    }
+
+    Ok((
+        mods,
+        vec![
+            "Load order rules contradict each other, check the after rules in the config"
+                .to_string(),
+        ],
+    ))
+}
+
+/// Order the mods and server mods of the selection, see [`order_mods`]. Clientsides only allow
+/// keys, so their order does not matter
+pub fn order_selected(
+    settings: &LoadOrderSettings,
+    selected: SelectedMods,
+) -> Result<SelectedMods> {
+    let (mods, mut warnings) = order_mods(settings, selected.mods)?;
+    let (server_mods, server_warnings) = order_mods(settings, selected.server_mods)?;
+    warnings.extend(server_warnings);
+    warnings.dedup();
+
+    Ok(SelectedMods {
+        mods,
+        server_mods,
+        warnings: [selected.warnings, warnings].concat(),
+        ..selected
+    })
+}
+
+fn pattern(pattern: &str) -> Result<Pattern> {
+    Pattern::new(pattern).map_err(|err| {
+        anyhow::Error::msg(format!("Invalid load order pattern {}: {}", pattern, err))
+    })
+}
+
+fn patterns(patterns: &[String]) -> Result<Vec<Pattern>> {
+    patterns.iter().map(|p| pattern(p)).collect()
+}
+
+/// Match a pattern against the folder name of a mod
+fn matches(pattern: &Pattern, mod_path: &Path) -> bool {
+    let options = MatchOptions {
+        case_sensitive: false,
+        ..Default::default()
+    };
+    mod_path
+        .file_name()
+        .is_some_and(|name| pattern.matches_with(&name.to_string_lossy(), options))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::configs::load_order::AfterRule;
+
+    #[test]
+    fn test_order_mods() {
+        let mut settings = LoadOrderSettings {
+            last: vec!["@zeus*".to_string()],
+            after: vec![AfterRule {
+                mods: "@ace_compat*".to_string(),
+                after: vec!["@ace*".to_string(), "@rhs*".to_string()],
+            }],
+            ..Default::default()
+        };
+        let mods: Vec<PathBuf> = [
+            "mods/@ace_compat_rhs",
+            "mods/@zeus_enhanced",
+            "mods/@rhs",
+            "mods/@ace",
+            "mods/@CBA_A3",
+        ]
+        .iter()
+        .map(PathBuf::from)
+        .collect();
+
+        let (ordered, warnings) = order_mods(&settings, mods.clone()).unwrap();
+        assert!(warnings.is_empty());
+        assert_eq!(
+            ordered,
+            [
+                "mods/@CBA_A3",
+                "mods/@rhs",
+                "mods/@ace",
+                "mods/@ace_compat_rhs",
+                "mods/@zeus_enhanced",
+            ]
+            .iter()
+            .map(PathBuf::from)
+            .collect::<Vec<_>>()
+        );
+
+        // rules that can not all hold are reported
+        settings.after.push(AfterRule {
+            mods: "@rhs".to_string(),
+            after: vec!["@ace_compat*".to_string()],
+        });
+        let (_, warnings) = order_mods(&settings, mods).unwrap();
+        assert_eq!(warnings.len(), 1);
+    }
+}
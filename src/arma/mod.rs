@@ -1,2 +1,24 @@
+pub mod addon_config;
+pub mod dependencies;
+pub mod instances;
+pub mod keys;
+pub mod launch_plan;
+pub mod load_order;
+pub mod mod_conflicts;
+pub mod mod_meta;
+pub mod mod_paths;
+pub mod mod_size;
+pub mod paa;
+pub mod pbo;
+pub mod ports;
+pub mod preset;
+pub mod processes;
+pub mod readiness;
+pub mod rpt;
+pub mod scheduler;
+pub mod selection;
+pub mod server_config;
 pub mod server_launch;
 pub mod server_modlist;
+pub mod validation;
+pub mod watchdog;
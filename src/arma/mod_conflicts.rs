@@ -0,0 +1,194 @@
+//! Combines the mods of the selected modlists so each mod is loaded once, and halts on mods that
+//! can not be loaded together
+use crate::ServerModList;
+use crate::arma::mod_paths::resolve_mod_path;
+use crate::configs::conflicts::ConflictRule;
+use anyhow::Result;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+/// The mods of the selected modlists, each mod once, with the paths as they are on disk
+#[derive(Debug, Default, PartialEq)]
+pub struct SelectedMods {
+    pub mods: Vec<PathBuf>,
+    pub clientsides: Vec<PathBuf>,
+    pub server_mods: Vec<PathBuf>,
+    /// mods that were in several lists and are only loaded once
+    pub warnings: Vec<String>,
+}
+
+/// Combine the selected modpacks, clientsides and server mods. A mod in several lists of the same
+/// kind is kept once, and a mod that is also in a modpack is only loaded as a modpack mod
+pub fn combine_mods(
+    a3root: &Path,
+    modpacks: &[ServerModList],
+    clientsides: &[ServerModList],
+    servermods: &[ServerModList],
+) -> SelectedMods {
+    let mut warnings = Vec::new();
+    let no_lists = HashMap::new();
+
+    let (mods, in_modpacks) = add_lists(a3root, modpacks, &no_lists, "modpack", &mut warnings);
+    let (server_mods, _) = add_lists(
+        a3root,
+        servermods,
+        &in_modpacks,
+        "server mods",
+        &mut warnings,
+    );
+    let (clientsides, _) = add_lists(
+        a3root,
+        clientsides,
+        &in_modpacks,
+        "clientside",
+        &mut warnings,
+    );
+
+    for warning in &warnings {
+        log::warn!("{}", warning);
+    }
+    SelectedMods {
+        mods,
+        clientsides,
+        server_mods,
+        warnings,
+    }
+}
+
+/// The mods of the selected lists, skipping the ones already added or already in a modpack.
+/// Returns the mods with the list each one came from, by lowercase path
+fn add_lists<'a>(
+    a3root: &Path,
+    lists: &'a [ServerModList],
+    in_modpacks: &HashMap<String, &'a str>,
+    kind: &str,
+    warnings: &mut Vec<String>,
+) -> (Vec<PathBuf>, HashMap<String, &'a str>) {
+    let mut mods = Vec::new();
+    let mut added: HashMap<String, &str> = HashMap::new();
+
+    for list in lists.iter().filter(|l| l.selected) {
+        for mod_path in &list.mods {
+            let mod_path = resolve_mod_path(a3root, mod_path);
+            let key = mod_path.to_string_lossy().replace('\\', "/").to_lowercase();
+            let shown = mod_path.to_string_lossy();
+
+            if let Some(first) = added.get(&key) {
+                warnings.push(match *first == list.name {
+                    true => format!("{} is twice in {}, loading it once", shown, list.name),
+                    false => format!(
+                        "{} is in {} and {}, loading it once",
+                        shown, first, list.name
+                    ),
+                });
+                continue;
+            }
+            if let Some(modpack) = in_modpacks.get(&key) {
+                warnings.push(format!(
+                    "{} is in modpack {} and {} {}, only loading it as a modpack mod",
+                    shown, modpack, kind, list.name
+                ));
+                continue;
+            }
+
+            added.insert(key, &list.name);
+            mods.push(mod_path);
+        }
+    }
+    (mods, added)
+}
+
+/// Launch was halted because mods that can not be loaded together are selected
+#[derive(Debug)]
+pub struct ModConflicts(pub Vec<String>);
+
+impl std::fmt::Display for ModConflicts {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0.join("\n"))
+    }
+}
+
+impl std::error::Error for ModConflicts {}
+
+/// Fail with [`ModConflicts`] if the selected mods match two entries of a conflict rule
+pub fn check_conflicts(rules: &[ConflictRule], selected: &SelectedMods) -> Result<()> {
+    let folders: Vec<String> = selected
+        .mods
+        .iter()
+        .chain(selected.clientsides.iter())
+        .chain(selected.server_mods.iter())
+        .filter_map(|m| m.file_name())
+        .map(|f| f.to_string_lossy().to_string())
+        .collect();
+    let folders: Vec<&str> = folders.iter().map(String::as_str).collect();
+
+    let mut conflicts = Vec::new();
+    for rule in rules {
+        let matches = rule.matches(&folders)?;
+        if matches.len() > 1 {
+            let found: Vec<String> = matches
+                .iter()
+                .map(|(_, folders)| folders.join(", "))
+                .collect();
+            conflicts.push(format!(
+                "{}: {} can not be loaded together",
+                rule.name,
+                found.join(" and ")
+            ));
+        }
+    }
+
+    match conflicts.is_empty() {
+        true => Ok(()),
+        false => Err(ModConflicts(conflicts).into()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::configs::conflicts::default_conflicts;
+
+    fn list(name: &str, mods: &[&str]) -> ServerModList {
+        ServerModList {
+            name: name.to_string(),
+            path: PathBuf::from(format!("{}.txt", name)),
+            selected: true,
+            mods: mods.iter().map(PathBuf::from).collect(),
+            unresolved: Vec::new(),
+            size: None,
+        }
+    }
+
+    #[test]
+    fn test_combine_mods() {
+        let a3root = Path::new("/nonexistent/a3");
+        let modpacks = [
+            list("basic", &["mods/@cba", "mods/@ace"]),
+            list("extra", &["mods/@CBA", "mods/@rhs"]),
+        ];
+        let servermods = [list("ocap", &["mods/@ocap", "mods/@ace"])];
+        let clientsides = [list("radio", &["mods/@task_force_radio"])];
+
+        let selected = combine_mods(a3root, &modpacks, &clientsides, &servermods);
+        assert_eq!(
+            selected.mods,
+            vec![
+                PathBuf::from("mods/@cba"),
+                PathBuf::from("mods/@ace"),
+                PathBuf::from("mods/@rhs")
+            ]
+        );
+        assert_eq!(selected.server_mods, vec![PathBuf::from("mods/@ocap")]);
+        assert_eq!(selected.warnings.len(), 2);
+        assert!(check_conflicts(&default_conflicts(), &selected).is_ok());
+
+        let modpacks = [list("acre", &["mods/@ACRE2"])];
+        let selected = combine_mods(a3root, &modpacks, &clientsides, &servermods);
+        let err = check_conflicts(&default_conflicts(), &selected).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Radio mods: @task_force_radio and @ACRE2 can not be loaded together"
+        );
+    }
+}
@@ -0,0 +1,240 @@
+//! Reads the `meta.cpp` the Steam workshop writes into each downloaded mod, and the `mod.cpp` mod
+//! authors ship with their name, logo and version
+use crate::arma::mod_paths::{resolve_case_insensitive, resolve_mod_path};
+use crate::arma::paa::load_logo;
+use chrono::{DateTime, Utc};
+use image::RgbaImage;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Seconds between 0001-01-01, where .NET ticks start, and the unix epoch
+const TICKS_EPOCH_OFFSET: i64 = 62_135_596_800;
+/// The top two bits of a serialized .NET date hold its time zone kind, the rest are ticks
+const TICKS_MASK: u64 = 0x3FFF_FFFF_FFFF_FFFF;
+
+/// What the workshop knows about a mod
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ModMeta {
+    /// Steam workshop id, 0 or missing for mods that are not from the workshop
+    pub published_id: Option<u64>,
+    pub name: Option<String>,
+    /// last update on the workshop, as a serialized .NET date
+    pub timestamp: Option<u64>,
+}
+
+impl ModMeta {
+    /// Read `meta.cpp` in the mod folder, `None` if there is none
+    pub fn load(mod_folder: &Path) -> Option<ModMeta> {
+        let raw = fs::read_to_string(mod_folder.join("meta.cpp")).ok()?;
+        Some(ModMeta::parse(&raw))
+    }
+
+    pub fn parse(raw: &str) -> ModMeta {
+        let fields = parse_fields(raw);
+        ModMeta {
+            published_id: fields
+                .get("publishedid")
+                .and_then(|id| id.parse::<u64>().ok())
+                .filter(|id| *id != 0),
+            name: fields.get("name").cloned(),
+            timestamp: fields
+                .get("timestamp")
+                .and_then(|ticks| ticks.parse::<u64>().ok())
+                .filter(|ticks| *ticks != 0),
+        }
+    }
+
+    /// When the mod was last updated on the workshop
+    pub fn updated(&self) -> Option<DateTime<Utc>> {
+        let seconds = ((self.timestamp? & TICKS_MASK) / 10_000_000) as i64 - TICKS_EPOCH_OFFSET;
+        DateTime::from_timestamp(seconds, 0)
+    }
+}
+
+/// What the mod says about itself in its `mod.cpp`
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ModCpp {
+    pub name: Option<String>,
+    pub author: Option<String>,
+    /// path of the logo, relative to the mod folder
+    pub logo: Option<String>,
+    pub version: Option<String>,
+}
+
+impl ModCpp {
+    /// Read `mod.cpp` in the mod folder, `None` if there is none
+    pub fn load(mod_folder: &Path) -> Option<ModCpp> {
+        let path = resolve_case_insensitive(mod_folder, Path::new("mod.cpp"))?;
+        let raw = fs::read_to_string(mod_folder.join(path)).ok()?;
+        Some(ModCpp::parse(&raw))
+    }
+
+    pub fn parse(raw: &str) -> ModCpp {
+        let mut fields = parse_fields(raw);
+        let mut take = |key: &str| fields.remove(key).filter(|v| !v.is_empty());
+        ModCpp {
+            name: take("name"),
+            author: take("author"),
+            logo: take("logo")
+                .or_else(|| take("logosmall"))
+                .or_else(|| take("picture")),
+            version: take("version"),
+        }
+    }
+}
+
+/// Everything shown about a mod in the modlist details
+#[derive(Debug, Clone)]
+pub struct ModDetails {
+    /// as written in the modlist
+    pub path: PathBuf,
+    /// folder was found in the A3 root
+    pub installed: bool,
+    pub cpp: Option<ModCpp>,
+    pub meta: Option<ModMeta>,
+    pub logo: Option<RgbaImage>,
+}
+
+impl ModDetails {
+    /// Read the `mod.cpp`, `meta.cpp` and logo of a mod. Mod paths are relative to the A3 root
+    pub fn load(a3root: &Path, mod_path: &Path) -> ModDetails {
+        let folder = a3root.join(resolve_mod_path(a3root, mod_path));
+        let cpp = ModCpp::load(&folder);
+
+        // logos inside a pbo are not read
+        let logo = cpp
+            .as_ref()
+            .and_then(|cpp| cpp.logo.as_deref())
+            .map(|logo| PathBuf::from(logo.replace('\\', "/").trim_start_matches('/')))
+            .and_then(|logo| resolve_case_insensitive(&folder, &logo))
+            .and_then(|logo| match load_logo(&folder.join(&logo)) {
+                Ok(image) => Some(image),
+                Err(err) => {
+                    log::debug!("Failed to read logo {:?}: {}", logo, err);
+                    None
+                }
+            });
+
+        ModDetails {
+            path: mod_path.to_path_buf(),
+            installed: folder.is_dir(),
+            meta: ModMeta::load(&folder),
+            cpp,
+            logo,
+        }
+    }
+
+    /// Name from `mod.cpp`, then `meta.cpp`, then the folder name
+    pub fn name(&self) -> String {
+        self.cpp
+            .as_ref()
+            .and_then(|cpp| cpp.name.clone())
+            .or_else(|| self.meta.as_ref().and_then(|meta| meta.name.clone()))
+            .unwrap_or_else(|| {
+                self.path
+                    .file_name()
+                    .map(|f| f.to_string_lossy().to_string())
+                    .unwrap_or_default()
+            })
+    }
+
+    /// Author, version, workshop id and last update, whichever are known
+    pub fn summary(&self) -> String {
+        let cpp = self.cpp.clone().unwrap_or_default();
+        let meta = self.meta.clone().unwrap_or_default();
+
+        let mut parts = Vec::new();
+        if let Some(author) = cpp.author {
+            parts.push(format!("by {}", author));
+        }
+        if let Some(version) = cpp.version {
+            parts.push(format!("version {}", version));
+        }
+        match meta.published_id {
+            Some(id) => parts.push(format!("workshop id {}", id)),
+            None => parts.push("local mod".to_string()),
+        }
+        if let Some(updated) = meta.updated() {
+            parts.push(format!("updated {}", updated.format("%Y-%m-%d")));
+        }
+        parts.join(" · ")
+    }
+}
+
+/// Parse the `key = value;` lines of a mod.cpp or meta.cpp. Keys are lowercase, quotes are removed
+/// from values
+pub fn parse_fields(raw: &str) -> HashMap<String, String> {
+    statements(raw.trim_start_matches('\u{feff}'))
+        .into_iter()
+        .filter_map(|statement| {
+            let (key, value) = statement.split_once('=')?;
+            let value = value.trim();
+            let value = value
+                .strip_prefix('"')
+                .and_then(|v| v.strip_suffix('"'))
+                .map(|v| v.replace("\"\"", "\""))
+                .unwrap_or(value.to_string());
+            Some((key.trim().to_lowercase(), value))
+        })
+        .collect()
+}
+
+/// Split at the `;` that are not inside quotes, skipping `//` comments
+fn statements(raw: &str) -> Vec<String> {
+    let mut statements = Vec::new();
+    let mut current = String::new();
+    let mut in_quotes = false;
+    let mut chars = raw.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            // a doubled quote inside a string toggles twice, so it stays inside
+            '"' => {
+                in_quotes = !in_quotes;
+                current.push(c);
+            }
+            ';' if !in_quotes => statements.push(std::mem::take(&mut current)),
+            '/' if !in_quotes && chars.peek() == Some(&'/') => {
+                for c in chars.by_ref() {
+                    if c == '\n' {
+                        break;
+                    }
+                }
+            }
+            _ => current.push(c),
+        }
+    }
+    statements.push(current);
+    statements
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_mod_cpp() {
+        let cpp = ModCpp::parse(
+            "name = \"Advanced Combat Environment 3\";\n\
+             // shown in the launcher\n\
+             picture = \"title_co.paa\";\n\
+             logo = \"logo_ace3_ca.paa\";\n\
+             author = \"ACE-Team\";\n\
+             overview = \"Semicolons; and \"\"quotes\"\" in here\";\n",
+        );
+        assert_eq!(cpp.name.as_deref(), Some("Advanced Combat Environment 3"));
+        assert_eq!(cpp.author.as_deref(), Some("ACE-Team"));
+        assert_eq!(cpp.logo.as_deref(), Some("logo_ace3_ca.paa"));
+        assert_eq!(cpp.version, None);
+
+        let meta = ModMeta::parse(
+            "protocol = 1;\npublishedid = 463939057;\nname = \"ace\";\ntimestamp = 5250321407190773952;",
+        );
+        assert_eq!(meta.published_id, Some(463939057));
+        assert_eq!(
+            meta.updated().unwrap().format("%Y-%m-%d").to_string(),
+            "2024-10-03"
+        );
+    }
+}
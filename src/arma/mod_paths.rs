@@ -0,0 +1,207 @@
+//! Mod paths from modlists are often written on Windows, with `\` separators and any casing.
+//! Linux servers need them with `/`, matching the casing on disk, and ideally all lowercase
+use anyhow::Result;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+
+/// Turn a mod path from a modlist into a path for this platform, whichever separator it uses
+pub fn normalize_separators(entry: &str) -> PathBuf {
+    let mut path = PathBuf::new();
+    if entry.starts_with(['/', '\\']) {
+        path.push(std::path::MAIN_SEPARATOR_STR);
+    }
+    for part in entry.split(['/', '\\']) {
+        match part {
+            "" | "." => {}
+            // a drive like `C:` has to be followed by a separator to be absolute
+            drive if path.as_os_str().is_empty() && drive.len() == 2 && drive.ends_with(':') => {
+                path.push(format!("{}{}", drive, std::path::MAIN_SEPARATOR))
+            }
+            part => path.push(part),
+        }
+    }
+    path
+}
+
+/// Find the path on disk for `path` relative to `base`, ignoring the casing of each part. The
+/// exact path is used if it exists. Returns `None` if any part is not found
+pub fn resolve_case_insensitive(base: &Path, path: &Path) -> Option<PathBuf> {
+    if base.join(path).exists() {
+        return Some(path.to_path_buf());
+    }
+
+    let mut resolved = PathBuf::new();
+    for component in path.components() {
+        let Component::Normal(name) = component else {
+            resolved.push(component);
+            continue;
+        };
+        let exact = resolved.join(name);
+        if base.join(&exact).exists() {
+            resolved = exact;
+            continue;
+        }
+
+        let wanted = name.to_string_lossy().to_lowercase();
+        let found = fs::read_dir(base.join(&resolved))
+            .ok()?
+            .filter_map(|e| e.ok())
+            .map(|e| e.file_name())
+            .find(|entry| entry.to_string_lossy().to_lowercase() == wanted)?;
+        resolved.push(found);
+    }
+    Some(resolved)
+}
+
+/// The path of a mod as it is on disk, or as written if it can not be found
+pub fn resolve_mod_path(a3root: &Path, mod_path: &Path) -> PathBuf {
+    resolve_case_insensitive(a3root, mod_path).unwrap_or(mod_path.to_path_buf())
+}
+
+/// Files and folders that were, or would be, renamed to lowercase
+#[derive(Debug, Default)]
+pub struct LowercaseReport {
+    pub renamed: Vec<(PathBuf, PathBuf)>,
+    /// not renamed, as the lowercase name is already taken
+    pub conflicts: Vec<PathBuf>,
+}
+
+impl std::fmt::Display for LowercaseReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (from, to) in &self.renamed {
+            writeln!(f, "{} -> {}", from.to_string_lossy(), to.to_string_lossy())?;
+        }
+        for path in &self.conflicts {
+            writeln!(
+                f,
+                "{} not renamed, the lowercase name already exists",
+                path.to_string_lossy()
+            )?;
+        }
+        write!(
+            f,
+            "{} renamed, {} conflicts",
+            self.renamed.len(),
+            self.conflicts.len()
+        )
+    }
+}
+
+/// Rename a mod folder and everything in it to lowercase, as the Linux server expects. With
+/// `dry_run` only the report is made
+pub fn lowercase_mod(folder: &Path, dry_run: bool) -> Result<LowercaseReport> {
+    let mut report = LowercaseReport::default();
+    lowercase_tree(folder, dry_run, &mut report)?;
+    Ok(report)
+}
+
+/// Rename the contents first, so the paths in the report are the ones before renaming
+fn lowercase_tree(path: &Path, dry_run: bool, report: &mut LowercaseReport) -> Result<()> {
+    if path.is_dir() {
+        for entry in fs::read_dir(path)? {
+            lowercase_tree(&entry?.path(), dry_run, report)?;
+        }
+    }
+
+    let Some(name) = path.file_name().map(|n| n.to_string_lossy().to_string()) else {
+        return Ok(());
+    };
+    let lower = name.to_lowercase();
+    if lower == name {
+        return Ok(());
+    }
+
+    let target = path.with_file_name(&lower);
+    // on case insensitive filesystems the target exists as the same file
+    let taken = target.exists() && !same_file(path, &target);
+    match taken {
+        true => report.conflicts.push(path.to_path_buf()),
+        false => {
+            if !dry_run {
+                fs::rename(path, &target)?;
+            }
+            report.renamed.push((path.to_path_buf(), target));
+        }
+    }
+    Ok(())
+}
+
+fn same_file(a: &Path, b: &Path) -> bool {
+    match (fs::canonicalize(a), fs::canonicalize(b)) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => false,
+    }
+}
+
+/// Whether the mod folder or its addons have uppercase letters in their names
+pub fn has_uppercase(folder: &Path) -> bool {
+    let uppercase = |path: &Path| {
+        path.file_name()
+            .is_some_and(|n| n.to_string_lossy().chars().any(char::is_uppercase))
+    };
+    if uppercase(folder) {
+        return true;
+    }
+
+    let Some(addons) = resolve_case_insensitive(folder, Path::new("addons")) else {
+        return false;
+    };
+    let addons = folder.join(addons);
+    uppercase(&addons)
+        || fs::read_dir(&addons)
+            .map(|entries| entries.filter_map(|e| e.ok()).any(|e| uppercase(&e.path())))
+            .unwrap_or(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize_separators() {
+        assert_eq!(
+            normalize_separators("mods\\@CBA_A3"),
+            Path::new("mods").join("@CBA_A3")
+        );
+        assert_eq!(
+            normalize_separators("./mods//@ace/"),
+            Path::new("mods").join("@ace")
+        );
+        assert_eq!(normalize_separators("@ace"), PathBuf::from("@ace"));
+    }
+
+    #[test]
+    fn test_resolve_and_lowercase() {
+        let root =
+            std::env::temp_dir().join(format!("corvus_test_mod_paths_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(root.join("Mods/@CBA_A3/Addons")).unwrap();
+        fs::write(root.join("Mods/@CBA_A3/Addons/CBA_Main.pbo"), "").unwrap();
+        fs::create_dir_all(root.join("mods_lower/@ace")).unwrap();
+        fs::create_dir_all(root.join("mods_lower/@ACE")).unwrap();
+
+        let resolved = resolve_mod_path(&root, &normalize_separators("mods\\@cba_a3"));
+        assert!(root.join(&resolved).is_dir());
+        assert!(resolve_case_insensitive(&root, Path::new("mods/@missing")).is_none());
+
+        let folder = root.join(&resolved);
+        assert!(has_uppercase(&folder));
+        let dry = lowercase_mod(&folder, true).unwrap();
+        assert_eq!(dry.renamed.len(), 3);
+        assert!(folder.exists());
+
+        let report = lowercase_mod(&folder, false).unwrap();
+        assert_eq!(report.renamed.len(), 3);
+        let lowered = folder.with_file_name("@cba_a3");
+        assert!(lowered.join("addons/cba_main.pbo").exists());
+        assert!(!has_uppercase(&lowered));
+
+        // both names exist on case sensitive filesystems, so the uppercase one can not be renamed
+        let report = lowercase_mod(&root.join("mods_lower/@ACE"), false).unwrap();
+        if cfg!(target_os = "linux") {
+            assert_eq!(report.conflicts.len(), 1);
+        }
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -0,0 +1,125 @@
+//! Disk size of mods, to tell players how big a download the selected modlists are
+use crate::arma::mod_paths::resolve_mod_path;
+use humansize::{DECIMAL, format_size};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::iter::Sum;
+use std::ops::Add;
+use std::path::{Path, PathBuf};
+
+/// Total size of the files in a mod folder
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct ModSize {
+    pub bytes: u64,
+    pub files: u64,
+}
+
+impl Add for ModSize {
+    type Output = ModSize;
+
+    fn add(self, other: ModSize) -> ModSize {
+        ModSize {
+            bytes: self.bytes + other.bytes,
+            files: self.files + other.files,
+        }
+    }
+}
+
+impl Sum for ModSize {
+    fn sum<I: Iterator<Item = ModSize>>(iter: I) -> ModSize {
+        iter.fold(ModSize::default(), Add::add)
+    }
+}
+
+impl std::fmt::Display for ModSize {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}, {} files",
+            format_size(self.bytes, DECIMAL),
+            self.files
+        )
+    }
+}
+
+/// Add up the size of all files in the folder and its subfolders. Links are not followed
+pub fn folder_size(folder: &Path) -> ModSize {
+    let Ok(entries) = fs::read_dir(folder) else {
+        return ModSize::default();
+    };
+
+    entries
+        .filter_map(|e| e.ok())
+        .filter_map(|e| Some((e.path(), e.file_type().ok()?)))
+        .map(|(path, file_type)| match file_type {
+            t if t.is_dir() => folder_size(&path),
+            t if t.is_file() => ModSize {
+                bytes: path.metadata().map(|m| m.len()).unwrap_or(0),
+                files: 1,
+            },
+            _ => ModSize::default(),
+        })
+        .sum()
+}
+
+/// Folder of a mod on disk, which is the key of the sizes cache
+pub fn mod_folder(a3root: &Path, mod_path: &Path) -> PathBuf {
+    a3root.join(resolve_mod_path(a3root, mod_path))
+}
+
+/// Combined size of the mods, counting mods that are in several lists once. `None` until the size
+/// of every mod is known
+pub fn total_size(
+    sizes: &HashMap<PathBuf, ModSize>,
+    a3root: &Path,
+    mods: &[PathBuf],
+) -> Option<ModSize> {
+    let folders: HashSet<PathBuf> = mods.iter().map(|m| mod_folder(a3root, m)).collect();
+    folders
+        .iter()
+        .map(|folder| sizes.get(folder).copied())
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_total_size() {
+        let a3root = Path::new("/nonexistent/a3");
+        let sizes = HashMap::from([
+            (
+                a3root.join("@cba"),
+                ModSize {
+                    bytes: 2_000_000,
+                    files: 10,
+                },
+            ),
+            (
+                a3root.join("@ace"),
+                ModSize {
+                    bytes: 500_000,
+                    files: 5,
+                },
+            ),
+        ]);
+
+        let mods = vec![
+            PathBuf::from("@cba"),
+            PathBuf::from("@ace"),
+            PathBuf::from("@cba"),
+        ];
+        let total = total_size(&sizes, a3root, &mods).unwrap();
+        assert_eq!(
+            total,
+            ModSize {
+                bytes: 2_500_000,
+                files: 15
+            }
+        );
+        assert_eq!(total.to_string(), "2.50 MB, 15 files");
+
+        assert_eq!(total_size(&sizes, a3root, &[PathBuf::from("@tfar")]), None);
+    }
+}
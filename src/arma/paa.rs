@@ -0,0 +1,208 @@
+//! Reads mod logos. Most are `.paa` textures, of which the DXT1 and DXT5 compressed ones are
+//! understood, anything else is left to the image crate
+use anyhow::{Result, anyhow};
+use image::RgbaImage;
+use std::fs;
+use std::path::Path;
+
+const DXT1: u16 = 0xFF01;
+const DXT5: u16 = 0xFF05;
+/// Set in the width of a mipmap whose data is LZO compressed
+const LZO_FLAG: u16 = 0x8000;
+
+/// Read a logo from a `.paa` or any format the image crate knows
+pub fn load_logo(path: &Path) -> Result<RgbaImage> {
+    let is_paa = path
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("paa") || ext.eq_ignore_ascii_case("pac"));
+    match is_paa {
+        true => decode_paa(&fs::read(path)?),
+        false => Ok(image::open(path)?.to_rgba8()),
+    }
+}
+
+/// Decode the largest mipmap of a `.paa` that is stored uncompressed. Compressed mipmaps are
+/// skipped, the smaller ones a logo needs usually are not
+pub fn decode_paa(data: &[u8]) -> Result<RgbaImage> {
+    let mut reader = Reader { data, pos: 0 };
+    let kind = reader.u16()?;
+    if kind != DXT1 && kind != DXT5 {
+        return Err(anyhow!("unsupported paa type {:#06x}", kind));
+    }
+
+    // taggs hold averages and flags, not needed for drawing
+    while reader.peek(4) == Some(b"GGAT") {
+        reader.take(8)?;
+        let len = reader.u32()? as usize;
+        reader.take(len)?;
+    }
+    let palette = reader.u16()? as usize;
+    reader.take(palette * 3)?;
+
+    loop {
+        let width = reader.u16()?;
+        let height = reader.u16()?;
+        if width == 0 || height == 0 {
+            return Err(anyhow!("paa has no uncompressed mipmap"));
+        }
+        let len = reader.u24()? as usize;
+        let pixels = reader.take(len)?;
+
+        if width & LZO_FLAG == 0 {
+            return decode_dxt(kind, width as u32, height as u32, pixels);
+        }
+    }
+}
+
+fn decode_dxt(kind: u16, width: u32, height: u32, data: &[u8]) -> Result<RgbaImage> {
+    let block_size = if kind == DXT1 { 8 } else { 16 };
+    let blocks_x = width.div_ceil(4);
+    let blocks_y = height.div_ceil(4);
+    if data.len() < (blocks_x * blocks_y) as usize * block_size {
+        return Err(anyhow!("paa mipmap of {}x{} is cut short", width, height));
+    }
+
+    let mut image = RgbaImage::new(width, height);
+    for (i, block) in data
+        .chunks_exact(block_size)
+        .take((blocks_x * blocks_y) as usize)
+        .enumerate()
+    {
+        let (colors, alphas) = match kind {
+            DXT1 => (&block[..8], None),
+            _ => (&block[8..], Some(&block[..8])),
+        };
+        let palette = color_palette(colors, alphas.is_none());
+        let indices = u32::from_le_bytes([colors[4], colors[5], colors[6], colors[7]]);
+        let alpha = alphas.map(alpha_values);
+
+        let (bx, by) = (i as u32 % blocks_x * 4, i as u32 / blocks_x * 4);
+        for texel in 0..16 {
+            let (x, y) = (bx + texel % 4, by + texel / 4);
+            if x >= width || y >= height {
+                continue;
+            }
+            let mut pixel = palette[(indices >> (texel * 2) & 0b11) as usize];
+            if let Some((values, bits)) = alpha {
+                pixel[3] = values[(bits >> (texel * 3) & 0b111) as usize];
+            }
+            image.put_pixel(x, y, image::Rgba(pixel));
+        }
+    }
+    Ok(image)
+}
+
+/// The four colors of a DXT block. DXT1 blocks with the first color not above the second have
+/// three colors and transparency
+fn color_palette(block: &[u8], dxt1: bool) -> [[u8; 4]; 4] {
+    let c0 = u16::from_le_bytes([block[0], block[1]]);
+    let c1 = u16::from_le_bytes([block[2], block[3]]);
+    let (a, b) = (rgb565(c0), rgb565(c1));
+    let mix = |wa: u16, wb: u16| {
+        let channel = |i: usize| ((a[i] as u16 * wa + b[i] as u16 * wb) / (wa + wb)) as u8;
+        [channel(0), channel(1), channel(2), 255]
+    };
+
+    match !dxt1 || c0 > c1 {
+        true => [a, b, mix(2, 1), mix(1, 2)],
+        false => [a, b, mix(1, 1), [0, 0, 0, 0]],
+    }
+}
+
+fn rgb565(color: u16) -> [u8; 4] {
+    let r = (color >> 11 & 0x1F) as u8;
+    let g = (color >> 5 & 0x3F) as u8;
+    let b = (color & 0x1F) as u8;
+    [r << 3 | r >> 2, g << 2 | g >> 4, b << 3 | b >> 2, 255]
+}
+
+/// The eight alpha values of a DXT5 block, with the 3 bit index of each texel
+fn alpha_values(block: &[u8]) -> ([u8; 8], u64) {
+    let (a0, a1) = (block[0] as u16, block[1] as u16);
+    let mut values = [0u8; 8];
+    values[0] = a0 as u8;
+    values[1] = a1 as u8;
+    match a0 > a1 {
+        true => {
+            for i in 1..7 {
+                values[i + 1] = ((a0 * (7 - i as u16) + a1 * i as u16) / 7) as u8;
+            }
+        }
+        false => {
+            for i in 1..5 {
+                values[i + 1] = ((a0 * (5 - i as u16) + a1 * i as u16) / 5) as u8;
+            }
+            values[6] = 0;
+            values[7] = 255;
+        }
+    }
+
+    let mut bits = [0u8; 8];
+    bits[..6].copy_from_slice(&block[2..8]);
+    (values, u64::from_le_bytes(bits))
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn peek(&self, len: usize) -> Option<&'a [u8]> {
+        self.data.get(self.pos..self.pos + len)
+    }
+
+    fn take(&mut self, len: usize) -> Result<&'a [u8]> {
+        let bytes = self.peek(len).ok_or(anyhow!("paa ends early"))?;
+        self.pos += len;
+        Ok(bytes)
+    }
+
+    fn u16(&mut self) -> Result<u16> {
+        let b = self.take(2)?;
+        Ok(u16::from_le_bytes([b[0], b[1]]))
+    }
+
+    fn u24(&mut self) -> Result<u32> {
+        let b = self.take(3)?;
+        Ok(u32::from_le_bytes([b[0], b[1], b[2], 0]))
+    }
+
+    fn u32(&mut self) -> Result<u32> {
+        let b = self.take(4)?;
+        Ok(u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode_paa() {
+        let mut paa = DXT1.to_le_bytes().to_vec();
+        // a tagg, then an empty palette
+        paa.extend(b"GGATCGVA");
+        paa.extend(4u32.to_le_bytes());
+        paa.extend([1, 2, 3, 4]);
+        paa.extend(0u16.to_le_bytes());
+        // compressed 8x8 mipmap, skipped
+        paa.extend((8 | LZO_FLAG).to_le_bytes());
+        paa.extend(8u16.to_le_bytes());
+        paa.extend([3, 0, 0, 0xAA, 0xBB, 0xCC]);
+        // 4x4 mipmap of pure red, the top row blue
+        paa.extend(4u16.to_le_bytes());
+        paa.extend(4u16.to_le_bytes());
+        paa.extend([8, 0, 0]);
+        paa.extend(0xF800u16.to_le_bytes());
+        paa.extend(0x001Fu16.to_le_bytes());
+        paa.extend(0x0000_0055u32.to_le_bytes());
+        // end of the mipmaps
+        paa.extend([0, 0, 0, 0]);
+
+        let image = decode_paa(&paa).unwrap();
+        assert_eq!(image.dimensions(), (4, 4));
+        assert_eq!(image.get_pixel(0, 0).0, [0, 0, 255, 255]);
+        assert_eq!(image.get_pixel(3, 3).0, [255, 0, 0, 255]);
+    }
+}
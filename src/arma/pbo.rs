@@ -0,0 +1,196 @@
+//! Reads PBO archives, the packed addons inside mod folders. Only the header is read up front,
+//! files are read from disk when asked for
+use anyhow::{Result, anyhow};
+use std::fs::File;
+use std::io::{BufReader, Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+
+/// Packing method of the header extension entry, "Vers"
+const PACKING_VERSION: u32 = 0x5665_7273;
+/// Packing method of LZSS compressed files, "Cprs"
+const PACKING_COMPRESSED: u32 = 0x4370_7273;
+
+#[derive(Debug, Clone)]
+pub struct Pbo {
+    pub path: PathBuf,
+    pub entries: Vec<PboEntry>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PboEntry {
+    /// path inside the pbo, with `\` separators
+    pub name: String,
+    pub packing: u32,
+    /// size once unpacked, 0 if the file is not compressed
+    pub original_size: u32,
+    pub timestamp: u32,
+    /// size of the data in the pbo
+    pub data_size: u32,
+    /// where the data starts in the pbo
+    pub offset: u64,
+}
+
+impl Pbo {
+    /// Read the header of a pbo
+    pub fn open(path: &Path) -> Result<Pbo> {
+        let mut reader = BufReader::new(File::open(path)?);
+        let mut entries = Vec::new();
+
+        loop {
+            let name = read_string(&mut reader)?;
+            let packing = read_u32(&mut reader)?;
+            let original_size = read_u32(&mut reader)?;
+            let _reserved = read_u32(&mut reader)?;
+            let timestamp = read_u32(&mut reader)?;
+            let data_size = read_u32(&mut reader)?;
+
+            if name.is_empty() {
+                if packing != PACKING_VERSION {
+                    break;
+                }
+                // header extension, pairs of strings like the prefix ending with an empty one
+                while !read_string(&mut reader)?.is_empty() {
+                    read_string(&mut reader)?;
+                }
+                continue;
+            }
+
+            entries.push(PboEntry {
+                name: name.replace('/', "\\"),
+                packing,
+                original_size,
+                timestamp,
+                data_size,
+                offset: 0,
+            });
+        }
+
+        // file data follows the header, in the order of the entries
+        let mut offset = reader.stream_position()?;
+        for entry in &mut entries {
+            entry.offset = offset;
+            offset += entry.data_size as u64;
+        }
+
+        Ok(Pbo {
+            path: path.to_path_buf(),
+            entries,
+        })
+    }
+
+    /// Find a file by its path inside the pbo, ignoring casing and separators
+    pub fn entry(&self, name: &str) -> Option<&PboEntry> {
+        let name = name.replace('/', "\\");
+        self.entries
+            .iter()
+            .find(|e| e.name.eq_ignore_ascii_case(&name))
+    }
+
+    /// Read a file from the pbo, unpacking it if it is compressed
+    pub fn read(&self, entry: &PboEntry) -> Result<Vec<u8>> {
+        let mut file = File::open(&self.path)?;
+        file.seek(SeekFrom::Start(entry.offset))?;
+        let mut data = vec![0; entry.data_size as usize];
+        file.read_exact(&mut data)?;
+
+        match entry.packing == PACKING_COMPRESSED && entry.original_size != entry.data_size {
+            true => lzss_decompress(&data, entry.original_size as usize),
+            false => Ok(data),
+        }
+    }
+}
+
+/// Unpack the LZSS variant used by pbos. The checksum after the data is not checked
+fn lzss_decompress(data: &[u8], size: usize) -> Result<Vec<u8>> {
+    let mut out: Vec<u8> = Vec::with_capacity(size);
+    let mut input = data.iter().copied();
+    let ends_early = || anyhow!("compressed file ends early");
+
+    while out.len() < size {
+        let flags = input.next().ok_or_else(ends_early)?;
+        for bit in 0..8 {
+            if out.len() >= size {
+                break;
+            }
+            if flags & (1 << bit) != 0 {
+                out.push(input.next().ok_or_else(ends_early)?);
+                continue;
+            }
+
+            // pointer back into what was unpacked so far
+            let b0 = input.next().ok_or_else(ends_early)? as usize;
+            let b1 = input.next().ok_or_else(ends_early)? as usize;
+            let distance = b0 | ((b1 & 0xF0) << 4);
+            let length = (b1 & 0x0F) + 3;
+            for _ in 0..length {
+                // pointing before the start of the data means spaces
+                let byte = match out.len().checked_sub(distance) {
+                    Some(pos) => out[pos],
+                    None => b' ',
+                };
+                out.push(byte);
+            }
+        }
+    }
+
+    out.truncate(size);
+    Ok(out)
+}
+
+fn read_u32(reader: &mut impl Read) -> Result<u32> {
+    let mut bytes = [0; 4];
+    reader.read_exact(&mut bytes)?;
+    Ok(u32::from_le_bytes(bytes))
+}
+
+fn read_string(reader: &mut impl Read) -> Result<String> {
+    let mut bytes = Vec::new();
+    let mut byte = [0; 1];
+    loop {
+        reader.read_exact(&mut byte)?;
+        if byte[0] == 0 {
+            break;
+        }
+        bytes.push(byte[0]);
+    }
+    Ok(String::from_utf8_lossy(&bytes).to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn header(name: &str, packing: u32, original_size: u32, data_size: u32) -> Vec<u8> {
+        let mut bytes = name.as_bytes().to_vec();
+        bytes.push(0);
+        for value in [packing, original_size, 0, 0, data_size] {
+            bytes.extend(value.to_le_bytes());
+        }
+        bytes
+    }
+
+    #[test]
+    fn test_read_pbo() {
+        let mut pbo = header("", PACKING_VERSION, 0, 0);
+        pbo.extend(b"prefix\0z\\ace\\addons\\common\0\0");
+        pbo.extend(header("config.cpp", 0, 0, 5));
+        // three letters, then a pointer three letters back for six more
+        pbo.extend(header("texts/Stringtable.xml", PACKING_COMPRESSED, 9, 6));
+        pbo.extend(header("", 0, 0, 0));
+        pbo.extend(b"hello");
+        pbo.extend([0b0000_0111, b'a', b'b', b'c', 3, 3]);
+
+        let path =
+            std::env::temp_dir().join(format!("corvus_test_read_{}.pbo", std::process::id()));
+        std::fs::write(&path, &pbo).unwrap();
+        let read = Pbo::open(&path).unwrap();
+
+        assert_eq!(read.entries.len(), 2);
+        let config = read.entry("CONFIG.cpp").unwrap();
+        assert_eq!(read.read(config).unwrap(), b"hello");
+        let stringtable = read.entry("texts/stringtable.xml").unwrap();
+        assert_eq!(read.read(stringtable).unwrap(), b"abcabcabc");
+
+        std::fs::remove_file(&path).unwrap();
+    }
+}
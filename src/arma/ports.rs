@@ -0,0 +1,124 @@
+//! The UDP ports a server uses. Arma takes the game port and the four after it (VON, Steam query,
+//! Steam master and BattlEye), so two servers need their ports at least [`PORT_BLOCK`] apart
+use anyhow::Result;
+use std::net::UdpSocket;
+use std::ops::RangeInclusive;
+
+/// Ports used by one server, starting at the game port
+pub const PORT_BLOCK: u16 = 5;
+/// Below this ports are reserved for the system
+const MIN_PORT: u16 = 1024;
+
+/// The port is not a number or outside the usable range
+#[derive(Debug)]
+pub struct InvalidPort(pub String);
+
+impl std::fmt::Display for InvalidPort {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl std::error::Error for InvalidPort {}
+
+/// Ports of the block are already taken by another program or server
+#[derive(Debug)]
+pub struct PortsInUse {
+    pub port: u16,
+    pub used: Vec<u16>,
+}
+
+impl std::fmt::Display for PortsInUse {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let used: Vec<String> = self.used.iter().map(u16::to_string).collect();
+        let block = port_block(self.port);
+        write!(
+            f,
+            "Ports {}-{} are needed by the server, but these are in use: {}",
+            block.start(),
+            block.end(),
+            used.join(", ")
+        )
+    }
+}
+
+impl std::error::Error for PortsInUse {}
+
+/// Parse the game port, it has to leave room for the whole block
+pub fn parse_port(input: &str) -> Result<u16> {
+    let max = u16::MAX - (PORT_BLOCK - 1);
+    match input.trim().parse::<u16>() {
+        Ok(port) if (MIN_PORT..=max).contains(&port) => Ok(port),
+        Ok(port) => Err(InvalidPort(format!(
+            "Port {} is out of range, it has to be between {} and {}",
+            port, MIN_PORT, max
+        ))
+        .into()),
+        Err(_) => Err(InvalidPort(format!("Port {:?} is not a number", input.trim())).into()),
+    }
+}
+
+/// All ports used by a server on the game port
+pub fn port_block(port: u16) -> RangeInclusive<u16> {
+    port..=port.saturating_add(PORT_BLOCK - 1)
+}
+
+/// Ports of the block that are bound by some program, or belong to a server in `reserved`.
+/// Servers still loading may not have bound their ports yet, so the launcher passes its own
+pub fn ports_in_use(port: u16, reserved: &[u16]) -> Vec<u16> {
+    port_block(port)
+        .filter(|p| {
+            reserved.iter().any(|r| port_block(*r).contains(p))
+                || UdpSocket::bind(("0.0.0.0", *p)).is_err()
+        })
+        .collect()
+}
+
+/// Fails if any port of the block is taken
+pub fn check_port_block(port: u16, reserved: &[u16]) -> Result<()> {
+    let used = ports_in_use(port, reserved);
+    match used.is_empty() {
+        true => Ok(()),
+        false => Err(PortsInUse { port, used }.into()),
+    }
+}
+
+/// The first block from `port` on, in steps of a block, that is completely free
+pub fn next_free_block(port: u16, reserved: &[u16]) -> Result<u16> {
+    let max = u16::MAX - (PORT_BLOCK - 1);
+    (port..=max)
+        .step_by(PORT_BLOCK as usize)
+        .find(|p| ports_in_use(*p, reserved).is_empty())
+        .ok_or_else(|| anyhow::Error::msg(format!("No free ports found after {}", port)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_port() {
+        assert_eq!(parse_port(" 2302 ").unwrap(), 2302);
+        assert!(parse_port("23o2").is_err());
+        assert!(parse_port("80").is_err());
+        assert!(parse_port("65534").is_err());
+        assert!(parse_port("70000").is_err());
+        assert_eq!(parse_port("65531").unwrap(), 65531);
+    }
+
+    #[test]
+    fn test_ports_in_use() {
+        // let the system pick a free port and keep it bound
+        let socket = UdpSocket::bind(("0.0.0.0", 0)).unwrap();
+        let bound = socket.local_addr().unwrap().port();
+        assert!(ports_in_use(bound, &[]).contains(&bound));
+        assert!(check_port_block(bound, &[]).is_err());
+
+        // ports of servers of the launcher count as used, even when not bound. Other programs
+        // may use any of the ports, so only the reserved ones are checked exactly
+        assert!(ports_in_use(40000, &[39998]).starts_with(&[40000, 40001, 40002]));
+        assert!(check_port_block(40000, &[40004]).is_err());
+        let next = next_free_block(40000, &[40000, 40005]).unwrap();
+        assert!(next >= 40010 && (next - 40000).is_multiple_of(PORT_BLOCK));
+    }
+}
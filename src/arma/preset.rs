@@ -0,0 +1,480 @@
+//! Mod presets exported from the Arma 3 Launcher, as HTML. Each mod is listed with its display
+//! name and a link to its workshop page, which is matched to the mods installed on the server.
+//! Presets can also be written, so players can load the mods the server runs
+use crate::arma::mod_meta::ModMeta;
+use crate::arma::mod_paths::resolve_mod_path;
+use anyhow::Result;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Steam app ids of the creator DLCs, with the folder the server loads them from and their name
+const DLCS: [(u64, &str, &str); 7] = [
+    (1042220, "gm", "Global Mobilization"),
+    (1227700, "vn", "S.O.G. Prairie Fire"),
+    (1294440, "csla", "CSLA Iron Curtain"),
+    (1681170, "ws", "Western Sahara"),
+    (1175380, "spe", "Spearhead 1944"),
+    (2647760, "rf", "Reaction Forces"),
+    (2647830, "ef", "Expeditionary Forces"),
+];
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Preset {
+    pub name: Option<String>,
+    pub mods: Vec<PresetMod>,
+    /// Steam app ids of the DLCs the preset needs
+    pub dlcs: Vec<u64>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PresetMod {
+    pub name: String,
+    /// Workshop id, none for mods loaded from a local folder
+    pub workshop_id: Option<u64>,
+}
+
+/// An entry of a preset that matches no mod on the server
+#[derive(Debug, Clone, PartialEq)]
+pub struct UnresolvedMod {
+    pub name: String,
+    pub workshop_id: Option<u64>,
+}
+
+impl std::fmt::Display for UnresolvedMod {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.workshop_id {
+            Some(id) => write!(f, "{} (workshop id {})", self.name, id),
+            None => write!(f, "{} (local mod)", self.name),
+        }
+    }
+}
+
+impl Preset {
+    pub fn load(path: &Path) -> Result<Preset> {
+        Ok(Preset::parse(&fs::read_to_string(path)?))
+    }
+
+    /// Parse the HTML written by the Arma 3 Launcher. Only the parts the launcher writes are
+    /// understood, not HTML in general
+    pub fn parse(html: &str) -> Preset {
+        let name = meta_content(html, "arma:PresetName");
+
+        let mods = containers(html, "ModContainer")
+            .filter_map(|row| {
+                let name = cell(row, "DisplayName")?;
+                Some(PresetMod {
+                    workshop_id: link(row).and_then(|href| id_from_link(&href, "id=")),
+                    name,
+                })
+            })
+            .collect();
+
+        let dlcs = containers(html, "DlcContainer")
+            .filter_map(|row| link(row).and_then(|href| id_from_link(&href, "/app/")))
+            .collect();
+
+        Preset { name, mods, dlcs }
+    }
+
+    /// Name for a preset of the modpacks, or of the file it is written to when only clientsides
+    /// are exported, so the Arma 3 Launcher does not show an empty name
+    pub fn default_name(modpacks: &[&str], path: &Path) -> String {
+        match modpacks.is_empty() {
+            true => path
+                .file_stem()
+                .unwrap_or_default()
+                .to_string_lossy()
+                .to_string(),
+            false => modpacks.join(" + "),
+        }
+    }
+
+    /// Preset of the given mods, relative to the A3 root. Workshop ids are read from the
+    /// `meta.cpp` of each mod, mods without one are local mods. DLC folders become DLCs
+    pub fn from_mods(name: &str, a3root: &Path, mods: &[PathBuf]) -> Preset {
+        let mut preset = Preset {
+            name: Some(name.to_string()),
+            ..Default::default()
+        };
+
+        for mod_path in mods {
+            let folder = mod_path.to_string_lossy();
+            if let Some((app_id, ..)) = DLCS
+                .iter()
+                .find(|(_, f, _)| f.eq_ignore_ascii_case(&folder))
+            {
+                if !preset.dlcs.contains(app_id) {
+                    preset.dlcs.push(*app_id);
+                }
+                continue;
+            }
+
+            let meta = ModMeta::load(&a3root.join(resolve_mod_path(a3root, mod_path)));
+            let entry = PresetMod {
+                name: meta
+                    .as_ref()
+                    .and_then(|m| m.name.clone())
+                    .unwrap_or(folder_name(mod_path)),
+                workshop_id: meta.and_then(|m| m.published_id),
+            };
+            if !preset.mods.contains(&entry) {
+                preset.mods.push(entry);
+            }
+        }
+        preset
+    }
+
+    /// Write the preset as HTML the Arma 3 Launcher can import. Local mods are listed in their own
+    /// table, as players have to get them some other way
+    pub fn to_html(&self) -> String {
+        let name = encode_entities(self.name.as_deref().unwrap_or("CorvusLauncher"));
+        let (workshop, local): (Vec<&PresetMod>, Vec<&PresetMod>) =
+            self.mods.iter().partition(|m| m.workshop_id.is_some());
+
+        let mut html = format!(
+            r#"<?xml version="1.0" encoding="utf-8"?>
+<html>
+  <!--Created by CorvusLauncher-->
+  <head>
+    <meta name="arma:Type" content="preset" />
+    <meta name="arma:PresetName" content="{name}" />
+    <meta name="generator" content="CorvusLauncher" />
+    <title>Arma 3</title>
+  </head>
+  <body>
+    <h1>Arma 3 - Preset <strong>{name}</strong></h1>
+    <p class="before-list">
+      <em>To import this preset, drag this file onto the Launcher window. Or click the MODS tab, then PRESET in the top right, then IMPORT at the bottom, and finally select this file.</em>
+    </p>
+    <div class="mod-list">
+      <table>
+"#
+        );
+        for entry in workshop {
+            let id = entry.workshop_id.unwrap_or_default();
+            html.push_str(&format!(
+                r#"        <tr data-type="ModContainer">
+          <td data-type="DisplayName">{}</td>
+          <td><span class="from-steam">Steam</span></td>
+          <td><a href="https://steamcommunity.com/sharedfiles/filedetails/?id={id}" data-type="Link">https://steamcommunity.com/sharedfiles/filedetails/?id={id}</a></td>
+        </tr>
+"#,
+                encode_entities(&entry.name)
+            ));
+        }
+        html.push_str("      </table>\n    </div>\n");
+
+        if !local.is_empty() {
+            html.push_str(
+                r#"    <h2>Local mods</h2>
+    <p><em>These mods are not on the workshop and have to be installed by hand.</em></p>
+    <div class="mod-list">
+      <table>
+"#,
+            );
+            for entry in local {
+                let name = encode_entities(&entry.name);
+                html.push_str(&format!(
+                    r#"        <tr data-type="ModContainer">
+          <td data-type="DisplayName">{name}</td>
+          <td><span class="from-local">Local</span></td>
+        </tr>
+"#
+                ));
+            }
+            html.push_str("      </table>\n    </div>\n");
+        }
+
+        if !self.dlcs.is_empty() {
+            html.push_str("    <div class=\"dlc-list\">\n      <table>\n");
+            for app_id in &self.dlcs {
+                let name = DLCS
+                    .iter()
+                    .find(|(id, ..)| id == app_id)
+                    .map_or(app_id.to_string(), |(.., name)| name.to_string());
+                html.push_str(&format!(
+                    r#"        <tr data-type="DlcContainer">
+          <td data-type="DisplayName">{name}</td>
+          <td><a href="https://store.steampowered.com/app/{app_id}" data-type="Link">https://store.steampowered.com/app/{app_id}</a></td>
+        </tr>
+"#
+                ));
+            }
+            html.push_str("      </table>\n    </div>\n");
+        }
+
+        html.push_str("  </body>\n</html>\n");
+        html
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        fs::write(path, self.to_html())?;
+        Ok(())
+    }
+}
+
+/// A mod installed on the server, found by [`find_local_mods`]
+#[derive(Debug, Clone)]
+pub struct LocalMod {
+    /// relative to the A3 root
+    pub path: PathBuf,
+    pub meta: Option<ModMeta>,
+}
+
+/// Mod folders, starting with `@`, in the A3 root and the folders directly in it
+pub fn find_local_mods(a3root: &Path) -> Vec<LocalMod> {
+    let mut found: Vec<PathBuf> = Vec::new();
+    let Ok(entries) = fs::read_dir(a3root) else {
+        return Vec::new();
+    };
+
+    for entry in entries.filter_map(|e| e.ok()).filter(|e| e.path().is_dir()) {
+        let name = PathBuf::from(entry.file_name());
+        match name.to_string_lossy().starts_with('@') {
+            true => found.push(name),
+            false => {
+                let Ok(children) = fs::read_dir(entry.path()) else {
+                    continue;
+                };
+                found.extend(
+                    children
+                        .filter_map(|e| e.ok())
+                        .filter(|e| e.path().is_dir())
+                        .filter(|e| e.file_name().to_string_lossy().starts_with('@'))
+                        .map(|e| name.join(e.file_name())),
+                );
+            }
+        }
+    }
+
+    found.sort();
+    found
+        .into_iter()
+        .map(|path| LocalMod {
+            meta: ModMeta::load(&a3root.join(&path)),
+            path,
+        })
+        .collect()
+}
+
+/// Match the entries of a preset to the installed mods. Workshop ids are matched against the
+/// `meta.cpp` of the mods, names against the name in `meta.cpp` and the folder name. DLCs are
+/// loaded from their own folders
+pub fn resolve_preset(preset: &Preset, local: &[LocalMod]) -> (Vec<PathBuf>, Vec<UnresolvedMod>) {
+    let mut mods: Vec<PathBuf> = preset
+        .dlcs
+        .iter()
+        .filter_map(|app| DLCS.iter().find(|(id, ..)| id == app))
+        .map(|(_, folder, _)| PathBuf::from(folder))
+        .collect();
+    let mut unresolved = Vec::new();
+
+    for entry in &preset.mods {
+        let by_id = entry.workshop_id.and_then(|id| {
+            local
+                .iter()
+                .find(|m| m.meta.as_ref().and_then(|meta| meta.published_id) == Some(id))
+        });
+        let wanted = simplify(&entry.name);
+        let by_name = || {
+            local.iter().find(|m| {
+                m.meta
+                    .as_ref()
+                    .and_then(|meta| meta.name.as_deref())
+                    .is_some_and(|name| simplify(name) == wanted)
+                    || m.path
+                        .file_name()
+                        .is_some_and(|f| simplify(&f.to_string_lossy()) == wanted)
+            })
+        };
+
+        match by_id.or_else(by_name) {
+            Some(found) => mods.push(found.path.clone()),
+            None => unresolved.push(UnresolvedMod {
+                name: entry.name.clone(),
+                workshop_id: entry.workshop_id,
+            }),
+        }
+    }
+
+    (mods, unresolved)
+}
+
+/// Lowercase name without `@` and punctuation, so "@CBA_A3" matches "CBA A3"
+fn simplify(name: &str) -> String {
+    name.chars()
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+/// Name of the mod folder without the `@`
+fn folder_name(mod_path: &Path) -> String {
+    mod_path
+        .file_name()
+        .map(|f| f.to_string_lossy().trim_start_matches('@').to_string())
+        .unwrap_or_default()
+}
+
+/// The table rows with the given data-type
+fn containers<'a>(html: &'a str, data_type: &'a str) -> impl Iterator<Item = &'a str> + 'a {
+    let marker = format!("data-type=\"{}\"", data_type);
+    html.split("<tr")
+        .skip(1)
+        .filter(move |row| row.starts_with(|c: char| c.is_whitespace()) && row.contains(&marker))
+        .map(|row| row.split("</tr>").next().unwrap_or(row))
+}
+
+/// Text of the cell with the given data-type
+fn cell(row: &str, data_type: &str) -> Option<String> {
+    let start = row.find(&format!("data-type=\"{}\"", data_type))?;
+    let rest = &row[start..];
+    let text = &rest[rest.find('>')? + 1..];
+    let text = &text[..text.find("</td>")?];
+    Some(decode_entities(text.trim()))
+}
+
+/// Target of the first link in the row
+fn link(row: &str) -> Option<String> {
+    let start = row.find("href=\"")? + "href=\"".len();
+    let rest = &row[start..];
+    Some(decode_entities(&rest[..rest.find('"')?]))
+}
+
+/// The number following `marker` in a link
+fn id_from_link(href: &str, marker: &str) -> Option<u64> {
+    let start = href.find(marker)? + marker.len();
+    let digits: String = href[start..]
+        .chars()
+        .take_while(char::is_ascii_digit)
+        .collect();
+    digits.parse().ok()
+}
+
+/// Content of a `<meta name="..." content="...">` tag
+fn meta_content(html: &str, name: &str) -> Option<String> {
+    let start = html.find(&format!("name=\"{}\"", name))?;
+    let rest = &html[start..];
+    let rest = &rest[rest.find("content=\"")? + "content=\"".len()..];
+    Some(decode_entities(&rest[..rest.find('"')?]))
+}
+
+fn decode_entities(text: &str) -> String {
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
+
+fn encode_entities(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PRESET: &str = r#"<?xml version="1.0" encoding="utf-8"?>
+<html>
+  <head>
+    <meta name="arma:Type" content="preset" />
+    <meta name="arma:PresetName" content="Ops &amp; Training" />
+    <title>Arma 3</title>
+  </head>
+  <body>
+    <div class="mod-list">
+      <table>
+        <tr data-type="ModContainer">
+          <td data-type="DisplayName">CBA_A3</td>
+          <td><span class="from-steam">Steam</span></td>
+          <td><a href="http://steamcommunity.com/sharedfiles/filedetails/?id=450814997" data-type="Link">link</a></td>
+        </tr>
+        <tr data-type="ModContainer">
+          <td data-type="DisplayName">ace</td>
+          <td><span class="from-steam">Steam</span></td>
+          <td><a href="https://steamcommunity.com/sharedfiles/filedetails/?id=463939057" data-type="Link">link</a></td>
+        </tr>
+        <tr data-type="ModContainer">
+          <td data-type="DisplayName">Our Local Fixes</td>
+          <td><span class="from-local">Local</span></td>
+        </tr>
+        <tr data-type="ModContainer">
+          <td data-type="DisplayName">Task Force Arrowhead Radio</td>
+          <td><a href="https://steamcommunity.com/sharedfiles/filedetails/?id=894678801" data-type="Link">link</a></td>
+        </tr>
+      </table>
+    </div>
+    <div class="dlc-list">
+      <table>
+        <tr data-type="DlcContainer">
+          <td data-type="DisplayName">S.O.G. Prairie Fire</td>
+          <td><a href="https://store.steampowered.com/app/1227700" data-type="Link">link</a></td>
+        </tr>
+      </table>
+    </div>
+  </body>
+</html>"#;
+
+    #[test]
+    fn test_parse_preset() {
+        let preset = Preset::parse(PRESET);
+        assert_eq!(preset.name.as_deref(), Some("Ops & Training"));
+        assert_eq!(preset.mods.len(), 4);
+        assert_eq!(preset.mods[0].name, "CBA_A3");
+        assert_eq!(preset.mods[0].workshop_id, Some(450814997));
+        assert_eq!(preset.mods[2].workshop_id, None);
+        assert_eq!(preset.dlcs, vec![1227700]);
+
+        let local = vec![
+            LocalMod {
+                path: PathBuf::from("mods/@cba"),
+                meta: Some(ModMeta::parse(
+                    "protocol = 1;\npublishedid = 450814997;\nname = \"CBA_A3\";",
+                )),
+            },
+            LocalMod {
+                path: PathBuf::from("mods/@ACE"),
+                meta: None,
+            },
+            LocalMod {
+                path: PathBuf::from("mods/@our_local_fixes"),
+                meta: None,
+            },
+        ];
+        let (mods, unresolved) = resolve_preset(&preset, &local);
+        assert_eq!(
+            mods,
+            vec![
+                PathBuf::from("vn"),
+                PathBuf::from("mods/@cba"),
+                PathBuf::from("mods/@ACE"),
+                PathBuf::from("mods/@our_local_fixes"),
+            ]
+        );
+        assert_eq!(unresolved.len(), 1);
+        assert_eq!(unresolved[0].workshop_id, Some(894678801));
+    }
+
+    #[test]
+    fn test_preset_round_trip() {
+        let preset = Preset::parse(PRESET);
+        let written = Preset::parse(&preset.to_html());
+
+        // local mods are written after the workshop mods
+        let mut expected = preset.clone();
+        expected.mods.sort_by_key(|m| m.workshop_id.is_none());
+        assert_eq!(written, expected);
+    }
+
+    #[test]
+    fn test_default_name() {
+        let path = Path::new("presets/tfar.html");
+        assert_eq!(Preset::default_name(&["basic", "ww2"], path), "basic + ww2");
+        assert_eq!(Preset::default_name(&[], path), "tfar");
+    }
+}
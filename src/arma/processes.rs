@@ -0,0 +1,877 @@
+//! Registry of the server and HC processes spawned by the launcher. Persisted to disk, so we still
+//! know what is running after the launcher has been closed and opened again
+use crate::arma::dependencies::cache_patches;
+use crate::arma::instances::check_keys_in_use;
+use crate::arma::launch_plan::LaunchPlan;
+use crate::arma::mod_size::mod_folder;
+use crate::arma::ports::parse_port;
+use crate::arma::server_launch::{ServerLaunch, hc_profile_folder, launch_hc};
+use crate::configs::keys::KeySettings;
+use crate::configs::profile::LaunchParameters;
+use anyhow::Result;
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, Stdio};
+use std::time::{Duration, Instant};
+
+/// How long to wait for a process to exit after asking it to stop
+const STOP_TIMEOUT: Duration = Duration::from_secs(15);
+/// Keeps the helper programs we run from opening a console window, the launcher has none
+#[cfg(target_os = "windows")]
+const CREATE_NO_WINDOW: u32 = 0x0800_0000;
+
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub enum ProcessKind {
+    Server,
+    HeadlessClient(u64),
+}
+
+impl std::fmt::Display for ProcessKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ProcessKind::Server => write!(f, "Server"),
+            ProcessKind::HeadlessClient(index) => write!(f, "HC{}", index),
+        }
+    }
+}
+
+/// Everything we know about a spawned process, including what is needed to launch it again
+#[derive(Clone, Debug, Deserialize, Serialize, bon::Builder)]
+pub struct ProcessRecord {
+    pub pid: u32,
+    pub kind: ProcessKind,
+    #[builder(default = Local::now())]
+    pub started: DateTime<Local>,
+    pub a3_root: PathBuf,
+    pub a3_executable: PathBuf,
+    pub port: String,
+    #[builder(default)]
+    pub profile: String,
+    #[builder(default)]
+    pub mods: Vec<PathBuf>,
+    #[builder(default)]
+    pub clientsides: Vec<PathBuf>,
+    #[builder(default)]
+    pub server_mods: Vec<PathBuf>,
+    #[builder(default)]
+    #[serde(default)]
+    pub launch_parameters: LaunchParameters,
+    /// server config chosen for the profile, also used by headless clients for the password
+    #[serde(default)]
+    pub server_config: Option<PathBuf>,
+    #[serde(default)]
+    pub basic_config: Option<PathBuf>,
+    /// named instance the process belongs to
+    #[serde(default)]
+    pub instance: Option<String>,
+    /// When we noticed the process was no longer running
+    pub exited: Option<DateTime<Local>>,
+    /// Exit code, only known if the process was spawned by this instance of the launcher
+    pub exit_code: Option<i32>,
+    /// Stopped from the launcher, so exiting was expected
+    #[builder(default)]
+    #[serde(default)]
+    pub stopped: bool,
+    /// Times the watchdog restarted this process in a row
+    #[builder(default)]
+    #[serde(default)]
+    pub restarts: u32,
+    /// The crash that led to the last restart, or this process crashing
+    #[serde(default)]
+    pub last_crash: Option<Crash>,
+}
+
+/// An exit that was not asked for
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+pub struct Crash {
+    pub time: DateTime<Local>,
+    pub exit_code: Option<i32>,
+    /// end of the RPT log at the time of the crash
+    pub last_lines: Vec<String>,
+}
+
+impl ProcessRecord {
+    /// Record for a server launched with the given settings
+    pub fn server(pid: u32, launch: &ServerLaunch) -> ProcessRecord {
+        ProcessRecord::builder()
+            .pid(pid)
+            .kind(ProcessKind::Server)
+            .a3_root(launch.a3_root.clone())
+            .a3_executable(launch.a3_executable.clone())
+            .port(launch.port.clone())
+            .profile(launch.profile.clone())
+            .mods(launch.mods.clone())
+            .clientsides(launch.clientsides.clone())
+            .server_mods(launch.server_mods.clone())
+            .launch_parameters(launch.parameters.clone())
+            .maybe_server_config(launch.server_config.clone())
+            .maybe_basic_config(launch.basic_config.clone())
+            .maybe_instance(launch.instance.clone())
+            .build()
+    }
+
+    /// Record for a headless client
+    pub fn headless_client(
+        pid: u32,
+        index: u64,
+        a3_root: &Path,
+        a3_executable: &Path,
+        port: &str,
+        server_config: Option<&Path>,
+        instance: Option<&str>,
+    ) -> ProcessRecord {
+        ProcessRecord::builder()
+            .pid(pid)
+            .kind(ProcessKind::HeadlessClient(index))
+            .a3_root(a3_root.to_path_buf())
+            .a3_executable(a3_executable.to_path_buf())
+            .port(port.to_string())
+            .maybe_server_config(server_config.map(Path::to_path_buf))
+            .maybe_instance(instance.map(str::to_string))
+            .build()
+    }
+
+    /// The settings the server was launched with. Keys are managed with the current settings
+    pub fn server_launch(&self, key_settings: &KeySettings) -> ServerLaunch {
+        ServerLaunch::builder()
+            .a3_root(self.a3_root.clone())
+            .a3_executable(self.a3_executable.clone())
+            .port(self.port.clone())
+            .profile(self.profile.clone())
+            .mods(self.mods.clone())
+            .clientsides(self.clientsides.clone())
+            .server_mods(self.server_mods.clone())
+            .parameters(self.launch_parameters.clone())
+            .key_settings(key_settings.clone())
+            .maybe_server_config(self.server_config.clone())
+            .maybe_basic_config(self.basic_config.clone())
+            .maybe_instance(self.instance.clone())
+            .build()
+    }
+
+    /// Kind of process with the instance it belongs to, like "Training HC0"
+    pub fn label(&self) -> String {
+        match &self.instance {
+            Some(instance) => format!("{} {}", instance, self.kind),
+            None => self.kind.to_string(),
+        }
+    }
+
+    /// Folder the process writes its RPT logs to
+    pub fn profile_folder(&self) -> PathBuf {
+        match self.kind {
+            ProcessKind::Server => self.a3_root.join(&self.profile),
+            ProcessKind::HeadlessClient(index) => {
+                hc_profile_folder(&self.a3_root, self.instance.as_deref(), index)
+            }
+        }
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.exited.is_none()
+    }
+
+    /// Exited without being stopped from the launcher
+    pub fn crashed(&self) -> bool {
+        self.exited.is_some() && !self.stopped
+    }
+
+    /// Two records are the same process if pid and start time matches, as pids can be reused
+    fn same_process(&self, other: &ProcessRecord) -> bool {
+        self.pid == other.pid && self.started == other.started
+    }
+}
+
+/// A server running from an A3 root, started by the launcher or not
+#[derive(Debug, Clone, PartialEq)]
+pub struct RunningServer {
+    pub pid: u32,
+    /// what the launcher knows about it, none if it was started some other way
+    pub label: Option<String>,
+}
+
+impl std::fmt::Display for RunningServer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.label {
+            Some(label) => write!(f, "{} (pid {})", label, self.pid),
+            None => write!(f, "server not started by the launcher (pid {})", self.pid),
+        }
+    }
+}
+
+/// A process that was asked to stop, see [`ProcessRegistry::begin_stop`]
+#[derive(Debug)]
+pub struct StopRequest {
+    pub pid: u32,
+    executable: PathBuf,
+    /// handle of a process spawned by this instance of the launcher
+    child: Option<Child>,
+}
+
+impl StopRequest {
+    /// Ask the process to exit, so the server can shut down cleanly, and kill it if it is still
+    /// running after the timeout. Blocks until it is gone. Returns the exit code, if it is known
+    pub fn wait(mut self) -> Result<Option<i32>> {
+        let asked = kill_pid(self.pid, false).is_ok();
+        if asked && let Some(exit_code) = self.wait_for_exit() {
+            return Ok(exit_code);
+        }
+
+        log::warn!("Process {} did not stop when asked, killing it", self.pid);
+        match &mut self.child {
+            Some(child) => {
+                child.kill()?;
+                Ok(child.wait()?.code())
+            }
+            None => {
+                kill_pid(self.pid, true)?;
+                self.wait_for_exit().ok_or_else(|| {
+                    anyhow::Error::msg(format!(
+                        "Process {} did not stop within {} seconds",
+                        self.pid,
+                        STOP_TIMEOUT.as_secs()
+                    ))
+                })
+            }
+        }
+    }
+
+    /// Wait up to the stop timeout for the process to exit. Returns its exit code if it did
+    fn wait_for_exit(&mut self) -> Option<Option<i32>> {
+        let deadline = Instant::now() + STOP_TIMEOUT;
+        loop {
+            let exited = match &mut self.child {
+                Some(child) => match child.try_wait() {
+                    Ok(Some(status)) => Some(status.code()),
+                    Ok(None) => None,
+                    Err(_) => (!is_pid_running(self.pid, &self.executable)).then_some(None),
+                },
+                None => (!is_pid_running(self.pid, &self.executable)).then_some(None),
+            };
+            if exited.is_some() || Instant::now() > deadline {
+                return exited;
+            }
+            std::thread::sleep(Duration::from_millis(250));
+        }
+    }
+}
+
+/// What is needed to launch a process again, worked out while it still runs
+#[derive(Debug, Clone)]
+pub struct Relaunch {
+    pub record: ProcessRecord,
+    /// launch of a server, headless clients only need their record
+    plan: Option<LaunchPlan>,
+}
+
+/// Layout of the registry file on disk
+#[derive(Default, Deserialize, Serialize)]
+struct RegistryFile {
+    #[serde(default)]
+    processes: Vec<ProcessRecord>,
+}
+
+#[derive(Debug, Default)]
+pub struct ProcessRegistry {
+    pub records: Vec<ProcessRecord>,
+    /// Handles for processes spawned by this instance, lets us get exit codes and reap them
+    children: HashMap<u32, Child>,
+    path: PathBuf,
+}
+
+impl ProcessRegistry {
+    /// Load registry from disk and check which of the processes are still alive
+    pub fn load(path: &Path) -> ProcessRegistry {
+        let mut registry = ProcessRegistry {
+            records: Vec::new(),
+            children: HashMap::new(),
+            path: path.to_path_buf(),
+        };
+        registry.records = registry.read_file();
+        registry.refresh();
+        registry
+    }
+
+    fn read_file(&self) -> Vec<ProcessRecord> {
+        let Ok(raw) = fs::read_to_string(&self.path) else {
+            return Vec::new();
+        };
+
+        match toml::from_str::<RegistryFile>(&raw) {
+            Ok(file) => file.processes,
+            Err(err) => {
+                log::error!("Failed to parse process registry {:?}: {}", self.path, err);
+                Vec::new()
+            }
+        }
+    }
+
+    fn save(&self) -> Result<()> {
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let file = RegistryFile {
+            processes: self.records.clone(),
+        };
+        fs::write(&self.path, toml::to_string(&file)?)?;
+        Ok(())
+    }
+
+    /// Add a newly spawned process to the registry
+    pub fn register(&mut self, child: Child, record: ProcessRecord) -> Result<()> {
+        log::info!("Registered {} with pid {}", record.kind, record.pid);
+        self.children.insert(record.pid, child);
+        self.records.push(record);
+        self.save()
+    }
+
+    /// Servers running from the A3 root. Uses the records of the launcher, and the pids
+    /// [`scan_servers`] found running an executable from the A3 root, like servers started by hand
+    pub fn servers_on_root(&self, a3_root: &Path, scanned: &[u32]) -> Vec<RunningServer> {
+        let mut servers: Vec<RunningServer> = self
+            .records
+            .iter()
+            .filter(|r| r.is_running() && r.kind == ProcessKind::Server && r.a3_root == a3_root)
+            .map(|r| RunningServer {
+                pid: r.pid,
+                label: Some(r.label()),
+            })
+            .collect();
+
+        for pid in scanned {
+            // headless clients of the launcher run the same executable
+            if !self.records.iter().any(|r| r.pid == *pid && r.is_running()) {
+                servers.push(RunningServer {
+                    pid: *pid,
+                    label: None,
+                });
+            }
+        }
+        servers
+    }
+
+    /// Game ports of the running servers, leaving out the `replaced` ones that are about to stop
+    pub fn server_ports(&self, replaced: &[u32]) -> Vec<u16> {
+        self.records
+            .iter()
+            .filter(|r| r.is_running() && r.kind == ProcessKind::Server)
+            .filter(|r| !replaced.contains(&r.pid))
+            .filter_map(|r| parse_port(&r.port).ok())
+            .collect()
+    }
+
+    /// Update status of all processes. Also picks up processes registered by other launcher
+    /// instances, like the headless CLI. Returns the records that exited since last refresh
+    pub fn refresh(&mut self) -> Vec<ProcessRecord> {
+        for record in self.read_file() {
+            if !self.records.iter().any(|r| r.same_process(&record)) {
+                self.records.push(record);
+            }
+        }
+
+        let mut exited = Vec::new();
+        for record in self.records.iter_mut().filter(|r| r.is_running()) {
+            let running = match self.children.get_mut(&record.pid) {
+                Some(child) => match child.try_wait() {
+                    Ok(Some(status)) => {
+                        record.exit_code = status.code();
+                        false
+                    }
+                    Ok(None) => true,
+                    Err(_) => is_pid_running(record.pid, &record.a3_executable),
+                },
+                None => is_pid_running(record.pid, &record.a3_executable),
+            };
+
+            if !running {
+                record.exited = Some(Local::now());
+                self.children.remove(&record.pid);
+                log::info!(
+                    "{} with pid {} exited with code {:?}",
+                    record.kind,
+                    record.pid,
+                    record.exit_code
+                );
+                exited.push(record.clone());
+            }
+        }
+
+        if !exited.is_empty()
+            && let Err(err) = self.save()
+        {
+            log::error!("Failed to save process registry: {}", err);
+        }
+
+        exited
+    }
+
+    /// Stop a running process and wait for it to exit, see [`ProcessRegistry::begin_stop`]. Blocks
+    /// for up to twice the stop timeout
+    pub fn stop(&mut self, pid: u32) -> Result<()> {
+        let request = self.begin_stop(pid)?;
+        let result = request.wait();
+        self.finish_stop(pid, result)
+    }
+
+    /// Mark a running process as stopped from the launcher, and hand over what is needed to stop
+    /// it. Waiting for it to exit takes a while, so [`StopRequest::wait`] is done on another
+    /// thread, and [`ProcessRegistry::finish_stop`] records the outcome
+    pub fn begin_stop(&mut self, pid: u32) -> Result<StopRequest> {
+        let Some(record) = self
+            .records
+            .iter_mut()
+            .find(|r| r.pid == pid && r.is_running())
+        else {
+            return Err(anyhow::Error::msg(format!(
+                "No running process with pid {}",
+                pid
+            )));
+        };
+        log::info!("Stopping {} with pid {}", record.kind, pid);
+        // so the exit is not taken for a crash
+        record.stopped = true;
+
+        Ok(StopRequest {
+            pid,
+            executable: record.a3_executable.clone(),
+            child: self.children.remove(&pid),
+        })
+    }
+
+    /// Record the outcome of a [`StopRequest`]. A process that could not be stopped is still
+    /// watched for crashes
+    pub fn finish_stop(&mut self, pid: u32, result: Result<Option<i32>>) -> Result<()> {
+        let record = self
+            .records
+            .iter_mut()
+            .rev()
+            .find(|r| r.pid == pid && r.stopped && r.exit_code.is_none());
+
+        match (record, result) {
+            (Some(record), Ok(exit_code)) => {
+                // the refresh might have noticed the exit already
+                record.exited.get_or_insert_with(Local::now);
+                record.exit_code = exit_code;
+                log::info!(
+                    "{} with pid {} stopped with code {:?}",
+                    record.kind,
+                    pid,
+                    exit_code
+                );
+                self.save()
+            }
+            (Some(record), Err(err)) => {
+                record.stopped = record.exited.is_some();
+                Err(err)
+            }
+            (None, result) => result.map(|_| ()),
+        }
+    }
+
+    /// Work out how to launch a process again, before it is stopped. `scanned` are the servers
+    /// [`scan_servers`] found on the A3 root of a server. Fails if the process is not known, or if a
+    /// server launch can not happen, so it is left running
+    pub fn prepare_restart(
+        &self,
+        pid: u32,
+        key_settings: &KeySettings,
+        scanned: &[u32],
+    ) -> Result<Relaunch> {
+        let Some(record) = self.records.iter().find(|r| r.pid == pid).cloned() else {
+            return Err(anyhow::Error::msg(format!("No process with pid {}", pid)));
+        };
+
+        let plan = match record.kind {
+            ProcessKind::Server => {
+                let launch = record.server_launch(key_settings);
+                let plan = LaunchPlan::build(&launch, &self.server_ports(&[pid]))?;
+                check_keys_in_use(self, &launch, &plan, &[pid], scanned)?;
+                Some(plan)
+            }
+            ProcessKind::HeadlessClient(_) => None,
+        };
+        Ok(Relaunch { record, plan })
+    }
+
+    /// Launch a process again, replacing its record. Returns the pid of the new process
+    pub fn relaunch(&mut self, relaunch: Relaunch) -> Result<u32> {
+        let Relaunch { record, plan } = relaunch;
+        let child = match (record.kind.clone(), plan) {
+            (_, Some(plan)) => plan.execute(&self.server_ports(&[record.pid]))?,
+            (ProcessKind::HeadlessClient(index), None) => launch_hc(
+                &record.a3_root,
+                &record.a3_executable,
+                &record.port,
+                index,
+                record.server_config.as_deref(),
+                record.instance.as_deref(),
+            )?,
+            (ProcessKind::Server, None) => {
+                return Err(anyhow::Error::msg("Server restart has no launch plan"));
+            }
+        };
+
+        // replace the old record with the new process
+        self.records.retain(|r| !r.same_process(&record));
+        let new_pid = child.id();
+        let new_record = ProcessRecord {
+            pid: new_pid,
+            started: Local::now(),
+            exited: None,
+            exit_code: None,
+            stopped: false,
+            restarts: 0,
+            ..record
+        };
+        self.register(child, new_record)?;
+        Ok(new_pid)
+    }
+
+    /// Store a crash with the process it happened to, or the process that replaced it
+    pub fn record_crash(&mut self, pid: u32, crash: Crash, restarts: u32) -> Result<()> {
+        if let Some(record) = self.records.iter_mut().rev().find(|r| r.pid == pid) {
+            record.last_crash = Some(crash);
+            record.restarts = restarts;
+        }
+        self.save()
+    }
+
+    /// Remove a record of an exited process from the list
+    pub fn remove(&mut self, pid: u32) -> Result<()> {
+        self.records.retain(|r| r.pid != pid || r.is_running());
+        self.save()
+    }
+
+    /// Records of processes that are currently running
+    pub fn running(&self) -> impl Iterator<Item = &ProcessRecord> {
+        self.records.iter().filter(|r| r.is_running())
+    }
+}
+
+/// Check if a process with the pid exists and is running the given executable
+#[cfg(target_os = "linux")]
+fn is_pid_running(pid: u32, executable: &Path) -> bool {
+    let proc_path = PathBuf::from(format!("/proc/{}", pid));
+
+    // zombie processes are exited, they just have not been reaped yet
+    if let Ok(stat) = fs::read_to_string(proc_path.join("stat"))
+        && let Some((_, state)) = stat.rsplit_once(')')
+        && state.trim_start().starts_with('Z')
+    {
+        return false;
+    }
+
+    match fs::read_link(proc_path.join("exe")) {
+        Ok(exe) => exe == fs::canonicalize(executable).unwrap_or(executable.to_path_buf()),
+        // we might not be allowed to read the link, so fall back to the pid existing
+        Err(_) => proc_path.exists(),
+    }
+}
+
+/// Check if a process with the pid exists and is running the given executable
+#[cfg(target_os = "windows")]
+fn is_pid_running(pid: u32, executable: &Path) -> bool {
+    use std::os::windows::process::CommandExt;
+
+    let output = Command::new("tasklist")
+        .args(["/FI", &format!("PID eq {}", pid), "/FO", "CSV", "/NH"])
+        .creation_flags(CREATE_NO_WINDOW)
+        .stdin(Stdio::null())
+        .output();
+
+    let Ok(output) = output else {
+        return false;
+    };
+    let stdout = String::from_utf8_lossy(&output.stdout).to_lowercase();
+    let image_name = executable
+        .file_name()
+        .map(|name| name.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+
+    stdout.contains(&format!("\"{}\"", pid)) && stdout.contains(&image_name)
+}
+
+/// What [`ProcessRegistry::prepare_restart`] needs for a server that takes a while: the servers
+/// [`scan_servers`] finds on its A3 root, and the configs of its mods read into the cache for the
+/// dependency check. The GUI runs it in the background. Headless clients need neither
+pub fn scan_for_restart(record: &ProcessRecord, key_settings: &KeySettings) -> Vec<u32> {
+    if record.kind != ProcessKind::Server {
+        return Vec::new();
+    }
+    let launch = record.server_launch(key_settings);
+    for mod_path in launch.mods.iter().chain(launch.server_mods.iter()) {
+        cache_patches(&mod_folder(&launch.a3_root, mod_path));
+    }
+    scan_servers(&record.a3_root)
+}
+
+/// Pids of servers running an executable from the A3 root. Headless clients are skipped, as they
+/// do not use the keys folder. Takes a while on windows, so the GUI runs it in the background
+#[cfg(target_os = "linux")]
+pub fn scan_servers(a3_root: &Path) -> Vec<u32> {
+    let root = fs::canonicalize(a3_root).unwrap_or(a3_root.to_path_buf());
+    let Ok(entries) = fs::read_dir("/proc") else {
+        return Vec::new();
+    };
+
+    entries
+        .flatten()
+        .filter_map(|entry| entry.file_name().to_str()?.parse::<u32>().ok())
+        .filter(|pid| {
+            let proc_path = PathBuf::from(format!("/proc/{}", pid));
+            // processes of other users can not be read, the records of the launcher cover ours
+            let Ok(exe) = fs::read_link(proc_path.join("exe")) else {
+                return false;
+            };
+            let cmdline = fs::read(proc_path.join("cmdline")).unwrap_or_default();
+            exe.starts_with(&root) && !cmdline.split(|b| *b == 0).any(|arg| arg == b"-client")
+        })
+        .collect()
+}
+
+/// Pids of servers running an executable from the A3 root. Headless clients are skipped, as they
+/// do not use the keys folder. Takes a while on windows, so the GUI runs it in the background
+#[cfg(target_os = "windows")]
+pub fn scan_servers(a3_root: &Path) -> Vec<u32> {
+    use std::os::windows::process::CommandExt;
+
+    let output = Command::new("powershell")
+        .args([
+            "-NoProfile",
+            "-Command",
+            "Get-CimInstance Win32_Process | ForEach-Object { \"$($_.ProcessId)`t$($_.ExecutablePath)`t$($_.CommandLine)\" }",
+        ])
+        .creation_flags(CREATE_NO_WINDOW)
+        .stdin(Stdio::null())
+        .output();
+    let Ok(output) = output else {
+        return Vec::new();
+    };
+
+    // paths are not case sensitive on windows
+    let root = a3_root.to_string_lossy().to_lowercase();
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.splitn(3, '\t');
+            let pid = fields.next()?.trim().parse::<u32>().ok()?;
+            let exe = fields.next()?.to_lowercase();
+            let cmdline = fields.next().unwrap_or_default().to_lowercase();
+            (!exe.is_empty()
+                && Path::new(&exe).starts_with(&root)
+                && !cmdline.split_whitespace().any(|arg| arg == "-client"))
+            .then_some(pid)
+        })
+        .collect()
+}
+
+/// Ask a process to exit, or kill it with `force`
+#[cfg(target_os = "linux")]
+fn kill_pid(pid: u32, force: bool) -> Result<()> {
+    let signal = match force {
+        true => "-KILL",
+        false => "-TERM",
+    };
+    let status = Command::new("kill")
+        .args([signal, &pid.to_string()])
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()?;
+    match status.success() {
+        true => Ok(()),
+        false => Err(anyhow::Error::msg(format!(
+            "Failed to stop process {}",
+            pid
+        ))),
+    }
+}
+
+/// Ask a process to exit, or kill it with `force`
+#[cfg(target_os = "windows")]
+fn kill_pid(pid: u32, force: bool) -> Result<()> {
+    use std::os::windows::process::CommandExt;
+
+    let pid = pid.to_string();
+    let mut args = vec!["/PID", &pid, "/T"];
+    if force {
+        args.push("/F");
+    }
+    let status = Command::new("taskkill")
+        .args(args)
+        .creation_flags(CREATE_NO_WINDOW)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()?;
+    match status.success() {
+        true => Ok(()),
+        false => Err(anyhow::Error::msg(format!(
+            "Failed to stop process {}",
+            pid
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Pid no process has, pids stay far below this
+    const NO_PID: u32 = u32::MAX - 7;
+
+    fn record(pid: u32, kind: ProcessKind) -> ProcessRecord {
+        ProcessRecord::builder()
+            .pid(pid)
+            .kind(kind)
+            .a3_root(PathBuf::from("/nonexistent/a3"))
+            .a3_executable(PathBuf::from("/nonexistent/a3/arma3server_x64"))
+            .port("2302".to_string())
+            .build()
+    }
+
+    fn registry_path(name: &str) -> PathBuf {
+        let path =
+            std::env::temp_dir().join(format!("corvus_test_{}_{}.toml", name, std::process::id()));
+        let _ = fs::remove_file(&path);
+        path
+    }
+
+    #[test]
+    fn test_registry_round_trip() {
+        let path = registry_path("registry");
+        let mut registry = ProcessRegistry::load(&path);
+        assert!(registry.records.is_empty());
+
+        let mut server = record(NO_PID, ProcessKind::Server);
+        server.exited = Some(Local::now());
+        server.exit_code = Some(0);
+        server.stopped = true;
+        let mut hc = record(NO_PID - 1, ProcessKind::HeadlessClient(1));
+        hc.exited = Some(Local::now());
+        hc.restarts = 2;
+        hc.last_crash = Some(Crash {
+            time: Local::now(),
+            exit_code: Some(1),
+            last_lines: vec!["Out of memory".to_string()],
+        });
+        // still running as far as the registry knows
+        let running = record(NO_PID - 2, ProcessKind::Server);
+        registry.records = vec![server, hc, running];
+        registry.save().unwrap();
+
+        let loaded = ProcessRegistry::load(&path);
+        assert_eq!(loaded.records.len(), 3);
+        for (saved, read) in registry.records.iter().zip(&loaded.records) {
+            assert!(saved.same_process(read));
+            assert_eq!(saved.kind, read.kind);
+            assert_eq!(saved.last_crash, read.last_crash);
+        }
+        assert_eq!(loaded.records[0].exit_code, Some(0));
+        assert!(!loaded.records[0].crashed());
+        assert_eq!(loaded.records[1].restarts, 2);
+        // loading checks which processes still run
+        assert!(loaded.records[2].crashed());
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_refresh_and_remove() {
+        let path = registry_path("refresh");
+        let mut registry = ProcessRegistry::load(&path);
+        registry.records.push(record(NO_PID, ProcessKind::Server));
+
+        // the exit is reported once
+        let exited = registry.refresh();
+        assert_eq!(exited.len(), 1);
+        assert!(exited[0].crashed());
+        assert!(registry.refresh().is_empty());
+
+        // processes registered by another launcher, like the CLI, are picked up once
+        let mut other = ProcessRegistry::load(&path);
+        let mut hc = record(NO_PID - 1, ProcessKind::HeadlessClient(0));
+        hc.exited = Some(Local::now());
+        other.records.push(hc);
+        other.save().unwrap();
+        registry.refresh();
+        registry.refresh();
+        assert_eq!(registry.records.len(), 2);
+
+        // running processes stay in the list
+        registry
+            .records
+            .push(record(NO_PID - 1, ProcessKind::HeadlessClient(0)));
+        registry.remove(NO_PID - 1).unwrap();
+        assert_eq!(registry.records.len(), 2);
+        assert!(registry.records[1].is_running());
+        assert_eq!(ProcessRegistry::load(&path).records.len(), 2);
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_server_ports() {
+        let mut registry = ProcessRegistry::default();
+        let mut exited = record(NO_PID - 1, ProcessKind::Server);
+        exited.port = "2402".to_string();
+        exited.exited = Some(Local::now());
+        let mut hc = record(NO_PID - 2, ProcessKind::HeadlessClient(0));
+        hc.port = "2502".to_string();
+        registry.records = vec![record(NO_PID, ProcessKind::Server), exited, hc];
+
+        // only running servers hold their ports, and not when they are about to be replaced
+        assert_eq!(registry.server_ports(&[]), vec![2302]);
+        assert!(registry.server_ports(&[NO_PID]).is_empty());
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_restart() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let a3_root =
+            std::env::temp_dir().join(format!("corvus_test_restart_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&a3_root);
+        fs::create_dir_all(&a3_root).unwrap();
+        fs::write(a3_root.join("server.cfg"), "password = \"secret\";\n").unwrap();
+        // stands in for the server, ignores the arguments
+        let executable = a3_root.join("arma3server_x64");
+        fs::write(&executable, "#!/bin/sh\nexec sleep 30\n").unwrap();
+        fs::set_permissions(&executable, fs::Permissions::from_mode(0o755)).unwrap();
+
+        let path = registry_path("restart");
+        let mut registry = ProcessRegistry::load(&path);
+        let child = Command::new(&executable).spawn().unwrap();
+        let pid = child.id();
+        let mut hc =
+            ProcessRecord::headless_client(pid, 0, &a3_root, &executable, "2302", None, None);
+        hc.restarts = 3;
+        registry.register(child, hc).unwrap();
+
+        let relaunch = registry
+            .prepare_restart(pid, &KeySettings::default(), &[])
+            .unwrap();
+        registry.stop(pid).unwrap();
+        let new_pid = registry.relaunch(relaunch).unwrap();
+        assert_ne!(new_pid, pid);
+        assert_eq!(registry.records.len(), 1);
+        let restarted = &registry.records[0];
+        assert_eq!(restarted.pid, new_pid);
+        assert_eq!(restarted.kind, ProcessKind::HeadlessClient(0));
+        assert_eq!(restarted.restarts, 0);
+        assert!(restarted.is_running() && !restarted.stopped);
+
+        registry.stop(new_pid).unwrap();
+        let stopped = &registry.records[0];
+        assert!(!stopped.is_running());
+        assert!(!stopped.crashed());
+        assert!(registry.stop(new_pid).is_err());
+
+        fs::remove_file(&path).unwrap();
+        fs::remove_dir_all(&a3_root).unwrap();
+    }
+}
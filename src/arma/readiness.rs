@@ -0,0 +1,115 @@
+//! Waiting for a server that was just launched to be ready for clients, by watching its RPT log
+use crate::arma::rpt::{RptTail, newest_rpt};
+use anyhow::Result;
+use std::path::PathBuf;
+use std::time::{Duration, Instant, SystemTime};
+
+/// Lines the server writes to its RPT once clients can connect
+const READY_MARKERS: [&str; 2] = ["Host identity created", "Mission read"];
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Readiness {
+    Waiting,
+    /// ready, with the log line that said so
+    Ready(String),
+    TimedOut,
+}
+
+#[derive(Debug, Clone)]
+pub struct ReadinessWatch {
+    profile_folder: PathBuf,
+    /// RPT files from before the launch belong to an earlier run
+    launched: SystemTime,
+    timeout: Duration,
+    deadline: Instant,
+    /// the log of this run, once the server has created it
+    pub rpt: Option<RptTail>,
+}
+
+impl ReadinessWatch {
+    /// Create before launching the server, so its RPT is not mistaken for an older one
+    pub fn new(profile_folder: PathBuf, timeout: Duration) -> ReadinessWatch {
+        ReadinessWatch {
+            profile_folder,
+            launched: SystemTime::now(),
+            timeout,
+            deadline: Instant::now() + timeout,
+            rpt: None,
+        }
+    }
+
+    /// How long the server has to get ready
+    pub fn timeout(&self) -> Duration {
+        self.timeout
+    }
+
+    /// Check the log for new lines, call repeatedly until it is no longer waiting
+    pub fn poll(&mut self) -> Result<Readiness> {
+        if self.rpt.is_none() {
+            self.rpt = newest_rpt(&self.profile_folder, Some(self.launched)).map(RptTail::new);
+        }
+
+        if let Some(rpt) = &mut self.rpt {
+            let ready_line = rpt
+                .read_new_lines()?
+                .into_iter()
+                .find(|line| READY_MARKERS.iter().any(|marker| line.contains(marker)));
+            if let Some(line) = ready_line {
+                return Ok(Readiness::Ready(line.trim().to_string()));
+            }
+        }
+
+        match Instant::now() >= self.deadline {
+            true => Ok(Readiness::TimedOut),
+            false => Ok(Readiness::Waiting),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::{self, File, OpenOptions};
+    use std::io::Write;
+
+    #[test]
+    fn test_poll() {
+        let folder =
+            std::env::temp_dir().join(format!("corvus_test_readiness_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&folder);
+        fs::create_dir_all(&folder).unwrap();
+
+        // the log of an earlier run says it was ready, but does not count
+        let old = folder.join("arma3server_old.rpt");
+        fs::write(&old, "Host identity created.\n").unwrap();
+        File::options()
+            .write(true)
+            .open(&old)
+            .unwrap()
+            .set_modified(SystemTime::now() - Duration::from_secs(3600))
+            .unwrap();
+
+        let mut watch = ReadinessWatch::new(folder.clone(), Duration::from_secs(60));
+        assert_eq!(watch.timeout(), Duration::from_secs(60));
+        assert_eq!(watch.poll().unwrap(), Readiness::Waiting);
+        assert!(watch.rpt.is_none());
+
+        let new = folder.join("arma3server_new.rpt");
+        fs::write(&new, " 9:00:00 Loading mods\r\n").unwrap();
+        assert_eq!(watch.poll().unwrap(), Readiness::Waiting);
+        assert!(watch.rpt.is_some());
+
+        let mut file = OpenOptions::new().append(true).open(&new).unwrap();
+        write!(file, " 9:00:05 Mission read.\r\n").unwrap();
+        assert_eq!(
+            watch.poll().unwrap(),
+            Readiness::Ready("9:00:05 Mission read.".to_string())
+        );
+
+        // nothing new in the log after the timeout
+        let mut watch = ReadinessWatch::new(folder.clone(), Duration::ZERO);
+        assert_eq!(watch.poll().unwrap(), Readiness::TimedOut);
+
+        fs::remove_dir_all(&folder).unwrap();
+    }
+}
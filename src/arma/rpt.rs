@@ -0,0 +1,231 @@
+//! Reading the RPT logs the server and headless clients write to their profile folder
+use anyhow::Result;
+use std::fs::{self, File};
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+/// The most recently written RPT file in a profile folder. With `since`, only files written to
+/// after that time are considered, to skip the logs of earlier runs
+pub fn newest_rpt(folder: &Path, since: Option<SystemTime>) -> Option<PathBuf> {
+    fs::read_dir(folder)
+        .ok()?
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| {
+            entry
+                .path()
+                .extension()
+                .is_some_and(|ext| ext.eq_ignore_ascii_case("rpt"))
+        })
+        .filter_map(|entry| Some((entry.metadata().ok()?.modified().ok()?, entry.path())))
+        .filter(|(modified, _)| since.is_none_or(|since| *modified >= since))
+        .max_by_key(|(modified, _)| *modified)
+        .map(|(_, path)| path)
+}
+
+/// How far from the end of a log to look for its last lines
+const TAIL_BYTES: u64 = 64 * 1024;
+
+/// The last `count` lines of a log, without reading all of it
+pub fn last_lines(path: &Path, count: usize) -> Result<Vec<String>> {
+    let mut file = File::open(path)?;
+    let start = file.metadata()?.len().saturating_sub(TAIL_BYTES);
+    file.seek(SeekFrom::Start(start))?;
+
+    let mut buffer = Vec::new();
+    file.read_to_end(&mut buffer)?;
+    let text = String::from_utf8_lossy(&buffer);
+
+    let lines: Vec<String> = text
+        .lines()
+        .map(|line| line.trim_end_matches('\r').to_string())
+        .collect();
+    Ok(lines[lines.len().saturating_sub(count)..].to_vec())
+}
+
+/// Follows an RPT file as it is written, returning only complete lines
+#[derive(Debug, Clone)]
+pub struct RptTail {
+    pub path: PathBuf,
+    /// bytes read so far
+    offset: u64,
+    /// start of a line that has not been finished yet
+    partial: String,
+    /// started in the middle of a line, which is dropped
+    skip_partial: bool,
+}
+
+impl RptTail {
+    pub fn new(path: PathBuf) -> RptTail {
+        RptTail {
+            path,
+            offset: 0,
+            partial: String::new(),
+            skip_partial: false,
+        }
+    }
+
+    /// Follow the log from near its end, the same [`TAIL_BYTES`] [`last_lines`] looks at. Logs
+    /// of servers that ran for a while are too big to read from the start
+    pub fn from_end(path: PathBuf) -> RptTail {
+        let offset = fs::metadata(&path)
+            .map(|metadata| metadata.len().saturating_sub(TAIL_BYTES))
+            .unwrap_or(0);
+        RptTail {
+            path,
+            offset,
+            partial: String::new(),
+            skip_partial: offset > 0,
+        }
+    }
+
+    /// Lines written since the last call
+    pub fn read_new_lines(&mut self) -> Result<Vec<String>> {
+        let mut file = File::open(&self.path)?;
+
+        // file was replaced or truncated, start over
+        if file.metadata()?.len() < self.offset {
+            self.offset = 0;
+            self.partial.clear();
+            self.skip_partial = false;
+        }
+
+        file.seek(SeekFrom::Start(self.offset))?;
+        let mut buffer = Vec::new();
+        file.read_to_end(&mut buffer)?;
+        self.offset += buffer.len() as u64;
+
+        let text = std::mem::take(&mut self.partial) + &String::from_utf8_lossy(&buffer);
+        let mut lines: Vec<String> = text
+            .split('\n')
+            .map(|line| line.trim_end_matches('\r').to_string())
+            .collect();
+        // the last piece has no line ending yet
+        self.partial = lines.pop().unwrap_or_default();
+        if self.skip_partial && !lines.is_empty() {
+            lines.remove(0);
+            self.skip_partial = false;
+        }
+
+        Ok(lines)
+    }
+}
+
+/// Severity of an RPT line, ordered from least to most severe
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    #[default]
+    Info,
+    Warning,
+    Error,
+    /// errors from SQF scripts, like undefined variables
+    ScriptError,
+}
+
+impl Severity {
+    pub const ALL: [Severity; 4] = [
+        Severity::Info,
+        Severity::Warning,
+        Severity::Error,
+        Severity::ScriptError,
+    ];
+
+    pub fn of(line: &str) -> Severity {
+        // script errors are logged as a block, with the details indented below the first line
+        if line.contains("Error in expression")
+            || line.contains("Error position:")
+            || line.contains("  Error ")
+        {
+            return Severity::ScriptError;
+        }
+
+        let lowercase = line.to_lowercase();
+        if lowercase.contains("error") {
+            Severity::Error
+        } else if lowercase.contains("warning") {
+            Severity::Warning
+        } else {
+            Severity::Info
+        }
+    }
+}
+
+impl std::fmt::Display for Severity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Severity::Info => write!(f, "Info"),
+            Severity::Warning => write!(f, "Warning"),
+            Severity::Error => write!(f, "Error"),
+            Severity::ScriptError => write!(f, "Script error"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    #[test]
+    fn test_rpt_tail() {
+        let path = std::env::temp_dir().join(format!("corvus_test_{}.rpt", std::process::id()));
+        let mut file = File::create(&path).unwrap();
+        let mut tail = RptTail::new(path.clone());
+
+        // lines are only returned once they are finished
+        write!(file, "first line\r\nsecond").unwrap();
+        assert_eq!(tail.read_new_lines().unwrap(), vec!["first line"]);
+        write!(file, " line\r\n").unwrap();
+        assert_eq!(tail.read_new_lines().unwrap(), vec!["second line"]);
+        assert!(tail.read_new_lines().unwrap().is_empty());
+
+        // a new file with the same name starts over
+        fs::write(&path, "new\n").unwrap();
+        assert_eq!(tail.read_new_lines().unwrap(), vec!["new"]);
+
+        // a long log is followed from near its end, starting with a whole line
+        let long = "a line of the log\r\n".repeat(TAIL_BYTES as usize);
+        fs::write(&path, &long).unwrap();
+        let mut tail = RptTail::from_end(path.clone());
+        let lines = tail.read_new_lines().unwrap();
+        assert!(!lines.is_empty() && lines.len() < TAIL_BYTES as usize / 10);
+        assert!(lines.iter().all(|line| line == "a line of the log"));
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_last_lines() {
+        let path = std::env::temp_dir().join(format!("corvus_last_{}.rpt", std::process::id()));
+        fs::write(&path, "one\r\ntwo\r\nthree\r\n").unwrap();
+
+        assert_eq!(last_lines(&path, 2).unwrap(), vec!["two", "three"]);
+        assert_eq!(last_lines(&path, 10).unwrap().len(), 3);
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_severity() {
+        assert_eq!(
+            Severity::of(" 9:00:01 Host identity created."),
+            Severity::Info
+        );
+        assert_eq!(
+            Severity::of(" 9:00:01 Warning Message: No entry 'bin\\config.bin/CfgWeapons.x'."),
+            Severity::Warning
+        );
+        assert_eq!(
+            Severity::of(" 9:00:01 Error: Object(2 : 3) not found"),
+            Severity::Error
+        );
+        assert_eq!(
+            Severity::of(" 9:00:01 Error in expression <_x setDamage 1>"),
+            Severity::ScriptError
+        );
+        assert_eq!(
+            Severity::of(" 9:00:01   Error Undefined variable in expression: _x"),
+            Severity::ScriptError
+        );
+    }
+}
@@ -0,0 +1,182 @@
+//! Works out when scheduled launches are due, and runs them: whatever runs on the port of the job
+//! is stopped, then the server is launched the same way as from the GUI
+use crate::Config;
+use crate::arma::instances::check_keys_in_use;
+use crate::arma::launch_plan::LaunchPlan;
+use crate::arma::processes::{ProcessRecord, ProcessRegistry, StopRequest};
+use crate::arma::readiness::ReadinessWatch;
+use crate::arma::selection::select_launch;
+use crate::arma::server_launch::ServerLaunch;
+use crate::configs::schedule::ScheduledJob;
+use anyhow::{Context, Result};
+use chrono::{DateTime, Local};
+use croner::Cron;
+use std::str::FromStr;
+use std::time::Duration;
+
+/// Server started by a scheduled job, HCs still have to be started once it is ready
+#[derive(Debug)]
+pub struct StartedJob {
+    pub pid: u32,
+    pub launch: ServerLaunch,
+    pub watch: ReadinessWatch,
+}
+
+/// The first time after `after` the job should run
+pub fn next_run(job: &ScheduledJob, after: &DateTime<Local>) -> Result<DateTime<Local>> {
+    let cron = Cron::from_str(&job.cron)
+        .with_context(|| format!("Invalid schedule for {}: {}", job.name, job.cron))?;
+    cron.find_next_occurrence(after, false)
+        .with_context(|| format!("No next run for {}: {}", job.name, job.cron))
+}
+
+/// Enabled jobs with their next run, soonest first. Jobs with an invalid schedule come last
+pub fn upcoming<'a>(
+    jobs: &'a [ScheduledJob],
+    now: &DateTime<Local>,
+) -> Vec<(&'a ScheduledJob, Result<DateTime<Local>>)> {
+    let mut upcoming: Vec<_> = jobs
+        .iter()
+        .filter(|job| job.enabled)
+        .map(|job| (job, next_run(job, now)))
+        .collect();
+    upcoming.sort_by_key(|(_, next)| match next {
+        Ok(time) => (false, Some(*time)),
+        Err(_) => (true, None),
+    });
+    upcoming
+}
+
+/// Enabled jobs that should have run after `last_check`, up to and including `now`
+pub fn due<'a>(
+    jobs: &'a [ScheduledJob],
+    last_check: &DateTime<Local>,
+    now: &DateTime<Local>,
+) -> Vec<&'a ScheduledJob> {
+    jobs.iter()
+        .filter(|job| job.enabled)
+        .filter(|job| matches!(next_run(job, last_check), Ok(next) if next <= *now))
+        .collect()
+}
+
+/// Launch of a due job that passed its checks, to start once the processes it replaces stopped
+#[derive(Debug)]
+pub struct ReadyJob {
+    pub name: String,
+    /// HCs to start once the server is ready
+    pub hcs: u64,
+    pub launch: ServerLaunch,
+    pub plan: LaunchPlan,
+    ready_timeout: Duration,
+}
+
+/// Check the launch of the job, then start stopping everything running on its port or instance.
+/// `scanned` are the servers [`scan_servers`](crate::arma::processes::scan_servers) found on the
+/// A3 root of the job. Stopping takes a while, so the requests are waited for by the caller before
+/// [`start_job`]
+pub fn prepare_job(
+    job: &ScheduledJob,
+    config: &Config,
+    registry: &mut ProcessRegistry,
+    scanned: &[u32],
+) -> Result<(ReadyJob, Vec<StopRequest>)> {
+    // check the launch before stopping the running server, so a broken job leaves it running
+    let (launch, mod_warnings) = select_launch(config, &job.selection)?;
+    let running: Vec<u32> = registry
+        .records
+        .iter()
+        .filter(|r| r.is_running())
+        .filter(|r| {
+            r.port == launch.port || (r.instance.is_some() && r.instance == launch.instance)
+        })
+        .map(|r| r.pid)
+        .collect();
+    // the ports of the servers the job replaces are free once they stopped
+    let mut plan = LaunchPlan::build(&launch, &registry.server_ports(&running))?;
+    plan.warnings.extend(mod_warnings);
+    for warning in &plan.warnings {
+        log::warn!("{}: {}", job.name, warning);
+    }
+
+    // servers of other instances on the A3 root would lose their keys
+    check_keys_in_use(registry, &launch, &plan, &running, scanned)?;
+    let stops = running
+        .into_iter()
+        .map(|pid| registry.begin_stop(pid))
+        .collect::<Result<Vec<_>>>()?;
+
+    let ready = ReadyJob {
+        name: job.name.clone(),
+        hcs: job.hcs,
+        launch,
+        plan,
+        ready_timeout: config.hcs.ready_timeout(),
+    };
+    Ok((ready, stops))
+}
+
+/// Launch the server of a job, once the processes it replaces are stopped
+pub fn start_job(job: ReadyJob, registry: &mut ProcessRegistry) -> Result<StartedJob> {
+    // watch for the log of this launch, not an older one
+    let watch = ReadinessWatch::new(
+        job.launch.a3_root.join(&job.launch.profile),
+        job.ready_timeout,
+    );
+    let child = job.plan.execute(&registry.server_ports(&[]))?;
+    let pid = child.id();
+    let record = ProcessRecord::server(pid, &job.launch);
+    registry.register(child, record)?;
+
+    Ok(StartedJob {
+        pid,
+        launch: job.launch,
+        watch,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn job(name: &str, cron: &str) -> ScheduledJob {
+        ScheduledJob {
+            name: name.to_string(),
+            cron: cron.to_string(),
+            enabled: true,
+            selection: Default::default(),
+            hcs: 0,
+        }
+    }
+
+    #[test]
+    fn test_due() {
+        let jobs = vec![
+            job("nightly", "0 4 * * *"),
+            job("event", "0 18 * * 6"),
+            job("broken", "not a cron"),
+        ];
+
+        // friday 2026-10-16
+        let before = Local.with_ymd_and_hms(2026, 10, 16, 3, 59, 30).unwrap();
+        let after = Local.with_ymd_and_hms(2026, 10, 16, 4, 0, 0).unwrap();
+        let names: Vec<&str> = due(&jobs, &before, &after)
+            .iter()
+            .map(|j| j.name.as_str())
+            .collect();
+        assert_eq!(names, vec!["nightly"]);
+
+        // already ran at 4:00, not due again on the next check
+        let later = Local.with_ymd_and_hms(2026, 10, 16, 4, 0, 10).unwrap();
+        assert!(due(&jobs, &after, &later).is_empty());
+
+        let upcoming = upcoming(&jobs, &after);
+        assert_eq!(upcoming[0].0.name, "nightly");
+        assert_eq!(
+            upcoming[0].1.as_ref().unwrap(),
+            &Local.with_ymd_and_hms(2026, 10, 17, 4, 0, 0).unwrap()
+        );
+        assert_eq!(upcoming[1].0.name, "event");
+        assert!(upcoming[2].1.is_err());
+    }
+}
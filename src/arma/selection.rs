@@ -0,0 +1,131 @@
+//! Launches where the modlists and profile are chosen by name instead of in the GUI, like from the
+//! command line or the scheduler
+use crate::arma::launch_plan::LaunchPlan;
+use crate::arma::load_order::order_selected;
+use crate::arma::mod_conflicts::{check_conflicts, combine_mods};
+use crate::arma::server_launch::ServerLaunch;
+use crate::arma::server_modlist::{load_modlists, select_by_name};
+use crate::arma::validation::check_modlists;
+use crate::configs::schedule::LaunchSelection;
+use crate::{Config, ServerModList};
+use anyhow::Result;
+
+/// A modlist or profile given by name does not exist
+#[derive(Debug)]
+pub struct UnknownSelection(pub String);
+
+impl std::fmt::Display for UnknownSelection {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl std::error::Error for UnknownSelection {}
+
+/// Load the selected modlists and work out the launch, the same checks as a launch from the GUI.
+/// `reserved` are the game ports of the running servers of the launcher
+pub fn prepare_launch(
+    config: &Config,
+    selection: &LaunchSelection,
+    reserved: &[u16],
+) -> Result<(ServerLaunch, LaunchPlan)> {
+    let (launch, mod_warnings) = select_launch(config, selection)?;
+    let mut plan = LaunchPlan::build(&launch, reserved)?;
+    plan.warnings.extend(mod_warnings);
+    Ok((launch, plan))
+}
+
+/// The launch of the selected modlists and profile, with warnings about the mods. Fails if a name
+/// is unknown or the mods can not be launched
+pub fn select_launch(
+    config: &Config,
+    selection: &LaunchSelection,
+) -> Result<(ServerLaunch, Vec<String>)> {
+    let instance = match &selection.instance {
+        Some(name) => match config.instance(name) {
+            Some(instance) => Some(instance),
+            None => {
+                let names: Vec<&str> = config.instances.iter().map(|i| i.name.as_str()).collect();
+                return Err(UnknownSelection(format!(
+                    "Instance not found: {}. Available: {}",
+                    name,
+                    names.join(", ")
+                ))
+                .into());
+            }
+        },
+        None => None,
+    };
+    let instance_name = selection.instance.as_deref();
+    let a3_root = config.instance_a3_root(instance_name);
+
+    // load all lists, presets are matched against the mods of the instance, and select the ones
+    // requested by name
+    let mut modpacks = load_modlists(&config.folder_modlists, &a3_root);
+    let mut clientsides = load_modlists(&config.folder_clientside, &a3_root);
+    let mut servermods = load_modlists(&config.folder_servermods, &a3_root);
+
+    select_by_name(&mut modpacks, &selection.modpacks)
+        .and_then(|_| select_by_name(&mut clientsides, &selection.clientsides))
+        .and_then(|_| select_by_name(&mut servermods, &selection.servermods))
+        .map_err(|err| UnknownSelection(err.to_string()))?;
+
+    // use the profile of the instance, or the first profile if none is given, same as the GUI
+    let profile = match selection
+        .profile
+        .as_ref()
+        .or(instance.and_then(|i| i.profile.as_ref()))
+    {
+        Some(profile) if config.server_profiles.contains(profile) => profile.clone(),
+        Some(profile) => {
+            return Err(UnknownSelection(format!(
+                "Server profile not found: {}. Available: {}",
+                profile,
+                config.server_profiles.join(", ")
+            ))
+            .into());
+        }
+        None => match config.server_profiles.first() {
+            Some(profile) => profile.clone(),
+            None => return Err(UnknownSelection("No server profiles in config".to_string()).into()),
+        },
+    };
+
+    // halt on missing or empty mod folders, before the keys folder is touched
+    let lists: Vec<&ServerModList> = modpacks
+        .iter()
+        .chain(clientsides.iter())
+        .chain(servermods.iter())
+        .collect();
+    let mut mod_warnings = check_modlists(&a3_root, &lists)?;
+
+    // each mod once, and none that can not be loaded together
+    let selected = combine_mods(&a3_root, &modpacks, &clientsides, &servermods);
+    check_conflicts(&config.conflicts, &selected)?;
+    let selected = order_selected(&config.load_order, selected)?;
+    mod_warnings.extend(selected.warnings);
+
+    let port = match (&selection.port, instance) {
+        (Some(port), _) => port.clone(),
+        (None, Some(instance)) => instance.port.clone(),
+        (None, None) => "2302".to_string(),
+    };
+
+    let settings = config.profile_settings(&profile);
+    let launch = ServerLaunch::builder()
+        .a3_executable(config.instance_executable(instance_name))
+        .a3_root(a3_root.clone())
+        .port(port)
+        .maybe_instance(selection.instance.clone())
+        .parameters(settings.launch_parameters)
+        .maybe_server_config(settings.server_config)
+        .maybe_basic_config(settings.basic_config)
+        .key_settings(config.keys.clone())
+        .profile(profile)
+        .mods(selected.mods)
+        .clientsides(selected.clientsides)
+        .server_mods(selected.server_mods)
+        .build();
+
+    Ok((launch, mod_warnings))
+}
@@ -0,0 +1,473 @@
+//! Parser for the Arma config syntax used by server.cfg. Produces a [`ServerConfig`] that can be
+//! written back to text. Comments and formatting are not kept, parsing the written text gives
+//! the same config again
+use anyhow::Result;
+use std::fs;
+use std::path::Path;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConfigValue {
+    String(String),
+    Number(f64),
+    /// unquoted value, like `true` or `false`
+    Word(String),
+    Array(Vec<ConfigValue>),
+}
+
+impl ConfigValue {
+    /// text of a string or unquoted value
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            ConfigValue::String(s) | ConfigValue::Word(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    pub fn as_number(&self) -> Option<f64> {
+        match self {
+            ConfigValue::Number(n) => Some(*n),
+            _ => None,
+        }
+    }
+}
+
+impl std::fmt::Display for ConfigValue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            // quotes inside strings are escaped by doubling them
+            ConfigValue::String(s) => write!(f, "\"{}\"", s.replace('"', "\"\"")),
+            ConfigValue::Number(n) => write!(f, "{}", n),
+            ConfigValue::Word(w) => write!(f, "{}", w),
+            ConfigValue::Array(values) => {
+                let values: Vec<String> = values.iter().map(ConfigValue::to_string).collect();
+                write!(f, "{{{}}}", values.join(", "))
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConfigEntry {
+    /// `name = value;` or `name[] = {...};` for arrays
+    Property { name: String, value: ConfigValue },
+    /// `class name : base { ... };`
+    Class {
+        name: String,
+        base: Option<String>,
+        entries: Vec<ConfigEntry>,
+    },
+}
+
+/// A parsed server.cfg. Names are case insensitive, like in Arma
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ServerConfig {
+    pub entries: Vec<ConfigEntry>,
+}
+
+impl ServerConfig {
+    pub fn parse(text: &str) -> Result<ServerConfig> {
+        let mut parser = Parser {
+            chars: text.chars().collect(),
+            pos: 0,
+            line: 1,
+        };
+        Ok(ServerConfig {
+            entries: parser.entries(false)?,
+        })
+    }
+
+    pub fn load(path: &Path) -> Result<ServerConfig> {
+        let text = fs::read_to_string(path)?;
+        ServerConfig::parse(&text).map_err(|err| {
+            anyhow::Error::msg(format!(
+                "Failed to parse server config {}: {}",
+                path.to_string_lossy(),
+                err
+            ))
+        })
+    }
+
+    /// Value of a top level property. If it is given more than once the last one is used
+    pub fn get(&self, name: &str) -> Option<&ConfigValue> {
+        find_property(&self.entries, name)
+    }
+
+    /// Entries of a top level class, like `Missions`
+    pub fn class(&self, name: &str) -> Option<&[ConfigEntry]> {
+        find_class(&self.entries, name)
+    }
+
+    pub fn hostname(&self) -> Option<&str> {
+        self.get("hostname").and_then(ConfigValue::as_str)
+    }
+
+    /// Password needed to join, also used by headless clients
+    pub fn password(&self) -> Option<&str> {
+        self.get("password").and_then(ConfigValue::as_str)
+    }
+
+    pub fn max_players(&self) -> Option<u32> {
+        self.get("maxPlayers")
+            .and_then(ConfigValue::as_number)
+            .map(|n| n as u32)
+    }
+
+    /// Mission templates in the mission cycle, like `co10_escape.Altis`
+    pub fn missions(&self) -> Vec<&str> {
+        self.class("Missions")
+            .unwrap_or_default()
+            .iter()
+            .filter_map(|entry| match entry {
+                ConfigEntry::Class { entries, .. } => {
+                    find_property(entries, "template").and_then(ConfigValue::as_str)
+                }
+                _ => None,
+            })
+            .collect()
+    }
+}
+
+impl std::fmt::Display for ServerConfig {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write_entries(f, &self.entries, 0)
+    }
+}
+
+fn write_entries(
+    f: &mut std::fmt::Formatter<'_>,
+    entries: &[ConfigEntry],
+    depth: usize,
+) -> std::fmt::Result {
+    let indent = "\t".repeat(depth);
+    for entry in entries {
+        match entry {
+            ConfigEntry::Property {
+                name,
+                value: value @ ConfigValue::Array(_),
+            } => writeln!(f, "{}{}[] = {};", indent, name, value)?,
+            ConfigEntry::Property { name, value } => {
+                writeln!(f, "{}{} = {};", indent, name, value)?
+            }
+            ConfigEntry::Class {
+                name,
+                base,
+                entries,
+            } => {
+                match base {
+                    Some(base) => writeln!(f, "{}class {} : {}", indent, name, base)?,
+                    None => writeln!(f, "{}class {}", indent, name)?,
+                }
+                writeln!(f, "{}{{", indent)?;
+                write_entries(f, entries, depth + 1)?;
+                writeln!(f, "{}}};", indent)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+fn find_property<'a>(entries: &'a [ConfigEntry], name: &str) -> Option<&'a ConfigValue> {
+    entries.iter().rev().find_map(|entry| match entry {
+        ConfigEntry::Property { name: n, value } if n.eq_ignore_ascii_case(name) => Some(value),
+        _ => None,
+    })
+}
+
+fn find_class<'a>(entries: &'a [ConfigEntry], name: &str) -> Option<&'a [ConfigEntry]> {
+    entries.iter().rev().find_map(|entry| match entry {
+        ConfigEntry::Class {
+            name: n, entries, ..
+        } if n.eq_ignore_ascii_case(name) => Some(entries.as_slice()),
+        _ => None,
+    })
+}
+
+struct Parser {
+    chars: Vec<char>,
+    pos: usize,
+    /// current line, for error messages
+    line: usize,
+}
+
+impl Parser {
+    fn error(&self, message: &str) -> anyhow::Error {
+        anyhow::Error::msg(format!("line {}: {}", self.line, message))
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.pos += 1;
+        if c == '\n' {
+            self.line += 1;
+        }
+        Some(c)
+    }
+
+    fn starts_with(&self, s: &str) -> bool {
+        s.chars()
+            .enumerate()
+            .all(|(i, c)| self.chars.get(self.pos + i) == Some(&c))
+    }
+
+    /// skip whitespace and comments
+    fn skip_trivia(&mut self) {
+        loop {
+            if self.peek().is_some_and(char::is_whitespace) {
+                self.bump();
+            } else if self.starts_with("//") {
+                while self.peek().is_some_and(|c| c != '\n') {
+                    self.bump();
+                }
+            } else if self.starts_with("/*") {
+                self.pos += 2;
+                while self.peek().is_some() && !self.starts_with("*/") {
+                    self.bump();
+                }
+                self.pos = (self.pos + 2).min(self.chars.len());
+            } else {
+                return;
+            }
+        }
+    }
+
+    fn expect(&mut self, expected: char) -> Result<()> {
+        self.skip_trivia();
+        match self.bump() {
+            Some(c) if c == expected => Ok(()),
+            Some(c) => Err(self.error(&format!("expected '{}', found '{}'", expected, c))),
+            None => Err(self.error(&format!("expected '{}', found end of file", expected))),
+        }
+    }
+
+    fn name(&mut self) -> Result<String> {
+        self.skip_trivia();
+        let mut name = String::new();
+        while let Some(c) = self.peek().filter(|c| c.is_alphanumeric() || *c == '_') {
+            name.push(c);
+            self.bump();
+        }
+        match name.is_empty() {
+            true => Err(self.error("expected a name")),
+            false => Ok(name),
+        }
+    }
+
+    fn entries(&mut self, in_class: bool) -> Result<Vec<ConfigEntry>> {
+        let mut entries = Vec::new();
+        loop {
+            self.skip_trivia();
+            match self.peek() {
+                None if in_class => return Err(self.error("missing '}' at end of class")),
+                None => return Ok(entries),
+                Some('}') if in_class => {
+                    self.bump();
+                    return Ok(entries);
+                }
+                // stray semicolons are allowed
+                Some(';') => {
+                    self.bump();
+                }
+                Some('#') => return Err(self.error("preprocessor directives are not supported")),
+                Some(_) => entries.push(self.entry()?),
+            }
+        }
+    }
+
+    fn entry(&mut self) -> Result<ConfigEntry> {
+        let name = self.name()?;
+
+        if name.eq_ignore_ascii_case("class") {
+            let name = self.name()?;
+            self.skip_trivia();
+            let base = match self.peek() {
+                Some(':') => {
+                    self.bump();
+                    Some(self.name()?)
+                }
+                _ => None,
+            };
+            self.skip_trivia();
+            // `class Name;` declares an empty class
+            let entries = match self.peek() {
+                Some('{') => {
+                    self.bump();
+                    self.entries(true)?
+                }
+                _ => Vec::new(),
+            };
+            self.expect(';')?;
+            return Ok(ConfigEntry::Class {
+                name,
+                base,
+                entries,
+            });
+        }
+
+        self.skip_trivia();
+        let is_array = self.peek() == Some('[');
+        if is_array {
+            self.bump();
+            self.expect(']')?;
+        }
+        self.expect('=')?;
+        let value = match is_array {
+            true => self.array()?,
+            false => self.scalar(&[';'])?,
+        };
+        self.expect(';')?;
+
+        Ok(ConfigEntry::Property { name, value })
+    }
+
+    fn array(&mut self) -> Result<ConfigValue> {
+        self.expect('{')?;
+        let mut values = Vec::new();
+        loop {
+            self.skip_trivia();
+            match self.peek() {
+                Some('}') => {
+                    self.bump();
+                    return Ok(ConfigValue::Array(values));
+                }
+                Some('{') => values.push(self.array()?),
+                Some(_) => values.push(self.scalar(&[',', '}'])?),
+                None => return Err(self.error("missing '}' at end of array")),
+            }
+
+            self.skip_trivia();
+            match self.peek() {
+                Some(',') => {
+                    self.bump();
+                }
+                Some('}') => {}
+                _ => return Err(self.error("expected ',' or '}' in array")),
+            }
+        }
+    }
+
+    /// a string, number or unquoted value, ending before one of `end`
+    fn scalar(&mut self, end: &[char]) -> Result<ConfigValue> {
+        self.skip_trivia();
+        if self.peek() == Some('"') {
+            return self.string();
+        }
+
+        let mut raw = String::new();
+        while let Some(c) = self.peek() {
+            if end.contains(&c) || c == '\n' || self.starts_with("//") {
+                break;
+            }
+            raw.push(c);
+            self.bump();
+        }
+
+        let raw = raw.trim();
+        if raw.is_empty() {
+            return Err(self.error("expected a value"));
+        }
+        // parse would also accept words like inf and nan
+        Ok(match raw.parse::<f64>().ok().filter(|n| n.is_finite()) {
+            Some(n) => ConfigValue::Number(n),
+            None => ConfigValue::Word(raw.to_string()),
+        })
+    }
+
+    fn string(&mut self) -> Result<ConfigValue> {
+        let start_line = self.line;
+        self.bump();
+
+        let mut s = String::new();
+        loop {
+            match self.bump() {
+                // a doubled quote is a quote inside the string
+                Some('"') if self.peek() == Some('"') => {
+                    self.bump();
+                    s.push('"');
+                }
+                Some('"') => return Ok(ConfigValue::String(s)),
+                Some(c) => s.push(c),
+                None => {
+                    return Err(anyhow::Error::msg(format!(
+                        "line {}: string is never closed",
+                        start_line
+                    )));
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_parse_server_config() {
+        let path = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/test_asset_server.cfg");
+        let config = ServerConfig::load(&path).unwrap();
+
+        assert_eq!(config.hostname(), Some("Crow Dev Server"));
+        assert_eq!(config.password(), Some("passwordcanbe=anything"));
+        assert_eq!(
+            config.get("passwordAdmin"),
+            Some(&ConfigValue::String("superawesomepassword".to_string()))
+        );
+        assert_eq!(config.max_players(), Some(20));
+        assert_eq!(
+            config.get("voteThreshold"),
+            Some(&ConfigValue::Number(0.33))
+        );
+        assert_eq!(
+            config.get("autoSelectMission"),
+            Some(&ConfigValue::Word("false".to_string()))
+        );
+
+        let Some(ConfigValue::Array(vote_cmds)) = config.get("allowedVoteCmds") else {
+            panic!("allowedVoteCmds is not an array");
+        };
+        assert_eq!(vote_cmds.len(), 6);
+        assert_eq!(
+            vote_cmds[1],
+            ConfigValue::Array(vec![
+                ConfigValue::String("kick".to_string()),
+                ConfigValue::Word("false".to_string()),
+                ConfigValue::Word("true".to_string()),
+                ConfigValue::Number(0.51),
+            ])
+        );
+
+        // writing and parsing again gives the same config
+        assert_eq!(ServerConfig::parse(&config.to_string()).unwrap(), config);
+    }
+
+    #[test]
+    fn test_parse_edge_cases() {
+        let text = r#"
+            passwordAdmin = "admin"; /* block
+            comment */ password = "say ""hi"" // not a comment";
+            class Missions
+            {
+                Class Mission1
+                {
+                    template = "co10_escape.Altis";
+                    difficulty = "Regular";
+                    class Params {};
+                };
+            };
+        "#;
+        let config = ServerConfig::parse(text).unwrap();
+
+        assert_eq!(config.get("PASSWORDADMIN").unwrap().as_str(), Some("admin"));
+        assert_eq!(config.password(), Some("say \"hi\" // not a comment"));
+        assert_eq!(config.missions(), vec!["co10_escape.Altis"]);
+        assert_eq!(ServerConfig::parse(&config.to_string()).unwrap(), config);
+
+        let err = ServerConfig::parse("hostname = \"test\";\nmaxPlayers = 10\n").unwrap_err();
+        assert_eq!(err.to_string(), "line 3: expected ';', found end of file");
+    }
+}
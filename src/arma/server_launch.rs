@@ -1,12 +1,27 @@
+use crate::arma::server_config::ServerConfig;
+use crate::configs::instance::instance_file_name;
+use crate::configs::keys::KeySettings;
+use crate::configs::profile::LaunchParameters;
 use anyhow::Result;
 use glob::{MatchOptions, glob_with};
-use std::fs;
 use std::path::{Path, PathBuf};
-use std::process::{Command, Stdio};
+use std::process::{Child, Command, Stdio};
 
-static LOADED_MODS_FILE: &str = "corvuslauncher_loaded_mods.txt";
+pub(crate) static LOADED_MODS_FILE: &str = "corvuslauncher_loaded_mods.txt";
 
-pub fn find_bikey(path: &Path) -> Result<Vec<PathBuf>> {
+/// Launch was halted because one or more mods has no bikey
+#[derive(Debug)]
+pub struct MissingBikeys(pub Vec<String>);
+
+impl std::fmt::Display for MissingBikeys {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0.join("\n"))
+    }
+}
+
+impl std::error::Error for MissingBikeys {}
+
+pub fn find_bikey(path: &Path, key_settings: &KeySettings) -> Result<Vec<PathBuf>> {
     //
     let options = MatchOptions {
         case_sensitive: false,
@@ -21,11 +36,15 @@ pub fn find_bikey(path: &Path) -> Result<Vec<PathBuf>> {
 
     log::debug!("Searching for bikey files in {:?}", search_pattern);
 
+    let mod_folder = path.file_name().unwrap_or_default().to_string_lossy();
+
     for entry in glob_with(&search_pattern.to_string_lossy(), options)? {
         let p = entry?.to_path_buf();
 
-        // custom handling, do not include \optionals\ from ACE
-        if p.to_str().unwrap().contains("optionals") {
+        // skip keys excluded by the key rules, like \optionals\ from ACE
+        let relative = p.strip_prefix(path).unwrap_or(&p);
+        if !key_settings.is_key_included(&mod_folder, relative)? {
+            log::debug!("Skipping bikey excluded by key rules: {:?}", p);
             continue;
         }
 
@@ -48,10 +67,9 @@ pub fn find_bikey(path: &Path) -> Result<Vec<PathBuf>> {
     }
 }
 
-/// function that given all mods, create the -par file for server to load.
+/// function that given all mods, builds the content of the -par file for server to load.
 /// Input path for mods should be the ones loaded in preset, so relative to A3root
-pub fn build_mods_launch_file(mods: Vec<PathBuf>, output_file: &PathBuf) -> Result<()> {
-    // we got a vector of mods, build the string and output to file
+pub fn mods_launch_string(mods: &[PathBuf]) -> String {
     let mut mod_string = String::with_capacity(mods.len() * 15);
     mod_string.push_str("-mod=");
 
@@ -60,139 +78,69 @@ pub fn build_mods_launch_file(mods: Vec<PathBuf>, output_file: &PathBuf) -> Resu
         mod_string.push(';');
     }
 
-    // save file
-    fs::write(output_file, mod_string)?;
-
-    log::info!("Build server modlist and saved to parameter file");
-    log::debug!(
-        "Build server modlist successfully and saved to: {:?}",
-        output_file
-    );
-
-    Ok(())
+    mod_string
 }
 
-fn remove_dir_contents_but_a3key(path: &PathBuf) -> anyhow::Result<()> {
-    for entry in fs::read_dir(path)? {
-        let file = entry?;
-        // do not remove a3.bikey, as that should always be there
-        if !file.file_name().eq("a3.bikey") {
-            fs::remove_file(file.path())?;
-        }
-    }
-    Ok(())
+/// Everything needed to launch a server. The mods are the combined mods of all the selected
+/// modlists, so ensure to filter and combine them before building this
+#[derive(Clone, Debug, bon::Builder)]
+pub struct ServerLaunch {
+    pub a3_root: PathBuf,
+    pub a3_executable: PathBuf,
+    pub port: String,
+    pub profile: String,
+    #[builder(default)]
+    pub mods: Vec<PathBuf>,
+    #[builder(default)]
+    pub clientsides: Vec<PathBuf>,
+    #[builder(default)]
+    pub server_mods: Vec<PathBuf>,
+    #[builder(default)]
+    pub parameters: LaunchParameters,
+    #[builder(default)]
+    pub key_settings: KeySettings,
+    /// server config chosen for the profile, relative to the A3 root
+    pub server_config: Option<PathBuf>,
+    /// basic network config chosen for the profile, relative to the A3 root
+    pub basic_config: Option<PathBuf>,
+    /// named instance this server belongs to, none for the single unnamed server
+    pub instance: Option<String>,
 }
 
-/// launch server with given parameters. It expects a single combined ServerModList for all the
-/// selected mods. So ensure to filter and combine them into single entity before calling this
-pub fn launch_server(
-    a3root: &PathBuf,
-    a3_executable: &PathBuf,
-    port: &str,
-    server_profile: &str,
-    modlist: Vec<PathBuf>,
-    clientsides: Vec<PathBuf>,
-    server_mods: Vec<PathBuf>,
-) -> Result<()> {
-    let keys_folder = a3root.clone().join("keys");
-    let par_modlist = a3root.clone().join(LOADED_MODS_FILE);
-
-    // clean existing keys folder of all keys, besides a3.bikey
-    remove_dir_contents_but_a3key(&keys_folder)?;
-
-    // make vecs of paths to bikeys
-    let mut bikeys: Vec<PathBuf> = Vec::new();
-    let mut missing_keys: Vec<String> = Vec::new();
-
-    // find list of bikeys for all mods, clientsides, modlist and server-mods. Make into one big vec
-    let server_and_clientsides = [modlist.clone(), clientsides].concat();
-
-    for modpath in server_and_clientsides.iter() {
-        // make absolute path from relative
-        let full_path = a3root.clone().join(modpath);
-
-        // try and find bikeys
-        match find_bikey(&full_path) {
-            Ok(mut path) => {
-                bikeys.append(&mut path);
-            }
-            Err(e) => missing_keys.push(e.to_string()),
-        }
-    }
-
-    // if err vec is not empty, we halt the launch and displays errors to users instead of missing bikeys
-    if !missing_keys.is_empty() {
-        for e in &missing_keys {
-            log::error!("{}", e);
-        }
-        return Err(anyhow::Error::msg(missing_keys.join("\n")));
+/// Parameter file with the mods of an instance, read by its server and HCs
+pub fn par_file(a3root: &Path, instance: Option<&str>) -> PathBuf {
+    match instance {
+        Some(name) => a3root.join(format!(
+            "corvuslauncher_loaded_mods_{}.txt",
+            instance_file_name(name)
+        )),
+        None => a3root.join(LOADED_MODS_FILE),
     }
+}
 
-    // no keys are missing, we can continue by copying keys to the a3root/keys folder
-    for p in bikeys.iter() {
-        fs::copy(p, keys_folder.clone().join(p.file_name().unwrap()))?;
+/// Profile folder of a headless client, where it writes its RPT
+pub fn hc_profile_folder(a3root: &Path, instance: Option<&str>, index: u64) -> PathBuf {
+    match instance {
+        Some(name) => a3root.join(format!(
+            "headlessProfile_{}_{}",
+            instance_file_name(name),
+            index
+        )),
+        None => a3root.join(format!("headlessProfile{}", index)),
     }
-
-    // build parameter file for server mods
-    build_mods_launch_file(modlist, &par_modlist)?;
-
-    // build string for server mods
-    let server_mod_string_vec: Vec<String> = server_mods
-        .iter()
-        .map(|entry| String::from(entry.to_string_lossy()))
-        .collect();
-
-    // launch HC and null stdin, out and error, to fork and disown process. We should be able to close launcher without killing server
-    let mut launch_cmd = Command::new(a3_executable);
-    launch_cmd
-        .arg(format!("-port={}", port))
-        .args([
-            "-hugepages",
-            "-maxMem=30000",
-            "-maxFileCacheSize=8192",
-            "-enableHT",
-            "-bandwidthAlg=2",
-            "-limitFPS=1000",
-            "-loadMissionToMemory",
-        ])
-        .args(["-name=server", "-world=empty"])
-        .arg(format!(
-            "-profiles={}",
-            &a3root.clone().join(server_profile).to_string_lossy()
-        ))
-        .arg(format!(
-            "-config={}",
-            &find_config(a3root)?.to_string_lossy()
-        ))
-        .arg(format!(
-            "-cfg={}",
-            &a3root
-                .clone()
-                .join(server_profile)
-                .join("Users")
-                .join("server")
-                .join("Arma3.cfg")
-                .to_string_lossy()
-        ))
-        .arg(format!("-serverMod={}", &server_mod_string_vec.join(";")))
-        .arg(format!("-par={}", &par_modlist.to_string_lossy()));
-
-    log::debug!("launch server: {}", pretty_cmd(&launch_cmd));
-
-    // launch command
-    launch_cmd
-        .stdin(Stdio::null())
-        .stdout(Stdio::null())
-        .stderr(Stdio::null())
-        .spawn()?;
-
-    Ok(())
 }
 
 /// launch function for Headless
-pub fn launch_hc(a3root: &PathBuf, a3_executable: &PathBuf, port: &str, index: u64) -> Result<()> {
+pub fn launch_hc(
+    a3root: &Path,
+    a3_executable: &PathBuf,
+    port: &str,
+    index: u64,
+    server_config: Option<&Path>,
+    instance: Option<&str>,
+) -> Result<Child> {
     // get server password as we need to pass it to HC
-    let server_password = get_server_password_from_config(find_config(a3root)?)?;
+    let server_password = get_server_password_from_config(find_config(a3root, server_config)?)?;
 
     // launch HC and null stdin, out and error, to fork and disown process. We should be able to close launcher without killing hcs
     let mut launch_cmd = Command::new(a3_executable);
@@ -202,73 +150,103 @@ pub fn launch_hc(a3root: &PathBuf, a3_executable: &PathBuf, port: &str, index: u
         .arg(format!("-password={}", &server_password))
         .arg(format!(
             "-profiles={}",
-            &a3root
-                .clone()
-                .join(format!("headlessProfile{}", index))
-                .to_string_lossy()
+            hc_profile_folder(a3root, instance, index).to_string_lossy()
         ))
         .arg(format!("-name={}", &format!("hc{}", index)))
         .arg(format!(
             "-par={}",
-            &a3root.join(LOADED_MODS_FILE).to_string_lossy()
+            par_file(a3root, instance).to_string_lossy()
         ));
 
     // log launch parameter
     log::debug!("launch HC{}: {}", index, pretty_cmd(&launch_cmd));
 
     // run command
-    launch_cmd
+    let child = launch_cmd
         .stdin(Stdio::null())
         .stdout(Stdio::null())
         .stderr(Stdio::null())
         .spawn()?;
 
-    Ok(())
+    Ok(child)
+}
+
+/// Find the server config for a profile. Uses the file chosen for the profile, or the only .cfg
+/// file in the A3Root. Fails if there are several and none is chosen, as any of them could be meant
+pub fn find_config(a3root: &Path, chosen: Option<&Path>) -> Result<PathBuf> {
+    if let Some(chosen) = chosen {
+        let path = a3root.join(chosen);
+        return match path.is_file() {
+            true => Ok(path),
+            false => Err(anyhow::Error::msg(format!(
+                "Server config {} not found",
+                path.to_string_lossy()
+            ))),
+        };
+    }
+
+    let mut configs = server_configs(a3root)?;
+    match configs.len() {
+        0 => Err(anyhow::Error::msg("A3 Server Config not found in A3Root")),
+        1 => Ok(configs.remove(0)),
+        _ => {
+            let names: Vec<String> = configs
+                .iter()
+                .map(|c| {
+                    c.file_name()
+                        .unwrap_or_default()
+                        .to_string_lossy()
+                        .to_string()
+                })
+                .collect();
+            Err(anyhow::Error::msg(format!(
+                "Multiple server configs in A3Root: {}. Choose one for the profile in its launch parameters",
+                names.join(", ")
+            )))
+        }
+    }
 }
 
-/// Find the .cfg file in the A3Root to parse it and get the password
-pub fn find_config(a3root: &PathBuf) -> Result<PathBuf> {
-    let keys = std::fs::read_dir(a3root)?
+/// All .cfg files in the A3Root, sorted by name
+pub fn server_configs(a3root: &Path) -> Result<Vec<PathBuf>> {
+    let mut configs: Vec<PathBuf> = std::fs::read_dir(a3root)?
         // Filter out all those directory entries which couldn't be read
         .filter_map(|res| res.ok())
-        // Filter out all paths with extensions other than `csv`
-        .filter_map(|f| {
-            if f.path().extension().is_some_and(|ext| ext == "cfg") {
-                Some(f)
-            } else {
-                None
-            }
-        })
-        .collect::<Vec<_>>();
-
-    // should only return one, as the a3root should only have a single .cfg file
-    match keys.first() {
-        Some(keys) => Ok(keys.path()),
-        None => Err(anyhow::Error::msg("A3 Server Config not found in A3Root")),
+        .map(|f| f.path())
+        .filter(|path| path.is_file() && path.extension().is_some_and(|ext| ext == "cfg"))
+        .collect();
+    configs.sort();
+    Ok(configs)
+}
+
+/// Basic network config for a profile. `Users/server/Arma3.cfg` in the profile folder, unless
+/// another file is chosen
+pub fn find_basic_config(a3root: &Path, profile: &str, chosen: Option<&Path>) -> Result<PathBuf> {
+    match chosen {
+        Some(chosen) if a3root.join(chosen).is_file() => Ok(a3root.join(chosen)),
+        Some(chosen) => Err(anyhow::Error::msg(format!(
+            "Basic config {} not found",
+            a3root.join(chosen).to_string_lossy()
+        ))),
+        None => Ok(a3root
+            .join(profile)
+            .join("Users")
+            .join("server")
+            .join("Arma3.cfg")),
     }
 }
 
 /// Given the path to the A3 server config file, extract the password for use in HCs launch parameters
 pub fn get_server_password_from_config(a3_config: PathBuf) -> Result<String> {
-    // find the password which is given in the file as: password = "thepassword";
-    let config_string = std::fs::read_to_string(a3_config)?;
-
-    // find and extract the password string from: password = "thepassword";
-    for l in config_string.lines() {
-        if l.starts_with("password")
-            && let Some((password, _)) =
-                l[8..].replacen("=", "", 1).replace('"', "").split_once(";")
-        {
-            return Ok(password.trim().to_string());
-        }
+    match ServerConfig::load(&a3_config)?.password() {
+        Some(password) => Ok(password.to_string()),
+        None => Err(anyhow::Error::msg(
+            "Failed to parse config and find password...",
+        )),
     }
-
-    Err(anyhow::Error::msg(
-        "Failed to parse config and find password...",
-    ))
 }
 
-fn pretty_cmd(cmd: &Command) -> String {
+pub(crate) fn pretty_cmd(cmd: &Command) -> String {
     format!(
         "{} {:?}",
         cmd.get_envs()
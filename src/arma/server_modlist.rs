@@ -1,6 +1,10 @@
+use crate::arma::mod_paths::normalize_separators;
+use crate::arma::mod_size::ModSize;
+use crate::arma::preset::{LocalMod, Preset, UnresolvedMod, find_local_mods, resolve_preset};
+use anyhow::Result;
 use std::fs;
 use std::fs::read_to_string;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 #[derive(Debug)]
 pub struct ServerModList {
@@ -8,6 +12,10 @@ pub struct ServerModList {
     pub path: PathBuf,
     pub selected: bool,
     pub mods: Vec<PathBuf>,
+    /// entries of an imported preset that match no installed mod
+    pub unresolved: Vec<UnresolvedMod>,
+    /// size of all mods in the list, `None` until it is worked out
+    pub size: Option<ModSize>,
 }
 
 impl ServerModList {
@@ -17,7 +25,11 @@ impl ServerModList {
         let mods: Vec<PathBuf> = raw_file
             .trim_start_matches("\u{feff}")
             .split_terminator(";")
-            .map(PathBuf::from)
+            // lists are often saved with a trailing newline or spaces between entries
+            .map(str::trim)
+            .filter(|entry| !entry.is_empty())
+            // lists written on Windows use \ as separator
+            .map(normalize_separators)
             .collect();
 
         log::debug!("raw_file_string: {:?}", raw_file);
@@ -28,13 +40,41 @@ impl ServerModList {
             path,
             selected,
             mods,
+            unresolved: Vec::new(),
+            size: None,
         }
     }
+
+    /// Modlist from an Arma 3 Launcher HTML preset, with its mods matched to the installed mods
+    pub fn from_preset(name: String, path: PathBuf, local: &[LocalMod]) -> Result<ServerModList> {
+        let preset = Preset::load(&path)?;
+        let (mods, unresolved) = resolve_preset(&preset, local);
+        for entry in &unresolved {
+            log::warn!("{}: no installed mod found for {}", name, entry);
+        }
+
+        Ok(Self {
+            name,
+            path,
+            selected: false,
+            mods,
+            unresolved,
+            size: None,
+        })
+    }
 }
 
-/// function to load modlists from folder. Returns vector of modlists
-pub fn load_modlists(folder: &PathBuf) -> Vec<ServerModList> {
+fn is_preset(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("html") || ext.eq_ignore_ascii_case("htm"))
+}
+
+/// function to load modlists from folder. Returns vector of modlists. HTML presets are matched
+/// against the mods in the A3 root
+pub fn load_modlists(folder: &PathBuf, a3root: &Path) -> Vec<ServerModList> {
     let mut modlists: Vec<ServerModList> = Vec::new();
+    // only looked for once there is a preset
+    let mut local_mods: Option<Vec<LocalMod>> = None;
 
     // parse files if we can
     if let Ok(dir) = fs::read_dir(folder) {
@@ -47,14 +87,88 @@ pub fn load_modlists(folder: &PathBuf) -> Vec<ServerModList> {
                 continue;
             };
 
+            let name = String::from(path.file_stem().unwrap().to_string_lossy());
+            if is_preset(&path) {
+                let local = local_mods.get_or_insert_with(|| find_local_mods(a3root));
+                match ServerModList::from_preset(name, path.clone(), local) {
+                    Ok(modlist) => modlists.push(modlist),
+                    Err(err) => log::error!("Failed to read preset {:?}: {}", path, err),
+                }
+                continue;
+            }
+
             // push to vec
-            modlists.push(ServerModList::new(
-                String::from(path.file_stem().unwrap().to_string_lossy()),
-                path,
-                false,
-            ))
+            modlists.push(ServerModList::new(name, path, false))
         }
     }
 
     modlists
 }
+
+/// combine the mods of all selected modlists into a single vector, in the order the lists are given
+pub fn selected_mods(modlists: &[ServerModList]) -> Vec<PathBuf> {
+    modlists
+        .iter()
+        .filter(|e| e.selected)
+        .flat_map(|e| e.mods.clone())
+        .collect()
+}
+
+/// mark the modlists with the given names as selected. Fails if a name does not match any modlist
+pub fn select_by_name(modlists: &mut [ServerModList], names: &[String]) -> Result<()> {
+    for name in names {
+        match modlists.iter_mut().find(|e| &e.name == name) {
+            Some(modlist) => modlist.selected = true,
+            None => {
+                let available: Vec<&str> = modlists.iter().map(|e| e.name.as_str()).collect();
+                return Err(anyhow::Error::msg(format!(
+                    "Modlist not found: {}. Available: {}",
+                    name,
+                    available.join(", ")
+                )));
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_load_modlists() {
+        let root =
+            std::env::temp_dir().join(format!("corvus_test_modlists_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        let folder = root.join("modlists");
+        fs::create_dir_all(folder.join("not a list")).unwrap();
+        // saved on Windows, with a BOM, spaces and empty entries
+        fs::write(
+            folder.join("Main.txt"),
+            "\u{feff}mods\\@cba ; mods\\@ace;\r\n\r\n; ;mods/@rhs\r\n",
+        )
+        .unwrap();
+        fs::write(folder.join("Empty.txt"), "\n").unwrap();
+
+        let mut modlists = load_modlists(&folder, &root);
+        modlists.sort_by(|a, b| a.name.cmp(&b.name));
+        let names: Vec<&str> = modlists.iter().map(|m| m.name.as_str()).collect();
+        assert_eq!(names, vec!["Empty", "Main"]);
+        assert!(modlists[0].mods.is_empty());
+        assert_eq!(
+            modlists[1].mods,
+            vec![
+                PathBuf::from("mods/@cba"),
+                PathBuf::from("mods/@ace"),
+                PathBuf::from("mods/@rhs")
+            ]
+        );
+
+        assert!(select_by_name(&mut modlists, &["Other".to_string()]).is_err());
+        select_by_name(&mut modlists, &["Main".to_string()]).unwrap();
+        assert_eq!(selected_mods(&modlists).len(), 3);
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -0,0 +1,233 @@
+//! Checks that every mod referenced by the selected modlists exists and looks like a mod, before
+//! anything is launched
+use crate::ServerModList;
+use crate::arma::mod_paths::{has_uppercase, resolve_mod_path};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// How similar a folder name has to be to be suggested, from 0 to 1
+const SUGGESTION_THRESHOLD: f64 = 0.7;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ModProblem {
+    /// folder does not exist, with the closest matching folder if any
+    Missing(Option<String>),
+    /// folder exists but has nothing in it
+    Empty,
+    /// folder has no addons folder, so the server has nothing to load
+    NoAddons,
+    /// folder or addons have uppercase names, which Linux servers do not load
+    NotLowercase,
+    /// entry of an imported preset that matches no installed mod
+    NotInstalled,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ModIssue {
+    /// name of the modlist the mod is in
+    pub list: String,
+    pub mod_path: PathBuf,
+    pub problem: ModProblem,
+}
+
+impl ModIssue {
+    /// issues that should stop the launch, the rest are warnings
+    pub fn is_error(&self) -> bool {
+        matches!(
+            self.problem,
+            ModProblem::Missing(_) | ModProblem::Empty | ModProblem::NotInstalled
+        )
+    }
+}
+
+impl std::fmt::Display for ModIssue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mod_path = self.mod_path.to_string_lossy();
+        match &self.problem {
+            ModProblem::Missing(Some(suggestion)) => write!(
+                f,
+                "{}: {} does not exist, did you mean {}?",
+                self.list, mod_path, suggestion
+            ),
+            ModProblem::Missing(None) => write!(f, "{}: {} does not exist", self.list, mod_path),
+            ModProblem::Empty => write!(f, "{}: {} is empty", self.list, mod_path),
+            ModProblem::NoAddons => {
+                write!(f, "{}: {} has no addons folder", self.list, mod_path)
+            }
+            ModProblem::NotLowercase => write!(
+                f,
+                "{}: {} has uppercase file names, run the lowercase command to fix them",
+                self.list, mod_path
+            ),
+            ModProblem::NotInstalled => write!(f, "{}: {} is not installed", self.list, mod_path),
+        }
+    }
+}
+
+/// Launch was halted because mods in the selected lists are missing or empty
+#[derive(Debug)]
+pub struct InvalidMods(pub Vec<ModIssue>);
+
+impl std::fmt::Display for InvalidMods {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let issues: Vec<String> = self.0.iter().map(ModIssue::to_string).collect();
+        write!(f, "{}", issues.join("\n"))
+    }
+}
+
+impl std::error::Error for InvalidMods {}
+
+/// Check all mods in the selected modlists. Mod paths are relative to the A3 root
+pub fn validate_modlists(a3root: &Path, modlists: &[&ServerModList]) -> Vec<ModIssue> {
+    let mut issues = Vec::new();
+
+    for modlist in modlists.iter().filter(|m| m.selected) {
+        for mod_path in &modlist.mods {
+            if let Some(problem) = check_mod(a3root, mod_path) {
+                issues.push(ModIssue {
+                    list: modlist.name.clone(),
+                    mod_path: mod_path.clone(),
+                    problem,
+                });
+            }
+        }
+        // the display name stands in for the path, there is no folder to point to
+        for entry in &modlist.unresolved {
+            issues.push(ModIssue {
+                list: modlist.name.clone(),
+                mod_path: PathBuf::from(entry.to_string()),
+                problem: ModProblem::NotInstalled,
+            });
+        }
+    }
+
+    for issue in &issues {
+        log::warn!("Mod validation: {}", issue);
+    }
+    issues
+}
+
+fn check_mod(a3root: &Path, mod_path: &Path) -> Option<ModProblem> {
+    // casing and separators of modlists written on Windows do not have to match
+    let full_path = a3root.join(resolve_mod_path(a3root, mod_path));
+
+    if !full_path.is_dir() {
+        return Some(ModProblem::Missing(suggest_folder(a3root, mod_path)));
+    }
+
+    let mut entries = fs::read_dir(&full_path).ok()?.filter_map(|e| e.ok());
+    let has_addons = entries.any(|e| {
+        e.path().is_dir()
+            && e.file_name()
+                .to_string_lossy()
+                .eq_ignore_ascii_case("addons")
+    });
+    if has_addons {
+        return match cfg!(target_os = "linux") && has_uppercase(&full_path) {
+            true => Some(ModProblem::NotLowercase),
+            false => None,
+        };
+    }
+
+    match fs::read_dir(&full_path).ok()?.next().is_none() {
+        true => Some(ModProblem::Empty),
+        false => Some(ModProblem::NoAddons),
+    }
+}
+
+/// Find the folder next to where the mod should have been, with the most similar name
+fn suggest_folder(a3root: &Path, mod_path: &Path) -> Option<String> {
+    let wanted = mod_path.file_name()?.to_string_lossy().to_lowercase();
+    let parent = mod_path.parent().unwrap_or(Path::new(""));
+
+    fs::read_dir(a3root.join(parent))
+        .ok()?
+        .filter_map(|e| e.ok())
+        .filter(|e| e.path().is_dir())
+        .map(|e| e.file_name().to_string_lossy().to_string())
+        .map(|name| {
+            let score = strsim::normalized_damerau_levenshtein(&wanted, &name.to_lowercase());
+            (name, score)
+        })
+        .filter(|(_, score)| *score >= SUGGESTION_THRESHOLD)
+        .max_by(|a, b| a.1.total_cmp(&b.1))
+        .map(|(name, _)| name)
+}
+
+/// Validate the selected modlists, failing with [`InvalidMods`] if any mod is missing or empty.
+/// Returns the remaining issues as warnings
+pub fn check_modlists(a3root: &Path, modlists: &[&ServerModList]) -> anyhow::Result<Vec<String>> {
+    let (errors, warnings): (Vec<ModIssue>, Vec<ModIssue>) = validate_modlists(a3root, modlists)
+        .into_iter()
+        .partition(ModIssue::is_error);
+
+    if !errors.is_empty() {
+        return Err(InvalidMods(errors).into());
+    }
+    Ok(warnings.iter().map(ModIssue::to_string).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_mods() {
+        let root =
+            std::env::temp_dir().join(format!("corvus_test_validation_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(root.join("mods/@ace/addons")).unwrap();
+        fs::create_dir_all(root.join("mods/@empty")).unwrap();
+        fs::create_dir_all(root.join("mods/@noaddons")).unwrap();
+        fs::write(root.join("mods/@noaddons/readme.txt"), "").unwrap();
+        let list = root.join("mods.txt");
+        fs::write(&list, "mods/@ace;mods/@empty;mods/@noaddons;mods/@acee;").unwrap();
+
+        assert_eq!(check_mod(&root, Path::new("mods/@ace")), None);
+        assert_eq!(
+            check_mod(&root, Path::new("mods/@empty")),
+            Some(ModProblem::Empty)
+        );
+        assert_eq!(
+            check_mod(&root, Path::new("mods/@noaddons")),
+            Some(ModProblem::NoAddons)
+        );
+
+        // a typo is pointed to the closest folder, an unrelated name to none
+        assert_eq!(
+            check_mod(&root, Path::new("mods/@acee")),
+            Some(ModProblem::Missing(Some("@ace".to_string())))
+        );
+        assert_eq!(
+            suggest_folder(&root, Path::new("mods/@EMTPY")),
+            Some("@empty".to_string())
+        );
+        assert_eq!(
+            check_mod(&root, Path::new("mods/@rhsusaf")),
+            Some(ModProblem::Missing(None))
+        );
+        assert_eq!(suggest_folder(&root, Path::new("nowhere/@ace")), None);
+
+        // only selected lists are checked, and only missing or empty mods stop the launch
+        let mut modlist = ServerModList::new("Main".to_string(), list, false);
+        assert!(check_modlists(&root, &[&modlist]).unwrap().is_empty());
+        modlist.selected = true;
+        let err = check_modlists(&root, &[&modlist]).unwrap_err();
+        let issues = &err.downcast_ref::<InvalidMods>().unwrap().0;
+        assert_eq!(issues.len(), 2);
+        assert_eq!(
+            issues[1].to_string(),
+            "Main: mods/@acee does not exist, did you mean @ace?"
+        );
+
+        modlist
+            .mods
+            .retain(|m| !m.ends_with("@acee") && !m.ends_with("@empty"));
+        assert_eq!(
+            check_modlists(&root, &[&modlist]).unwrap(),
+            vec!["Main: mods/@noaddons has no addons folder"]
+        );
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -0,0 +1,302 @@
+//! Restarts servers and headless clients that exit without being stopped from the launcher
+use crate::arma::processes::{Crash, ProcessRecord, ProcessRegistry};
+use crate::arma::rpt::{last_lines, newest_rpt};
+use crate::configs::keys::KeySettings;
+use crate::configs::watchdog::WatchdogSettings;
+use chrono::{Local, TimeDelta};
+use std::time::SystemTime;
+
+/// A crashed process the watchdog launches again, once
+/// [`scan_for_restart`](crate::arma::processes::scan_for_restart) is done for it
+#[derive(Debug, Clone)]
+pub struct PendingRestart {
+    pub record: ProcessRecord,
+    pub crash: Crash,
+    /// restarts in a row before this one
+    pub restarts: u32,
+    max_restarts: u32,
+    /// the report of the crash so far
+    report: String,
+}
+
+/// Handle processes that exited since the last refresh. Crashes are recorded, or left to be
+/// restarted with [`restart_crashed`] if the watchdog is enabled. Returns a message for each crash
+/// that is not restarted, to show to the admin
+pub fn handle_exits(
+    registry: &mut ProcessRegistry,
+    exited: Vec<ProcessRecord>,
+    settings: &WatchdogSettings,
+) -> (Vec<String>, Vec<PendingRestart>) {
+    let mut reports = Vec::new();
+    let mut pending = Vec::new();
+
+    for record in exited.into_iter().filter(ProcessRecord::crashed) {
+        let crash = Crash {
+            time: record.exited.unwrap_or_else(Local::now),
+            exit_code: record.exit_code,
+            last_lines: crash_log(&record, settings.log_lines),
+        };
+
+        let mut report = format!(
+            "{} (pid {}) exited unexpectedly",
+            record.label(),
+            record.pid
+        );
+        if let Some(code) = crash.exit_code {
+            report.push_str(&format!(" with code {}", code));
+        }
+
+        // a process that ran for a while before crashing gets a fresh budget
+        let ran_for = crash.time - record.started;
+        let restarts = match ran_for > TimeDelta::minutes(settings.reset_after_mins as i64) {
+            true => 0,
+            false => record.restarts,
+        };
+
+        if settings.enabled && restarts < settings.max_restarts {
+            pending.push(PendingRestart {
+                record,
+                crash,
+                restarts,
+                max_restarts: settings.max_restarts,
+                report,
+            });
+            continue;
+        }
+
+        if settings.enabled {
+            report.push_str(&format!(
+                ", not restarted after {} restarts in a row",
+                restarts
+            ));
+        }
+        if let Err(err) = registry.record_crash(record.pid, crash.clone(), restarts) {
+            log::error!("Failed to record the crash of pid {}: {}", record.pid, err);
+        }
+        reports.push(finish_report(report, &crash));
+    }
+
+    (reports, pending)
+}
+
+/// Launch a crashed process again. `scanned` are the servers
+/// [`scan_for_restart`](crate::arma::processes::scan_for_restart) found for it.
+/// Returns the message for the crash, to show to the admin
+pub fn restart_crashed(
+    registry: &mut ProcessRegistry,
+    pending: PendingRestart,
+    key_settings: &KeySettings,
+    scanned: &[u32],
+) -> String {
+    let PendingRestart {
+        record,
+        crash,
+        restarts,
+        max_restarts,
+        mut report,
+    } = pending;
+
+    let result = registry
+        .prepare_restart(record.pid, key_settings, scanned)
+        .and_then(|relaunch| registry.relaunch(relaunch))
+        .and_then(|new_pid| {
+            report.push_str(&format!(
+                ", restarted as pid {} ({}/{})",
+                new_pid,
+                restarts + 1,
+                max_restarts
+            ));
+            registry.record_crash(new_pid, crash.clone(), restarts + 1)
+        });
+    if let Err(err) = result {
+        report.push_str(&format!(", restart failed: {}", err));
+    }
+    finish_report(report, &crash)
+}
+
+/// Add the end of the log to the report of a crash, and log it
+fn finish_report(mut report: String, crash: &Crash) -> String {
+    if !crash.last_lines.is_empty() {
+        report.push_str(&format!(
+            "\nLast log lines:\n{}",
+            crash.last_lines.join("\n")
+        ));
+    }
+    log::warn!("{}", report);
+    report
+}
+
+/// End of the RPT the process wrote, if it wrote one
+fn crash_log(record: &ProcessRecord, count: usize) -> Vec<String> {
+    let started = SystemTime::from(record.started);
+    let Some(rpt) = newest_rpt(&record.profile_folder(), Some(started)) else {
+        return Vec::new();
+    };
+
+    last_lines(&rpt, count).unwrap_or_else(|err| {
+        log::error!("Failed to read {:?}: {}", rpt, err);
+        Vec::new()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::arma::server_launch::hc_profile_folder;
+    use std::fs;
+    use std::path::{Path, PathBuf};
+
+    /// Pid no process has, pids stay far below this
+    const NO_PID: u32 = u32::MAX - 17;
+
+    /// HC that crashed after running for `ran_mins`
+    fn crashed_hc(pid: u32, a3_root: &Path, ran_mins: i64, restarts: u32) -> ProcessRecord {
+        let mut record = ProcessRecord::headless_client(
+            pid,
+            0,
+            a3_root,
+            &a3_root.join("arma3server_x64"),
+            "2302",
+            None,
+            None,
+        );
+        record.started = Local::now() - TimeDelta::minutes(ran_mins);
+        record.exited = Some(Local::now());
+        record.exit_code = Some(1);
+        record.restarts = restarts;
+        record
+    }
+
+    fn setup(name: &str) -> (PathBuf, ProcessRegistry) {
+        let a3_root =
+            std::env::temp_dir().join(format!("corvus_test_{}_{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&a3_root);
+        fs::create_dir_all(&a3_root).unwrap();
+        let registry = ProcessRegistry::load(&a3_root.join("processes.toml"));
+        (a3_root, registry)
+    }
+
+    fn handle(
+        registry: &mut ProcessRegistry,
+        record: ProcessRecord,
+        settings: &WatchdogSettings,
+    ) -> String {
+        registry.records.push(record.clone());
+        let (mut reports, pending) = handle_exits(registry, vec![record], settings);
+        // the GUI looks for running servers first, HCs do not need that
+        reports.extend(
+            pending
+                .into_iter()
+                .map(|pending| restart_crashed(registry, pending, &KeySettings::default(), &[])),
+        );
+        assert_eq!(reports.len(), 1);
+        reports.remove(0)
+    }
+
+    #[test]
+    fn test_handle_exits() {
+        let (a3_root, mut registry) = setup("watchdog");
+        let enabled = WatchdogSettings {
+            enabled: true,
+            ..Default::default()
+        };
+
+        // stopped from the launcher, nothing to report
+        let mut stopped = crashed_hc(NO_PID, &a3_root, 1, 0);
+        stopped.stopped = true;
+        let (reports, pending) = handle_exits(&mut registry, vec![stopped], &enabled);
+        assert!(reports.is_empty() && pending.is_empty());
+
+        // disabled only records the crash, with the end of the log
+        let log_folder = hc_profile_folder(&a3_root, None, 0);
+        fs::create_dir_all(&log_folder).unwrap();
+        fs::write(log_folder.join("hc.rpt"), "one\r\ntwo\r\nthree\r\n").unwrap();
+        let settings = WatchdogSettings {
+            log_lines: 2,
+            ..Default::default()
+        };
+        let report = handle(
+            &mut registry,
+            crashed_hc(NO_PID - 1, &a3_root, 1, 1),
+            &settings,
+        );
+        assert_eq!(
+            report,
+            format!(
+                "HC0 (pid {}) exited unexpectedly with code 1\nLast log lines:\ntwo\nthree",
+                NO_PID - 1
+            )
+        );
+        let record = registry.records.last().unwrap();
+        assert_eq!(record.restarts, 1);
+        let crash = record.last_crash.as_ref().unwrap();
+        assert_eq!(crash.exit_code, Some(1));
+        assert_eq!(crash.last_lines, vec!["two", "three"]);
+        fs::remove_dir_all(&log_folder).unwrap();
+
+        // out of restarts
+        let report = handle(
+            &mut registry,
+            crashed_hc(NO_PID - 2, &a3_root, 1, 3),
+            &enabled,
+        );
+        assert_eq!(
+            report,
+            format!(
+                "HC0 (pid {}) exited unexpectedly with code 1, not restarted after 3 restarts in a row",
+                NO_PID - 2
+            )
+        );
+        assert_eq!(registry.records.last().unwrap().restarts, 3);
+
+        // a failed restart is reported, there is no server config to read the password from
+        let report = handle(
+            &mut registry,
+            crashed_hc(NO_PID - 3, &a3_root, 1, 0),
+            &enabled,
+        );
+        assert!(report.contains(", restart failed: "), "{}", report);
+
+        fs::remove_dir_all(&a3_root).unwrap();
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_restart_budget_reset() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let (a3_root, mut registry) = setup("watchdog_restart");
+        fs::write(a3_root.join("server.cfg"), "password = \"secret\";\n").unwrap();
+        // stands in for the HC, ignores the arguments
+        let executable = a3_root.join("arma3server_x64");
+        fs::write(&executable, "#!/bin/sh\nexec sleep 30\n").unwrap();
+        fs::set_permissions(&executable, fs::Permissions::from_mode(0o755)).unwrap();
+        let settings = WatchdogSettings {
+            enabled: true,
+            ..Default::default()
+        };
+
+        // out of restarts, but it ran long enough to get a fresh budget
+        let report = handle(
+            &mut registry,
+            crashed_hc(NO_PID, &a3_root, 120, 3),
+            &settings,
+        );
+        let restarted = registry.records.last().unwrap().clone();
+        assert_ne!(restarted.pid, NO_PID);
+        assert!(restarted.is_running());
+        assert_eq!(restarted.restarts, 1);
+        assert_eq!(restarted.last_crash.as_ref().unwrap().exit_code, Some(1));
+        assert_eq!(
+            report,
+            format!(
+                "HC0 (pid {}) exited unexpectedly with code 1, restarted as pid {} (1/3)",
+                NO_PID, restarted.pid
+            )
+        );
+        assert!(!registry.records.iter().any(|r| r.pid == NO_PID));
+
+        registry.stop(restarted.pid).unwrap();
+        fs::remove_dir_all(&a3_root).unwrap();
+    }
+}
@@ -1,8 +1,9 @@
 use crate::configs::config::DEFAULT_CONFIG_FILE_PATH;
 use crate::configs::config::DEFAULT_LOG_FILE_PATH;
-use clap::Parser;
 use clap::ValueHint;
 use clap::builder::styling::{AnsiColor, Effects};
+use clap::{Args, Parser, Subcommand};
+use std::path::PathBuf;
 
 // styling for errors
 const STYLES: clap::builder::Styles = clap::builder::Styles::styled()
@@ -18,6 +19,10 @@ const STYLES: clap::builder::Styles = clap::builder::Styles::styled()
 #[command(version, styles = STYLES, long_about = None)]
 #[expect(clippy::struct_excessive_bools, reason = "normal for CLIs")]
 pub struct Cli {
+    /// Run a command without opening the launcher window
+    #[command(subcommand)]
+    pub command: Option<Command>,
+
     /// Use the provided configs file
     #[arg(
         help_heading = "Config",
@@ -82,3 +87,108 @@ pub struct Cli {
     )]
     pub debug: bool,
 }
+
+/// Commands that run headless, without starting the GUI
+#[derive(Subcommand, Debug)]
+pub enum Command {
+    /// Launch the server and headless clients with the given modlists
+    Launch(LaunchArgs),
+    /// Rename the mods of the given modlists to lowercase, as Linux servers need them
+    Lowercase(LowercaseArgs),
+    /// Write the mods of the given modlists as an Arma 3 Launcher preset, for players to import
+    Export(ExportArgs),
+}
+
+#[derive(Args, Debug)]
+pub struct ExportArgs {
+    /// Name of a modpack to export, can be given multiple times
+    #[arg(long, value_name = "NAME")]
+    pub modpack: Vec<String>,
+
+    /// Name of a clientside list to export, can be given multiple times
+    #[arg(long, value_name = "NAME")]
+    pub clientside: Vec<String>,
+
+    /// Named instance whose A3 root the mods are in. Defaults to the A3 root of the config
+    #[arg(long, value_name = "NAME")]
+    pub instance: Option<String>,
+
+    /// Name of the preset shown in the Arma 3 Launcher. Defaults to the names of the modpacks, or
+    /// the name of the output file without any
+    #[arg(long)]
+    pub name: Option<String>,
+
+    /// HTML file to write the preset to
+    #[arg(long, short, value_name = "FILE.html", value_hint = ValueHint::FilePath)]
+    pub output: PathBuf,
+}
+
+#[derive(Args, Debug)]
+pub struct LowercaseArgs {
+    /// Name of a modpack whose mods to rename, can be given multiple times
+    #[arg(long, value_name = "NAME")]
+    pub modpack: Vec<String>,
+
+    /// Name of a clientside list whose mods to rename, can be given multiple times
+    #[arg(long, value_name = "NAME")]
+    pub clientside: Vec<String>,
+
+    /// Name of a servermod list whose mods to rename, can be given multiple times
+    #[arg(long, value_name = "NAME")]
+    pub servermod: Vec<String>,
+
+    /// Named instance whose A3 root the mods are in. Defaults to the A3 root of the config
+    #[arg(long, value_name = "NAME")]
+    pub instance: Option<String>,
+
+    /// Print what would be renamed, without renaming anything
+    #[arg(long)]
+    pub dry_run: bool,
+}
+
+#[derive(Args, Debug)]
+pub struct LaunchArgs {
+    /// Name of a modpack to load, can be given multiple times
+    #[arg(long, value_name = "NAME")]
+    pub modpack: Vec<String>,
+
+    /// Name of a clientside list to allow, can be given multiple times
+    #[arg(long, value_name = "NAME")]
+    pub clientside: Vec<String>,
+
+    /// Name of a servermod list to load, can be given multiple times
+    #[arg(long, value_name = "NAME")]
+    pub servermod: Vec<String>,
+
+    /// Named instance to launch, its profile, port and HCs are used unless given
+    #[arg(long, value_name = "NAME")]
+    pub instance: Option<String>,
+
+    /// Server profile to use. Defaults to the first profile in the config
+    #[arg(long, value_name = "PROFILE")]
+    pub profile: Option<String>,
+
+    /// Port for the server and headless clients. Defaults to 2302
+    #[arg(long)]
+    pub port: Option<String>,
+
+    /// Use the next free ports after --port if any of its ports are taken
+    #[arg(long)]
+    pub auto_port: bool,
+
+    /// Number of headless clients to launch after the server. Defaults to 0
+    #[arg(long, value_name = "AMOUNT")]
+    pub hcs: Option<u64>,
+
+    /// Start the headless clients right away, instead of waiting for the server to be ready
+    #[arg(long)]
+    pub no_wait: bool,
+
+    /// Change the keys folder even if a server on the same A3 root is running
+    #[arg(long)]
+    pub replace_keys: bool,
+
+    /// Print what the launch would do, without changing any files or starting the server
+    #[arg(long)]
+    pub dry_run: bool,
+}
@@ -1,5 +1,14 @@
+use crate::configs::conflicts::{ConflictRule, default_conflicts};
+use crate::configs::hc::HcSettings;
+use crate::configs::instance::{InstanceSettings, instance_file_name};
+use crate::configs::keys::KeySettings;
+use crate::configs::load_order::LoadOrderSettings;
+use crate::configs::profile::ProfileSettings;
+use crate::configs::schedule::ScheduledJob;
+use crate::configs::watchdog::WatchdogSettings;
 use etcetera::BaseStrategy;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::default::Default;
 use std::fs;
 use std::fs::exists;
@@ -29,6 +38,30 @@ pub struct Config {
     pub folder_clientside: PathBuf,
     pub folder_servermods: PathBuf,
     pub server_profiles: Vec<String>,
+    /// Settings per server profile. Profiles not in here use the default settings
+    #[serde(default)]
+    pub profiles: BTreeMap<String, ProfileSettings>,
+    /// How the keys folder is managed
+    #[serde(default)]
+    pub keys: KeySettings,
+    /// How headless clients are started after the server
+    #[serde(default)]
+    pub hcs: HcSettings,
+    /// Restarting of crashed processes
+    #[serde(default)]
+    pub watchdog: WatchdogSettings,
+    /// Launches that run at set times
+    #[serde(default)]
+    pub schedules: Vec<ScheduledJob>,
+    /// Named servers run side by side. Without any, there is a single unnamed server
+    #[serde(default)]
+    pub instances: Vec<InstanceSettings>,
+    /// Mods that halt the launch when selected together
+    #[serde(default = "default_conflicts")]
+    pub conflicts: Vec<ConflictRule>,
+    /// Order the mods are loaded in
+    #[serde(default)]
+    pub load_order: LoadOrderSettings,
 }
 
 impl Default for Config {
@@ -40,6 +73,14 @@ impl Default for Config {
             folder_modlists: Default::default(),
             a3_server_executable: Default::default(),
             server_profiles: vec!["ServerNormal".to_string()],
+            profiles: BTreeMap::new(),
+            keys: KeySettings::default(),
+            hcs: HcSettings::default(),
+            watchdog: WatchdogSettings::default(),
+            schedules: Vec::new(),
+            instances: Vec::new(),
+            conflicts: default_conflicts(),
+            load_order: LoadOrderSettings::default(),
         }
     }
 }
@@ -89,6 +130,37 @@ impl Config {
         true
     }
 
+    /// Settings that contradict each other. Empty if the config is consistent
+    pub fn problems(&self) -> Vec<String> {
+        // the profiles to choose from are server_profiles, settings can not add any
+        let mut problems: Vec<String> = self
+            .profiles
+            .keys()
+            .filter(|profile| !self.server_profiles.contains(profile))
+            .map(|profile| {
+                format!(
+                    "Config has settings for profile {}, which is not in server_profiles",
+                    profile
+                )
+            })
+            .collect();
+
+        // instances would overwrite each other's parameter file and HC profiles
+        for (index, instance) in self.instances.iter().enumerate() {
+            let file_name = instance_file_name(&instance.name);
+            if let Some(other) = self.instances[..index]
+                .iter()
+                .find(|other| instance_file_name(&other.name) == file_name)
+            {
+                problems.push(format!(
+                    "Instances {} and {} would share their files as {}, rename one of them",
+                    other.name, instance.name, file_name
+                ));
+            }
+        }
+        problems
+    }
+
     pub fn update_config(
         &mut self,
         a3_root: PathBuf,
@@ -114,12 +186,58 @@ impl Config {
         self.folder_servermods = folder_servermods;
         log::debug!("Updated folder_servermods to: {:?}", self.folder_servermods);
 
-        // update file on disk, by just overwriting it with current configs. (Don't support external file changes without a restart)
-        fs::write(&*DEFAULT_CONFIG_FILE_PATH, toml::to_string(&self).unwrap())?;
+        self.save()?;
 
         log::info!("Updated configs");
         Ok(())
     }
+
+    /// update file on disk, by just overwriting it with current configs. (Don't support external file changes without a restart)
+    pub fn save(&self) -> anyhow::Result<()> {
+        fs::write(&*DEFAULT_CONFIG_FILE_PATH, toml::to_string(&self)?)?;
+        Ok(())
+    }
+
+    /// get settings for a server profile, or the defaults if nothing is stored for it
+    pub fn profile_settings(&self, profile: &str) -> ProfileSettings {
+        self.profiles.get(profile).cloned().unwrap_or_default()
+    }
+
+    pub fn instance(&self, name: &str) -> Option<&InstanceSettings> {
+        self.instances.iter().find(|i| i.name == name)
+    }
+
+    /// A3 root an instance runs from, its own or the one of the config
+    pub fn instance_a3_root(&self, instance: Option<&str>) -> PathBuf {
+        instance
+            .and_then(|name| self.instance(name))
+            .and_then(|i| i.a3_root.clone())
+            .unwrap_or_else(|| self.a3_root.clone())
+    }
+
+    /// Server executable of an instance, in its own A3 root if it has one
+    pub fn instance_executable(&self, instance: Option<&str>) -> PathBuf {
+        let a3_root = self.instance_a3_root(instance);
+        match a3_root == self.a3_root {
+            true => self.a3_server_executable.clone(),
+            false => a3_root.join(
+                self.a3_server_executable
+                    .file_name()
+                    .unwrap_or(A3_SERVER_BINARY_NAME.as_ref()),
+            ),
+        }
+    }
+
+    pub fn set_profile_settings(
+        &mut self,
+        profile: &str,
+        settings: ProfileSettings,
+    ) -> anyhow::Result<()> {
+        self.profiles.insert(profile.to_string(), settings);
+        log::debug!("Updated settings for profile: {}", profile);
+
+        self.save()
+    }
 }
 
 /// Represents the default location of the configs file
@@ -143,3 +261,55 @@ pub static DEFAULT_LOG_FILE_PATH: LazyLock<PathBuf> = LazyLock::new(|| {
         |strategy| strategy.cache_dir().join("corvuslauncher.log"),
     )
 });
+
+/// Represents the default location of the registry of launched processes
+pub static DEFAULT_PROCESS_FILE_PATH: LazyLock<PathBuf> = LazyLock::new(|| {
+    etcetera::choose_base_strategy().map_or_else(
+        |err| {
+            log::warn!("Could not determine the data directory: {err}");
+            PathBuf::from("corvuslauncher_processes.toml")
+        },
+        |strategy| strategy.data_dir().join("corvuslauncher_processes.toml"),
+    )
+});
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_problems() {
+        let mut config = Config::default();
+        assert!(config.problems().is_empty());
+
+        config
+            .profiles
+            .insert("ServerNormal".to_string(), ProfileSettings::default());
+        config
+            .profiles
+            .insert("ServerEvent".to_string(), ProfileSettings::default());
+        assert_eq!(
+            config.problems(),
+            vec!["Config has settings for profile ServerEvent, which is not in server_profiles"]
+        );
+
+        config.server_profiles.push("ServerEvent".to_string());
+        assert!(config.problems().is_empty());
+
+        for name in ["Main 2302", "Training", "main_2302"] {
+            config.instances.push(InstanceSettings {
+                name: name.to_string(),
+                profile: None,
+                port: "2302".to_string(),
+                hcs: 0,
+                a3_root: None,
+            });
+        }
+        assert_eq!(
+            config.problems(),
+            vec![
+                "Instances Main 2302 and main_2302 would share their files as main_2302, rename one of them"
+            ]
+        );
+    }
+}
@@ -0,0 +1,50 @@
+//! Settings for mods that can not be loaded together
+use anyhow::Result;
+use glob::{MatchOptions, Pattern};
+use serde::{Deserialize, Serialize};
+
+/// Mods that conflict with each other, like two radio mods. Each entry is a glob pattern matched
+/// against the mod folder name, like `@acre*`. Selecting mods that match two different entries
+/// halts the launch
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+pub struct ConflictRule {
+    pub name: String,
+    pub mods: Vec<String>,
+}
+
+/// the radio mods replace each others radios
+pub fn default_conflicts() -> Vec<ConflictRule> {
+    vec![ConflictRule {
+        name: "Radio mods".to_string(),
+        mods: vec!["@task_force_radio*".to_string(), "@acre*".to_string()],
+    }]
+}
+
+impl ConflictRule {
+    /// The folders matching each entry of the rule, for the entries that match any
+    pub fn matches<'a>(&self, mod_folders: &[&'a str]) -> Result<Vec<(&str, Vec<&'a str>)>> {
+        let options = MatchOptions {
+            case_sensitive: false,
+            ..Default::default()
+        };
+
+        let mut matches = Vec::new();
+        for entry in &self.mods {
+            let pattern = Pattern::new(entry).map_err(|err| {
+                anyhow::Error::msg(format!(
+                    "Invalid pattern {} in conflict rule {}: {}",
+                    entry, self.name, err
+                ))
+            })?;
+            let found: Vec<&str> = mod_folders
+                .iter()
+                .filter(|folder| pattern.matches_with(folder, options))
+                .copied()
+                .collect();
+            if !found.is_empty() {
+                matches.push((entry.as_str(), found));
+            }
+        }
+        Ok(matches)
+    }
+}
@@ -0,0 +1,40 @@
+//! Settings for starting headless clients once the server is ready
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+pub struct HcSettings {
+    /// Seconds between starting each headless client
+    #[serde(default = "default_stagger_secs")]
+    pub stagger_secs: u64,
+    /// Seconds to wait for the server to be ready before giving up on the headless clients
+    #[serde(default = "default_ready_timeout_secs")]
+    pub ready_timeout_secs: u64,
+}
+
+fn default_stagger_secs() -> u64 {
+    5
+}
+
+fn default_ready_timeout_secs() -> u64 {
+    600
+}
+
+impl Default for HcSettings {
+    fn default() -> Self {
+        Self {
+            stagger_secs: default_stagger_secs(),
+            ready_timeout_secs: default_ready_timeout_secs(),
+        }
+    }
+}
+
+impl HcSettings {
+    pub fn stagger(&self) -> Duration {
+        Duration::from_secs(self.stagger_secs)
+    }
+
+    pub fn ready_timeout(&self) -> Duration {
+        Duration::from_secs(self.ready_timeout_secs)
+    }
+}
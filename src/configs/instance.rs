@@ -0,0 +1,37 @@
+//! Named server instances, so several servers can run from one launcher
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+/// A server with its own port, profile and headless clients, like "Main" on 2302 and "Training"
+/// on 2402. Each instance writes its own parameter file, so they do not overwrite each other's mods
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+pub struct InstanceSettings {
+    pub name: String,
+    /// Server profile, the first profile in the config if not given
+    #[serde(default)]
+    pub profile: Option<String>,
+    #[serde(default = "default_port")]
+    pub port: String,
+    /// Headless clients to start with the server
+    #[serde(default)]
+    pub hcs: u64,
+    /// Separate Arma install for this instance. Instances on the same A3 root share its keys
+    /// folder, so launching one replaces the keys of the other
+    #[serde(default)]
+    pub a3_root: Option<PathBuf>,
+}
+
+fn default_port() -> String {
+    "2302".to_string()
+}
+
+/// Name of an instance as used in file names, lowercase with only letters, digits and `_`
+pub fn instance_file_name(name: &str) -> String {
+    name.trim()
+        .chars()
+        .map(|c| match c.is_ascii_alphanumeric() {
+            true => c.to_ascii_lowercase(),
+            false => '_',
+        })
+        .collect()
+}
@@ -0,0 +1,150 @@
+//! Settings for how the launcher manages the keys folder
+use anyhow::Result;
+use glob::{MatchOptions, Pattern};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
+
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+pub struct KeySettings {
+    /// Filenames of keys that are never removed from the keys folder
+    #[serde(default = "default_always_keep")]
+    pub always_keep: Vec<String>,
+    /// What to do when two mods ship a key with the same filename but different contents
+    #[serde(default)]
+    pub on_conflict: KeyConflict,
+    /// Rules for which keys in a mod to use, applied to all mods
+    #[serde(default = "default_rules")]
+    pub rules: KeyRules,
+    /// Rules for specific mods, by mod folder name like `@ace`. Applied on top of the global rules
+    #[serde(default)]
+    pub mod_rules: BTreeMap<String, KeyRules>,
+}
+
+/// Glob patterns matched against the path of a key relative to its mod folder, like
+/// `optionals/ace_foo.bikey`. A key matching an exclude pattern is skipped, unless it also
+/// matches an include pattern
+#[derive(Deserialize, Serialize, Debug, Clone, Default, PartialEq)]
+pub struct KeyRules {
+    #[serde(default)]
+    pub include: Vec<String>,
+    #[serde(default)]
+    pub exclude: Vec<String>,
+}
+
+/// do not include keys from the optionals folder, like in ACE
+fn default_rules() -> KeyRules {
+    KeyRules {
+        include: Vec::new(),
+        exclude: vec!["**/optionals/**".to_string()],
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum KeyConflict {
+    /// install the conflicting keys under a name with the mod folder appended
+    #[default]
+    Rename,
+    /// halt the launch
+    Error,
+}
+
+fn default_always_keep() -> Vec<String> {
+    vec!["a3.bikey".to_string()]
+}
+
+impl Default for KeySettings {
+    fn default() -> Self {
+        Self {
+            always_keep: default_always_keep(),
+            on_conflict: KeyConflict::default(),
+            rules: default_rules(),
+            mod_rules: BTreeMap::new(),
+        }
+    }
+}
+
+impl KeySettings {
+    pub fn is_always_kept(&self, filename: &str) -> bool {
+        self.always_keep
+            .iter()
+            .any(|keep| keep.eq_ignore_ascii_case(filename))
+    }
+
+    /// check if a key found in a mod should be used. `relative_key` is relative to the mod folder
+    pub fn is_key_included(&self, mod_folder: &str, relative_key: &Path) -> Result<bool> {
+        let mod_rules = self
+            .mod_rules
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case(mod_folder))
+            .map(|(_, rules)| rules);
+
+        let rule_sets = std::iter::once(&self.rules).chain(mod_rules);
+        let mut included = false;
+        let mut excluded = false;
+        for rules in rule_sets {
+            included |= matches_any(&rules.include, relative_key)?;
+            excluded |= matches_any(&rules.exclude, relative_key)?;
+        }
+
+        Ok(included || !excluded)
+    }
+}
+
+fn matches_any(patterns: &[String], path: &Path) -> Result<bool> {
+    let options = MatchOptions {
+        case_sensitive: false,
+        require_literal_separator: true,
+        ..Default::default()
+    };
+    // match with forward slashes on all platforms, so rules work the same everywhere
+    let path = path.to_string_lossy().replace('\\', "/");
+
+    for pattern in patterns {
+        let pattern = Pattern::new(pattern).map_err(|err| {
+            anyhow::Error::msg(format!("Invalid key rule pattern {}: {}", pattern, err))
+        })?;
+        if pattern.matches_with(&path, options) {
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_key_rules() {
+        let mut settings = KeySettings::default();
+        let included = |settings: &KeySettings, mod_folder: &str, key: &str| {
+            settings
+                .is_key_included(mod_folder, Path::new(key))
+                .unwrap()
+        };
+
+        // default skips optionals, but not a mod that has optionals in its own name
+        assert!(included(&settings, "@ace", "keys/ace.bikey"));
+        assert!(!included(&settings, "@ace", "optionals/ace_tracers.bikey"));
+        assert!(!included(
+            &settings,
+            "@ace",
+            "keys/Optionals/ace_tracers.bikey"
+        ));
+        assert!(included(&settings, "@optionals_pack", "keys/pack.bikey"));
+
+        // per mod rules are added to the global ones, and include wins over exclude
+        settings.mod_rules.insert(
+            "@ace".to_string(),
+            KeyRules {
+                include: vec!["optionals/ace_tracers.bikey".to_string()],
+                exclude: vec!["extras/**".to_string()],
+            },
+        );
+        assert!(included(&settings, "@ACE", "optionals/ace_tracers.bikey"));
+        assert!(!included(&settings, "@ace", "extras/ace_extra.bikey"));
+        assert!(included(&settings, "@cba", "extras/cba_extra.bikey"));
+    }
+}
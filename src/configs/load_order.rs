@@ -0,0 +1,38 @@
+//! Settings for the order mods are loaded in. Patterns are globs matched against the mod folder
+//! name, like `@cba*`
+use serde::{Deserialize, Serialize};
+
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+pub struct LoadOrderSettings {
+    /// Mods loaded before all others, in the order of the patterns
+    #[serde(default = "default_first")]
+    pub first: Vec<String>,
+    /// Mods loaded after all others, in the order of the patterns
+    #[serde(default)]
+    pub last: Vec<String>,
+    /// Mods that have to load after other mods, like compatibility patches after their parents
+    #[serde(default)]
+    pub after: Vec<AfterRule>,
+}
+
+/// Mods matching `mods` are loaded after every mod matching one of the `after` patterns
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+pub struct AfterRule {
+    pub mods: String,
+    pub after: Vec<String>,
+}
+
+/// CBA is needed by nearly every mod
+fn default_first() -> Vec<String> {
+    vec!["@cba*".to_string()]
+}
+
+impl Default for LoadOrderSettings {
+    fn default() -> Self {
+        Self {
+            first: default_first(),
+            last: Vec::new(),
+            after: Vec::new(),
+        }
+    }
+}
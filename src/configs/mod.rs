@@ -1,6 +1,14 @@
 pub mod cli;
 pub mod config;
+pub mod conflicts;
+pub mod hc;
+pub mod instance;
+pub mod keys;
+pub mod load_order;
+pub mod profile;
+pub mod schedule;
+pub mod watchdog;
 
-pub use cli::Cli;
+pub use cli::{Cli, Command, ExportArgs, LaunchArgs, LowercaseArgs};
 pub use config::Config;
 pub use config::DEFAULT_LOG_FILE_PATH;
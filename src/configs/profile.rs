@@ -0,0 +1,225 @@
+//! Settings stored per server profile
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+/// Settings for a single server profile, stored in the config under the profile name
+#[derive(Deserialize, Serialize, Debug, Clone, Default, PartialEq)]
+pub struct ProfileSettings {
+    #[serde(default)]
+    pub launch_parameters: LaunchParameters,
+    /// Server config passed with -config, relative to the A3 root. Only needed when the A3 root
+    /// has more than one .cfg file
+    #[serde(default)]
+    pub server_config: Option<PathBuf>,
+    /// Basic network config passed with -cfg, relative to the A3 root. Defaults to
+    /// `Users/server/Arma3.cfg` in the profile folder
+    #[serde(default)]
+    pub basic_config: Option<PathBuf>,
+}
+
+/// Performance and debug parameters passed to the server executable. Numeric values that are
+/// `None` are not passed, so the server uses its own default. Fields left out of the config get
+/// the values we have always launched with
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct LaunchParameters {
+    pub hugepages: bool,
+    pub max_mem: Option<u32>,
+    pub max_file_cache_size: Option<u32>,
+    pub cpu_count: Option<u32>,
+    pub ex_threads: Option<u32>,
+    pub enable_ht: bool,
+    pub bandwidth_alg: Option<u32>,
+    pub limit_fps: Option<u32>,
+    pub load_mission_to_memory: bool,
+    pub file_patching: bool,
+    /// Any other parameters, passed as is
+    pub extra: Vec<String>,
+}
+
+/// The values we have always launched the server with
+impl Default for LaunchParameters {
+    fn default() -> Self {
+        Self {
+            hugepages: true,
+            max_mem: Some(30000),
+            max_file_cache_size: Some(8192),
+            cpu_count: None,
+            ex_threads: None,
+            enable_ht: true,
+            bandwidth_alg: Some(2),
+            limit_fps: Some(1000),
+            load_mission_to_memory: true,
+            file_patching: false,
+            extra: Vec::new(),
+        }
+    }
+}
+
+/// On/off parameters, used to edit them from the GUI
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum LaunchFlag {
+    Hugepages,
+    EnableHt,
+    LoadMissionToMemory,
+    FilePatching,
+}
+
+impl LaunchFlag {
+    pub const ALL: [LaunchFlag; 4] = [
+        LaunchFlag::Hugepages,
+        LaunchFlag::EnableHt,
+        LaunchFlag::LoadMissionToMemory,
+        LaunchFlag::FilePatching,
+    ];
+
+    /// Name of the parameter as given to the server
+    pub fn name(&self) -> &'static str {
+        match self {
+            LaunchFlag::Hugepages => "hugepages",
+            LaunchFlag::EnableHt => "enableHT",
+            LaunchFlag::LoadMissionToMemory => "loadMissionToMemory",
+            LaunchFlag::FilePatching => "filePatching",
+        }
+    }
+}
+
+/// Numeric parameters, used to edit them from the GUI
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum LaunchValue {
+    MaxMem,
+    MaxFileCacheSize,
+    CpuCount,
+    ExThreads,
+    BandwidthAlg,
+    LimitFps,
+}
+
+impl LaunchValue {
+    pub const ALL: [LaunchValue; 6] = [
+        LaunchValue::MaxMem,
+        LaunchValue::MaxFileCacheSize,
+        LaunchValue::CpuCount,
+        LaunchValue::ExThreads,
+        LaunchValue::BandwidthAlg,
+        LaunchValue::LimitFps,
+    ];
+
+    /// Name of the parameter as given to the server
+    pub fn name(&self) -> &'static str {
+        match self {
+            LaunchValue::MaxMem => "maxMem",
+            LaunchValue::MaxFileCacheSize => "maxFileCacheSize",
+            LaunchValue::CpuCount => "cpuCount",
+            LaunchValue::ExThreads => "exThreads",
+            LaunchValue::BandwidthAlg => "bandwidthAlg",
+            LaunchValue::LimitFps => "limitFPS",
+        }
+    }
+}
+
+impl LaunchParameters {
+    pub fn flag(&self, flag: LaunchFlag) -> bool {
+        match flag {
+            LaunchFlag::Hugepages => self.hugepages,
+            LaunchFlag::EnableHt => self.enable_ht,
+            LaunchFlag::LoadMissionToMemory => self.load_mission_to_memory,
+            LaunchFlag::FilePatching => self.file_patching,
+        }
+    }
+
+    pub fn set_flag(&mut self, flag: LaunchFlag, enabled: bool) {
+        match flag {
+            LaunchFlag::Hugepages => self.hugepages = enabled,
+            LaunchFlag::EnableHt => self.enable_ht = enabled,
+            LaunchFlag::LoadMissionToMemory => self.load_mission_to_memory = enabled,
+            LaunchFlag::FilePatching => self.file_patching = enabled,
+        }
+    }
+
+    pub fn value(&self, value: LaunchValue) -> Option<u32> {
+        match value {
+            LaunchValue::MaxMem => self.max_mem,
+            LaunchValue::MaxFileCacheSize => self.max_file_cache_size,
+            LaunchValue::CpuCount => self.cpu_count,
+            LaunchValue::ExThreads => self.ex_threads,
+            LaunchValue::BandwidthAlg => self.bandwidth_alg,
+            LaunchValue::LimitFps => self.limit_fps,
+        }
+    }
+
+    pub fn set_value(&mut self, value: LaunchValue, new_value: Option<u32>) {
+        match value {
+            LaunchValue::MaxMem => self.max_mem = new_value,
+            LaunchValue::MaxFileCacheSize => self.max_file_cache_size = new_value,
+            LaunchValue::CpuCount => self.cpu_count = new_value,
+            LaunchValue::ExThreads => self.ex_threads = new_value,
+            LaunchValue::BandwidthAlg => self.bandwidth_alg = new_value,
+            LaunchValue::LimitFps => self.limit_fps = new_value,
+        }
+    }
+
+    /// Build the list of arguments to pass to the server executable
+    pub fn to_args(&self) -> Vec<String> {
+        let mut args = Vec::new();
+
+        if self.hugepages {
+            args.push(format!("-{}", LaunchFlag::Hugepages.name()));
+        }
+        for value in [LaunchValue::MaxMem, LaunchValue::MaxFileCacheSize] {
+            if let Some(v) = self.value(value) {
+                args.push(format!("-{}={}", value.name(), v));
+            }
+        }
+        if self.enable_ht {
+            args.push(format!("-{}", LaunchFlag::EnableHt.name()));
+        }
+        for value in [LaunchValue::BandwidthAlg, LaunchValue::LimitFps] {
+            if let Some(v) = self.value(value) {
+                args.push(format!("-{}={}", value.name(), v));
+            }
+        }
+        if self.load_mission_to_memory {
+            args.push(format!("-{}", LaunchFlag::LoadMissionToMemory.name()));
+        }
+        for value in [LaunchValue::CpuCount, LaunchValue::ExThreads] {
+            if let Some(v) = self.value(value) {
+                args.push(format!("-{}={}", value.name(), v));
+            }
+        }
+        if self.file_patching {
+            args.push(format!("-{}", LaunchFlag::FilePatching.name()));
+        }
+        args.extend(self.extra.iter().cloned());
+
+        args
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_launch_args() {
+        // defaults should give the parameters the launcher always used
+        assert_eq!(
+            LaunchParameters::default().to_args().join(" "),
+            "-hugepages -maxMem=30000 -maxFileCacheSize=8192 -enableHT -bandwidthAlg=2 -limitFPS=1000 -loadMissionToMemory"
+        );
+    }
+
+    #[test]
+    fn test_partial_launch_parameters() {
+        let settings: ProfileSettings =
+            toml::from_str("[launch_parameters]\ncpu_count = 4\nhugepages = false\n").unwrap();
+        assert_eq!(
+            settings.launch_parameters,
+            LaunchParameters {
+                cpu_count: Some(4),
+                hugepages: false,
+                ..Default::default()
+            }
+        );
+    }
+}
@@ -0,0 +1,42 @@
+//! Launches that run on a schedule, stored in the config
+use serde::{Deserialize, Serialize};
+
+/// Modlists, profile and port for a launch, chosen by name
+#[derive(Deserialize, Serialize, Debug, Clone, Default, PartialEq)]
+pub struct LaunchSelection {
+    /// Names of modlist files without `.txt`
+    #[serde(default)]
+    pub modpacks: Vec<String>,
+    #[serde(default)]
+    pub clientsides: Vec<String>,
+    #[serde(default)]
+    pub servermods: Vec<String>,
+    /// Named instance to launch, none for the single unnamed server
+    #[serde(default)]
+    pub instance: Option<String>,
+    /// Server profile, the one of the instance or the first profile in the config if not given
+    #[serde(default)]
+    pub profile: Option<String>,
+    /// Game port, the one of the instance or 2302 if not given
+    #[serde(default)]
+    pub port: Option<String>,
+}
+
+/// A server launch at the times given by a cron expression, like `0 4 * * *` for every night at
+/// 4. Whatever runs on the same port is stopped first, so this also does restarts
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+pub struct ScheduledJob {
+    pub name: String,
+    pub cron: String,
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    #[serde(flatten)]
+    pub selection: LaunchSelection,
+    /// Headless clients to start once the server is ready
+    #[serde(default)]
+    pub hcs: u64,
+}
+
+fn default_enabled() -> bool {
+    true
+}
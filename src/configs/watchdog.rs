@@ -0,0 +1,41 @@
+//! Settings for restarting processes that exit without being stopped from the launcher
+use serde::{Deserialize, Serialize};
+
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+pub struct WatchdogSettings {
+    /// Restart crashed processes. Crashes are reported either way
+    #[serde(default)]
+    pub enabled: bool,
+    /// Restarts in a row before giving up on a process
+    #[serde(default = "default_max_restarts")]
+    pub max_restarts: u32,
+    /// A process that ran this many minutes before crashing gets its restarts reset
+    #[serde(default = "default_reset_after_mins")]
+    pub reset_after_mins: u64,
+    /// Lines from the end of the RPT log kept with a crash
+    #[serde(default = "default_log_lines")]
+    pub log_lines: usize,
+}
+
+fn default_max_restarts() -> u32 {
+    3
+}
+
+fn default_reset_after_mins() -> u64 {
+    60
+}
+
+fn default_log_lines() -> usize {
+    20
+}
+
+impl Default for WatchdogSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            max_restarts: default_max_restarts(),
+            reset_after_mins: default_reset_after_mins(),
+            log_lines: default_log_lines(),
+        }
+    }
+}
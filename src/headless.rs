@@ -0,0 +1,411 @@
+//! Run launcher commands from the command line, without starting the GUI
+use crate::Config;
+use crate::arma::instances::{KeysInUse, check_keys_in_use};
+use crate::arma::keys::KeyConflicts;
+use crate::arma::mod_conflicts::ModConflicts;
+use crate::arma::mod_paths::{lowercase_mod, resolve_mod_path};
+use crate::arma::ports::{InvalidPort, PortsInUse, next_free_block, parse_port};
+use crate::arma::preset::Preset;
+use crate::arma::processes::{ProcessRecord, ProcessRegistry, scan_servers};
+use crate::arma::readiness::{Readiness, ReadinessWatch};
+use crate::arma::selection::{UnknownSelection, prepare_launch};
+use crate::arma::server_launch::{MissingBikeys, launch_hc};
+use crate::arma::server_modlist::{load_modlists, select_by_name, selected_mods};
+use crate::arma::validation::InvalidMods;
+use crate::configs::config::DEFAULT_PROCESS_FILE_PATH;
+use crate::configs::schedule::LaunchSelection;
+use crate::configs::{Command, ExportArgs, LaunchArgs, LowercaseArgs};
+use std::path::PathBuf;
+use std::process::ExitCode;
+use std::thread;
+use std::time::Duration;
+
+/// The config is invalid or a requested modlist or profile does not exist
+const EXIT_INVALID_INPUT: u8 = 2;
+/// One or more of the selected mods is missing a bikey
+const EXIT_MISSING_KEYS: u8 = 3;
+/// The server or a headless client failed to start
+const EXIT_LAUNCH_FAILED: u8 = 4;
+/// Two mods ship different keys with the same filename
+const EXIT_KEY_CONFLICT: u8 = 5;
+/// One or more of the selected mods is missing or empty
+const EXIT_INVALID_MODS: u8 = 6;
+/// Ports the server needs are taken by another program or server
+const EXIT_PORTS_IN_USE: u8 = 7;
+/// The keys folder would change while a server on the same A3 root is running
+const EXIT_KEYS_IN_USE: u8 = 8;
+/// Mods that a conflict rule forbids to load together are selected
+const EXIT_MOD_CONFLICT: u8 = 9;
+
+/// Run the given command and return the exit code for the process
+pub fn run(command: &Command, config: &Config) -> ExitCode {
+    if !config.is_config_valid() {
+        eprintln!("Config is not set up. Run the launcher once without a command to configure it");
+        return ExitCode::from(EXIT_INVALID_INPUT);
+    }
+    let problems = config.problems();
+    if !problems.is_empty() {
+        eprintln!("Config is invalid:\n{}", problems.join("\n"));
+        return ExitCode::from(EXIT_INVALID_INPUT);
+    }
+
+    match command {
+        Command::Launch(args) => launch(args, config),
+        Command::Lowercase(args) => lowercase(args, config),
+        Command::Export(args) => export(args, config),
+    }
+}
+
+/// A3 root of the named instance, or of the config without one. Unknown instances are reported
+fn instance_a3_root(config: &Config, instance: Option<&str>) -> Option<PathBuf> {
+    if let Some(name) = instance
+        && config.instance(name).is_none()
+    {
+        let names: Vec<&str> = config.instances.iter().map(|i| i.name.as_str()).collect();
+        eprintln!(
+            "Instance not found: {}. Available: {}",
+            name,
+            names.join(", ")
+        );
+        return None;
+    }
+    Some(config.instance_a3_root(instance))
+}
+
+fn export(args: &ExportArgs, config: &Config) -> ExitCode {
+    let Some(a3_root) = instance_a3_root(config, args.instance.as_deref()) else {
+        return ExitCode::from(EXIT_INVALID_INPUT);
+    };
+    let mut modpacks = load_modlists(&config.folder_modlists, &a3_root);
+    let mut clientsides = load_modlists(&config.folder_clientside, &a3_root);
+
+    let selection = select_by_name(&mut modpacks, &args.modpack)
+        .and_then(|_| select_by_name(&mut clientsides, &args.clientside));
+    if let Err(err) = selection {
+        eprintln!("{err}");
+        return ExitCode::from(EXIT_INVALID_INPUT);
+    }
+
+    let mut mods = selected_mods(&modpacks);
+    mods.extend(selected_mods(&clientsides));
+    let name = args.name.clone().unwrap_or_else(|| {
+        let modpacks: Vec<&str> = args.modpack.iter().map(String::as_str).collect();
+        Preset::default_name(&modpacks, &args.output)
+    });
+    let preset = Preset::from_mods(&name, &a3_root, &mods);
+
+    if let Err(err) = preset.save(&args.output) {
+        eprintln!("Failed to write {}: {err}", args.output.to_string_lossy());
+        return ExitCode::FAILURE;
+    }
+    let local = preset.mods.iter().filter(|m| m.workshop_id.is_none());
+    for entry in local {
+        println!(
+            "{} is not on the workshop, players have to install it by hand",
+            entry.name
+        );
+    }
+    println!("Wrote preset {} to {}", name, args.output.to_string_lossy());
+    ExitCode::SUCCESS
+}
+
+fn lowercase(args: &LowercaseArgs, config: &Config) -> ExitCode {
+    let Some(a3_root) = instance_a3_root(config, args.instance.as_deref()) else {
+        return ExitCode::from(EXIT_INVALID_INPUT);
+    };
+    let mut modpacks = load_modlists(&config.folder_modlists, &a3_root);
+    let mut clientsides = load_modlists(&config.folder_clientside, &a3_root);
+    let mut servermods = load_modlists(&config.folder_servermods, &a3_root);
+
+    let selection = select_by_name(&mut modpacks, &args.modpack)
+        .and_then(|_| select_by_name(&mut clientsides, &args.clientside))
+        .and_then(|_| select_by_name(&mut servermods, &args.servermod));
+    if let Err(err) = selection {
+        eprintln!("{err}");
+        return ExitCode::from(EXIT_INVALID_INPUT);
+    }
+
+    let mut mods = selected_mods(&modpacks);
+    mods.extend(selected_mods(&clientsides));
+    mods.extend(selected_mods(&servermods));
+    mods.sort();
+    mods.dedup();
+
+    let mut code = ExitCode::SUCCESS;
+    for mod_path in mods {
+        let folder = a3_root.join(resolve_mod_path(&a3_root, &mod_path));
+        if !folder.is_dir() {
+            eprintln!("{} does not exist", mod_path.to_string_lossy());
+            code = ExitCode::from(EXIT_INVALID_MODS);
+            continue;
+        }
+
+        match lowercase_mod(&folder, args.dry_run) {
+            Ok(report) if report.renamed.is_empty() && report.conflicts.is_empty() => {}
+            Ok(report) => println!("{}:\n{}", mod_path.to_string_lossy(), report),
+            Err(err) => {
+                eprintln!("Failed to rename {}: {err}", mod_path.to_string_lossy());
+                code = ExitCode::from(EXIT_INVALID_MODS);
+            }
+        }
+    }
+    code
+}
+
+fn launch(args: &LaunchArgs, config: &Config) -> ExitCode {
+    let mut registry = ProcessRegistry::load(&DEFAULT_PROCESS_FILE_PATH);
+    registry.refresh();
+
+    // an unknown instance is reported when preparing the launch
+    let instance = args
+        .instance
+        .as_deref()
+        .and_then(|name| config.instance(name));
+    let hcs = args.hcs.or(instance.map(|i| i.hcs)).unwrap_or(0);
+
+    let mut port = args.port.clone();
+    if args.auto_port {
+        let wanted = port
+            .clone()
+            .or(instance.map(|i| i.port.clone()))
+            .unwrap_or("2302".to_string());
+        let free = parse_port(&wanted)
+            .and_then(|start| next_free_block(start, &registry.server_ports(&[])));
+        match free {
+            Ok(free) => {
+                if free.to_string() != wanted.trim() {
+                    println!("Ports of {} are in use, using port {}", wanted, free);
+                }
+                port = Some(free.to_string());
+            }
+            Err(err) => {
+                eprintln!("{err}");
+                return ExitCode::from(EXIT_INVALID_INPUT);
+            }
+        }
+    }
+
+    let selection = LaunchSelection {
+        modpacks: args.modpack.clone(),
+        clientsides: args.clientside.clone(),
+        servermods: args.servermod.clone(),
+        instance: args.instance.clone(),
+        profile: args.profile.clone(),
+        port,
+    };
+
+    let (launch, mut plan) = match prepare_launch(config, &selection, &registry.server_ports(&[])) {
+        Ok(prepared) => prepared,
+        Err(err) => {
+            let code = exit_code(&err);
+            match code {
+                EXIT_INVALID_INPUT => eprintln!("{err}"),
+                EXIT_INVALID_MODS => eprintln!("Invalid mods in selected modlists:\n{err}"),
+                EXIT_MOD_CONFLICT => eprintln!("Conflicting mods in selected modlists:\n{err}"),
+                _ => eprintln!("Failed to launch server:\n{err}"),
+            }
+            return ExitCode::from(code);
+        }
+    };
+    // a running server on the same A3 root would lose the keys it checks players against
+    if let Err(err) = check_keys_in_use(
+        &registry,
+        &launch,
+        &plan,
+        &[],
+        &scan_servers(&launch.a3_root),
+    ) {
+        match args.dry_run || args.replace_keys {
+            true => plan.warnings.push(err.to_string()),
+            false => {
+                eprintln!("{err}\nStop it first, or pass --replace-keys to launch anyway");
+                return ExitCode::from(exit_code(&err));
+            }
+        }
+    }
+
+    // only show the plan, nothing is changed
+    if args.dry_run {
+        print!("{plan}");
+        if hcs > 0 {
+            println!("Headless clients:\n  {} on port {}", hcs, launch.port);
+        }
+        return ExitCode::SUCCESS;
+    }
+
+    // watch for the log of this launch, not an older one
+    let watch = ReadinessWatch::new(
+        launch.a3_root.join(&launch.profile),
+        config.hcs.ready_timeout(),
+    );
+
+    let child = match plan.execute(&registry.server_ports(&[])) {
+        Ok(child) => child,
+        Err(err) => {
+            eprintln!("Failed to launch server:\n{err}");
+            return ExitCode::from(exit_code(&err));
+        }
+    };
+    println!(
+        "Launched server with profile {} on port {} (pid {})",
+        launch.profile,
+        launch.port,
+        child.id()
+    );
+
+    let pid = child.id();
+    let record = ProcessRecord::server(pid, &launch);
+    if let Err(err) = registry.register(child, record) {
+        eprintln!("Failed to save process registry: {err}");
+    }
+
+    // HCs started while the server is still loading mods fail to connect
+    if hcs > 0 && !args.no_wait {
+        println!("Waiting for the server to be ready");
+        if let Err(code) = wait_until_ready(&mut registry, pid, watch) {
+            return code;
+        }
+    }
+
+    for i in 0..hcs {
+        if i > 0 && !args.no_wait {
+            thread::sleep(config.hcs.stagger());
+        }
+        let child = match launch_hc(
+            &launch.a3_root,
+            &launch.a3_executable,
+            &launch.port,
+            i,
+            launch.server_config.as_deref(),
+            launch.instance.as_deref(),
+        ) {
+            Ok(child) => child,
+            Err(err) => {
+                eprintln!("Failed to launch HC{i}: {err}");
+                return ExitCode::from(EXIT_LAUNCH_FAILED);
+            }
+        };
+        println!("Launched HC{i} (pid {})", child.id());
+
+        let record = ProcessRecord::headless_client(
+            child.id(),
+            i,
+            &launch.a3_root,
+            &launch.a3_executable,
+            &launch.port,
+            launch.server_config.as_deref(),
+            launch.instance.as_deref(),
+        );
+        if let Err(err) = registry.register(child, record) {
+            eprintln!("Failed to save process registry: {err}");
+        }
+    }
+
+    ExitCode::SUCCESS
+}
+
+/// Exit code for a failed launch, by the kind of error. Errors without their own code are launch
+/// failures
+fn exit_code(err: &anyhow::Error) -> u8 {
+    if err.downcast_ref::<UnknownSelection>().is_some()
+        || err.downcast_ref::<InvalidPort>().is_some()
+    {
+        EXIT_INVALID_INPUT
+    } else if err.downcast_ref::<MissingBikeys>().is_some() {
+        EXIT_MISSING_KEYS
+    } else if err.downcast_ref::<KeyConflicts>().is_some() {
+        EXIT_KEY_CONFLICT
+    } else if err.downcast_ref::<InvalidMods>().is_some() {
+        EXIT_INVALID_MODS
+    } else if err.downcast_ref::<PortsInUse>().is_some() {
+        EXIT_PORTS_IN_USE
+    } else if err.downcast_ref::<KeysInUse>().is_some() {
+        EXIT_KEYS_IN_USE
+    } else if err.downcast_ref::<ModConflicts>().is_some() {
+        EXIT_MOD_CONFLICT
+    } else {
+        EXIT_LAUNCH_FAILED
+    }
+}
+
+/// Block until the server log says it is ready. Fails if the server exits or takes too long
+fn wait_until_ready(
+    registry: &mut ProcessRegistry,
+    pid: u32,
+    mut watch: ReadinessWatch,
+) -> Result<(), ExitCode> {
+    loop {
+        thread::sleep(Duration::from_secs(1));
+
+        registry.refresh();
+        if !registry
+            .records
+            .iter()
+            .any(|r| r.pid == pid && r.is_running())
+        {
+            eprintln!("Server exited before it was ready, headless clients not started");
+            return Err(ExitCode::from(EXIT_LAUNCH_FAILED));
+        }
+
+        match watch.poll() {
+            Ok(Readiness::Waiting) => {}
+            Ok(Readiness::Ready(line)) => {
+                println!("Server ready: {line}");
+                return Ok(());
+            }
+            Ok(Readiness::TimedOut) => {
+                eprintln!("Server not ready in time, headless clients not started");
+                return Err(ExitCode::from(EXIT_LAUNCH_FAILED));
+            }
+            Err(err) => {
+                eprintln!("Failed to read the server log: {err}");
+                return Err(ExitCode::from(EXIT_LAUNCH_FAILED));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_exit_code() {
+        let errors: Vec<(anyhow::Error, u8)> = vec![
+            (
+                UnknownSelection("nope".to_string()).into(),
+                EXIT_INVALID_INPUT,
+            ),
+            (InvalidPort("70000".to_string()).into(), EXIT_INVALID_INPUT),
+            (MissingBikeys(Vec::new()).into(), EXIT_MISSING_KEYS),
+            (KeyConflicts(Vec::new()).into(), EXIT_KEY_CONFLICT),
+            (InvalidMods(Vec::new()).into(), EXIT_INVALID_MODS),
+            (
+                PortsInUse {
+                    port: 2302,
+                    used: vec![2303],
+                }
+                .into(),
+                EXIT_PORTS_IN_USE,
+            ),
+            (
+                KeysInUse {
+                    keys_folder: PathBuf::from("keys"),
+                    servers: Vec::new(),
+                }
+                .into(),
+                EXIT_KEYS_IN_USE,
+            ),
+            (ModConflicts(Vec::new()).into(), EXIT_MOD_CONFLICT),
+            (anyhow::anyhow!("spawn failed"), EXIT_LAUNCH_FAILED),
+        ];
+        for (err, code) in errors {
+            assert_eq!(exit_code(&err), code, "{err}");
+        }
+
+        // context added on the way up does not hide the kind of error
+        let err = anyhow::Error::from(MissingBikeys(Vec::new())).context("building the plan");
+        assert_eq!(exit_code(&err), EXIT_MISSING_KEYS);
+    }
+}
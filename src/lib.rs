@@ -3,7 +3,8 @@ mod configs;
 mod messages;
 mod ui;
 
+pub mod headless;
 pub mod logging;
 pub use arma::server_modlist::ServerModList;
-pub use configs::{Cli, Config, DEFAULT_LOG_FILE_PATH};
+pub use configs::{Cli, Command, Config, DEFAULT_LOG_FILE_PATH};
 pub use ui::App;
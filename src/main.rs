@@ -5,14 +5,25 @@ use clap::Parser as _;
 use corvus_launcher::Cli;
 use corvus_launcher::{App, Config};
 use iced::Theme::Dark;
+use std::process::ExitCode;
 use std::sync::{Arc, RwLock};
 
 /// RGBA bytes for the Logo. Generated with `build.rs`
 const LOGO: &[u8; 64 * 64 * 4] = include_bytes!(concat!(env!("OUT_DIR"), "/logo.bin"));
 
-fn main() -> anyhow::Result<()> {
+fn main() -> anyhow::Result<ExitCode> {
     // Parse command line arguments
-    let cli = Arc::new(Cli::parse());
+    let cli = match Cli::try_parse() {
+        Ok(cli) => Arc::new(cli),
+        Err(err) => {
+            attach_console();
+            err.exit();
+        }
+    };
+    // headless commands print their results, which needs a console to show up on windows
+    if cli.command.is_some() {
+        attach_console();
+    }
 
     // Setup logging
     corvus_launcher::logging::initialize(&cli);
@@ -22,18 +33,29 @@ fn main() -> anyhow::Result<()> {
 
     log::info!("Initialized CorvusLauncher");
 
+    // run headless command instead of the GUI if one is given
+    if let Some(command) = &cli.command {
+        return Ok(corvus_launcher::headless::run(
+            command,
+            &config.read().unwrap(),
+        ));
+    }
+
     // launch app
     iced::application(
         move || {
-            App::builder()
+            let mut app = App::builder()
                 .cli(Arc::clone(&cli))
                 .configs(Arc::clone(&config))
-                .build()
+                .build();
+            // count the size of the mods in the background
+            let task = app.refresh_sizes();
+            (app, task)
         },
         App::update,
         App::view,
     )
-    // .subscription(App::subscription)
+    .subscription(App::subscription)
     .window(iced::window::Settings {
         level: iced::window::Level::Normal,
         icon: Some(
@@ -46,5 +68,29 @@ fn main() -> anyhow::Result<()> {
     .theme(Dark)
     .default_font(iced::Font::MONOSPACE)
     .run()
-    .map_err(|err| anyhow!("Failed to start CorvusLauncher: {err}"))
+    .map_err(|err| anyhow!("Failed to start CorvusLauncher: {err}"))?;
+
+    Ok(ExitCode::SUCCESS)
 }
+
+/// Attach to the console of the terminal the launcher was started from. As a windows subsystem
+/// app it gets none of its own, so anything printed would be lost
+#[cfg(target_os = "windows")]
+fn attach_console() {
+    /// `AttachConsole` argument for the console of the parent process
+    const ATTACH_PARENT_PROCESS: u32 = u32::MAX;
+
+    #[link(name = "kernel32")]
+    unsafe extern "system" {
+        fn AttachConsole(process_id: u32) -> i32;
+    }
+
+    // fails when started from explorer, there is no console to attach to then
+    unsafe {
+        AttachConsole(ATTACH_PARENT_PROCESS);
+    }
+}
+
+/// Other platforms always print to the terminal the launcher was started from
+#[cfg(not(target_os = "windows"))]
+fn attach_console() {}
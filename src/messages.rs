@@ -1,5 +1,8 @@
 //! A message represents some event in the app that mutates the global state
+use crate::arma::mod_size::ModSize;
 use crate::ui;
+use std::collections::HashMap;
+use std::path::PathBuf;
 
 /// Represents an action happening in the application
 #[derive(Debug, Clone)]
@@ -12,16 +15,38 @@ pub enum Message {
     SelectionBoxUpdate(usize, ui::selection_listbox::Message),
     /// Update HC count
     HcInputChanged(ui::number_input::Message),
+    /// Update instance selected
+    InstanceChanged(ui::instance_chooser::Message),
     /// Update server profile selected
     ServerProfileChanged(ui::profile_chooser::Message),
+    /// launch parameter popup messages
+    ProfileSettingsUpdate(ui::profile_settings::Message),
     /// welcome view messages
     WelcomeViewMessage(ui::welcome_message::Message),
     /// port number change message
     ChangePortNumber(String),
-    /// Launch server
+    /// pick the next free ports on launch if the chosen ones are taken
+    ToggleAutoPort(bool),
+    /// Launch server, shows the launch preview first
     LaunchServer(),
+    /// launch preview messages, confirming or cancelling a launch
+    LaunchPreviewUpdate(ui::launch_preview::Message),
     /// Launch HCs
     LaunchHCs(),
+    /// Launch server, and the HCs once it is ready. Shows the launch preview first
+    LaunchAll(),
+    /// Write the selected modpacks and clientsides as an Arma 3 Launcher preset
+    ExportPreset(),
+    /// sizes of mod folders worked out in the background
+    SizesComputed(HashMap<PathBuf, ModSize>),
+    /// launch all progress messages
+    LaunchAllUpdate(ui::launch_all::Message),
+    /// process list messages, stop/restart of launched processes
+    ProcessListUpdate(ui::process_list::Message),
+    /// log viewer messages
+    LogViewerUpdate(ui::log_viewer::Message),
+    /// scheduled launches
+    SchedulerUpdate(ui::scheduler::Message),
 
     /// An error occured, display to the user
     Error(String),
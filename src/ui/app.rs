@@ -1,20 +1,40 @@
-use crate::arma::server_launch::{launch_hc, launch_server};
-use crate::arma::server_modlist::load_modlists;
+use crate::arma::dependencies::cache_patches;
+use crate::arma::instances::check_keys_in_use;
+use crate::arma::launch_plan::LaunchPlan;
+use crate::arma::load_order::order_selected;
+use crate::arma::mod_conflicts::{check_conflicts, combine_mods};
+use crate::arma::mod_size::{ModSize, folder_size, mod_folder, total_size};
+use crate::arma::ports::{next_free_block, parse_port};
+use crate::arma::preset::Preset;
+use crate::arma::processes::{ProcessRecord, scan_servers};
+use crate::arma::readiness::ReadinessWatch;
+use crate::arma::server_launch::{ServerLaunch, find_config, launch_hc};
+use crate::arma::server_modlist::{load_modlists, selected_mods};
+use crate::arma::validation::check_modlists;
+use crate::configs::config::DEFAULT_PROCESS_FILE_PATH;
 use crate::messages::Message;
 use crate::{Cli, Config, ServerModList};
 use iced::alignment::{Horizontal, Vertical};
 use iced::widget::space::{horizontal, vertical};
 use iced::widget::{Stack, rule, text_input};
-use iced::widget::{button, column, row, text};
-use iced::{Element, Length, Task};
+use iced::widget::{button, checkbox, column, row, text};
+use iced::{Element, Length, Subscription, Task};
+use std::collections::{HashMap, HashSet};
 use std::path::PathBuf;
 use std::sync::{Arc, RwLock};
 use std::time::{Duration, Instant};
 
-use super::Errors;
+use super::{Errors, background};
 use crate::ui;
+use crate::ui::instance_chooser::InstanceChooser;
+use crate::ui::launch_all::{LaunchAll, PendingHcs};
+use crate::ui::launch_preview::LaunchPreview;
+use crate::ui::log_viewer::LogViewer;
 use crate::ui::number_input::NumberInput;
+use crate::ui::process_list::ProcessList;
 use crate::ui::profile_chooser::ProfileChooser;
+use crate::ui::profile_settings::ProfileSettingsView;
+use crate::ui::scheduler::Scheduler;
 use crate::ui::selection_listbox::SelectionListbox;
 use crate::ui::welcome_message::WelcomeView;
 
@@ -33,6 +53,8 @@ pub struct App {
     pub cli: Arc<Cli>,
     /// port number for server and HC
     pub port_num: String,
+    /// move to the next free ports when launching, if the ports are taken
+    pub auto_port: bool,
 
     // Currently opened popup
     // pub popup: Option<Popup>,
@@ -42,54 +64,191 @@ pub struct App {
     /// Number of HCs to launch
     pub hc_launch_num: NumberInput,
 
+    /// Named instance chooser, only shown if the config has instances
+    pub instance_chooser: InstanceChooser,
+
     /// Server profile chooser
     pub server_profile_chooser: ProfileChooser,
 
+    /// popup to edit launch parameters of the selected profile
+    pub profile_settings: ProfileSettingsView,
+    /// which server config the selected profile will use, or why it cannot be used
+    pub server_config_label: String,
+
     /// welcome message - aka set config
     pub welcome_view: WelcomeView,
+
+    /// processes launched by the launcher
+    pub process_list: ProcessList,
+
+    /// confirmation popup for launching the server
+    pub launch_preview: LaunchPreview,
+
+    /// progress of launching the server and then the HCs
+    pub launch_all: LaunchAll,
+
+    /// popup following the RPT log of a launched process
+    pub log_viewer: LogViewer,
+
+    /// runs the scheduled launches from the config
+    pub scheduler: Scheduler,
+
+    /// size of each mod folder, so it is only counted once
+    pub mod_sizes: HashMap<PathBuf, ModSize>,
+    /// mod folders being counted in the background
+    pub sizing: HashSet<PathBuf>,
 }
 
 #[bon::bon]
 impl App {
     #[builder]
     pub fn new(cli: Arc<Cli>, configs: Arc<RwLock<Config>>) -> Self {
-        // make empty vectors
-        let mut modpacks: Vec<ServerModList> = vec![];
-        let mut clientside: Vec<ServerModList> = vec![];
-        let mut servermod: Vec<ServerModList> = vec![];
-
-        // try and load modlists from folders if config is valid
-        let c = configs.clone();
-        if c.read().unwrap().is_config_valid() {
-            // modpacks
-            modpacks = load_modlists(&c.read().unwrap().folder_modlists);
-            // clientsides
-            clientside = load_modlists(&c.read().unwrap().folder_clientside);
-            // servermods
-            servermod = load_modlists(&c.read().unwrap().folder_servermods);
-        }
-
-        Self {
+        let mut app = Self {
             time_started: Instant::now(),
             time_elapsed: Duration::ZERO,
             errors: Errors::default(),
             hc_launch_num: NumberInput::default(),
+            instance_chooser: InstanceChooser::new(configs.clone()),
             server_profile_chooser: ProfileChooser::new(
                 configs.clone().read().unwrap().server_profiles.clone(),
             ),
+            profile_settings: ProfileSettingsView::new(configs.clone()),
+            server_config_label: String::new(),
             welcome_view: WelcomeView::new(configs.clone()),
+            process_list: ProcessList::new(&DEFAULT_PROCESS_FILE_PATH, configs.clone()),
+            launch_preview: LaunchPreview::default(),
+            launch_all: LaunchAll::default(),
+            log_viewer: LogViewer::default(),
+            scheduler: Scheduler::new(configs.clone()),
+            mod_sizes: HashMap::new(),
+            sizing: HashSet::new(),
             config: configs,
             cli,
             port_num: "2302".to_string(),
+            auto_port: false,
             // popup: None,
             selection_listboxes: vec![
-                SelectionListbox::new(0, "Modpacks".parse().unwrap(), modpacks),
-                SelectionListbox::new(1, "Clientside".parse().unwrap(), clientside),
-                SelectionListbox::new(2, "Server mods".parse().unwrap(), servermod),
+                SelectionListbox::new(0, "Modpacks".parse().unwrap(), vec![]),
+                SelectionListbox::new(1, "Clientside".parse().unwrap(), vec![]),
+                SelectionListbox::new(2, "Server mods".parse().unwrap(), vec![]),
             ],
+        };
+        // try and load modlists from folders if config is valid
+        let valid = app.config.read().unwrap().is_config_valid();
+        if valid {
+            app.reload_modlists();
+        }
+        app.apply_instance();
+        app.update_server_config_label();
+        for problem in app.config.read().unwrap().problems() {
+            app.errors.push(problem);
+        }
+        app
+    }
+
+    /// Read the modlist folders again. Presets are matched against the mods in the A3 root of the
+    /// selected instance
+    fn reload_modlists(&mut self) {
+        let config = self.config.read().unwrap();
+        let a3_root = config.instance_a3_root(self.instance_chooser.selected().as_deref());
+        let folders = [
+            &config.folder_modlists,
+            &config.folder_clientside,
+            &config.folder_servermods,
+        ];
+        for (listbox, folder) in self.selection_listboxes.iter_mut().zip(folders) {
+            listbox.set_elements(load_modlists(folder, &a3_root));
+        }
+    }
+
+    /// Set profile, port and HCs to the ones of the selected instance
+    fn apply_instance(&mut self) {
+        let config = self.config.read().unwrap();
+        let Some(instance) = self
+            .instance_chooser
+            .selected()
+            .and_then(|name| config.instance(&name).cloned())
+        else {
+            return;
+        };
+
+        self.port_num = instance.port;
+        self.hc_launch_num.value = instance.hcs;
+        if let Some(profile) = &instance.profile {
+            self.server_profile_chooser.select(profile);
+        }
+    }
+
+    /// Work out the size of the mods that are not in the cache yet, in the background
+    pub fn refresh_sizes(&mut self) -> Task<Message> {
+        let a3_root = self
+            .config
+            .read()
+            .unwrap()
+            .instance_a3_root(self.instance_chooser.selected().as_deref());
+        let folders: HashSet<PathBuf> = self
+            .selection_listboxes
+            .iter()
+            .flat_map(|listbox| listbox.elements.iter())
+            .flat_map(|modlist| modlist.mods.iter())
+            .map(|mod_path| mod_folder(&a3_root, mod_path))
+            .filter(|folder| !self.mod_sizes.contains_key(folder) && !self.sizing.contains(folder))
+            .collect();
+
+        self.update_sizes();
+        if folders.is_empty() {
+            return Task::none();
+        }
+        self.sizing.extend(folders.iter().cloned());
+
+        // walking big mods takes a while, and so does reading their configs for the dependency check
+        background(
+            move || {
+                folders
+                    .into_iter()
+                    .map(|folder| {
+                        cache_patches(&folder);
+                        let size = folder_size(&folder);
+                        (folder, size)
+                    })
+                    .collect()
+            },
+            Message::SizesComputed,
+        )
+    }
+
+    /// Sizes of the modlists and of the selections, from the sizes known so far
+    fn update_sizes(&mut self) {
+        let a3_root = self
+            .config
+            .read()
+            .unwrap()
+            .instance_a3_root(self.instance_chooser.selected().as_deref());
+
+        for listbox in &mut self.selection_listboxes {
+            for modlist in &mut listbox.elements {
+                modlist.size = total_size(&self.mod_sizes, &a3_root, &modlist.mods);
+            }
+            listbox.selected_size =
+                total_size(&self.mod_sizes, &a3_root, &selected_mods(&listbox.elements));
         }
     }
 
+    /// Look up the server config of the selected profile again, after the profile or A3 root changed
+    fn update_server_config_label(&mut self) {
+        let config = self.config.read().unwrap();
+        let settings = config.profile_settings(&self.server_profile_chooser.get_selected_profile());
+        let a3_root = config.instance_a3_root(self.instance_chooser.selected().as_deref());
+
+        self.server_config_label = match find_config(&a3_root, settings.server_config.as_deref()) {
+            Ok(path) => format!(
+                "Server config: {}",
+                path.file_name().unwrap_or_default().to_string_lossy()
+            ),
+            Err(err) => err.to_string(),
+        };
+    }
+
     /// Renders the app
     pub fn view(&'_ self) -> iced::Element<'_, Message> {
         // conditionally set to Some or None to show this view
@@ -105,66 +264,152 @@ impl App {
 
         Stack::new()
             // Main window
-            .push(column![
-                // titel
-                text("CorvusLauncher")
-                    .width(Length::Fill)
-                    .size(40)
-                    .align_x(Horizontal::Center)
-                    .align_y(Vertical::Top),
-                vertical().height(20),
-                // listboxes
-                row(self
-                    .selection_listboxes
-                    .iter()
-                    .map(SelectionListbox::view)
-                    .enumerate()
-                    .map(|(index, listbox)| {
-                        // Here we turn our `Element<counter::Message>` into
-                        // an `Element<Message>` by combining the `index` and the
-                        // message of the `element`.
-                        listbox.map(move |message| Message::SelectionBoxUpdate(index, message))
-                    }),),
-                rule::horizontal(2),
-                row![
-                    self.server_profile_chooser
-                        .view(self)
-                        .map(Message::ServerProfileChanged),
-                    horizontal().width(20),
-                    column![
-                        text("Port").size(24),
-                        text_input("", &self.port_num)
-                            .on_input(Message::ChangePortNumber)
-                            .width(60)
-                            // .size(20)
-                            .align_x(Horizontal::Center),
-                        button("LAUNCH SERVER")
-                            .padding(10)
-                            .on_press(Message::LaunchServer()),
-                    ]
-                    .align_x(Horizontal::Center)
-                    .spacing(4),
-                    horizontal().width(20),
-                    column![
-                        text("HCs Amount").size(24),
-                        self.hc_launch_num.view(self).map(Message::HcInputChanged),
-                        button("LAUNCH HCs")
-                            .padding(10)
-                            .on_press(Message::LaunchHCs()),
+            .push(
+                column![
+                    // titel
+                    text("CorvusLauncher")
+                        .width(Length::Fill)
+                        .size(40)
+                        .align_x(Horizontal::Center)
+                        .align_y(Vertical::Top),
+                    vertical().height(20),
+                    // listboxes
+                    row(self
+                        .selection_listboxes
+                        .iter()
+                        .map(SelectionListbox::view)
+                        .enumerate()
+                        .map(|(index, listbox)| {
+                            // Here we turn our `Element<counter::Message>` into
+                            // an `Element<Message>` by combining the `index` and the
+                            // message of the `element`.
+                            listbox.map(move |message| Message::SelectionBoxUpdate(index, message))
+                        }),),
+                    rule::horizontal(2),
+                    row![
+                        self.instance_chooser
+                            .view(self)
+                            .map(|view| view.map(Message::InstanceChanged)),
+                        column![
+                            self.server_profile_chooser
+                                .view(self)
+                                .map(Message::ServerProfileChanged),
+                            button("Launch Parameters").padding(10).on_press(
+                                Message::ProfileSettingsUpdate(
+                                    ui::profile_settings::Message::Open(
+                                        self.server_profile_chooser.get_selected_profile(),
+                                        self.instance_chooser.selected(),
+                                    )
+                                )
+                            ),
+                            text(&self.server_config_label).size(14),
+                        ]
+                        .align_x(Horizontal::Center)
+                        .spacing(4),
+                        horizontal().width(20),
+                        column![
+                            text("Port").size(24),
+                            text_input("", &self.port_num)
+                                .on_input(Message::ChangePortNumber)
+                                .width(60)
+                                // .size(20)
+                                .align_x(Horizontal::Center),
+                            checkbox(self.auto_port)
+                                .label("Next free port")
+                                .on_toggle(Message::ToggleAutoPort),
+                            button("LAUNCH SERVER")
+                                .padding(10)
+                                .on_press(Message::LaunchServer()),
+                        ]
+                        .align_x(Horizontal::Center)
+                        .spacing(4),
+                        horizontal().width(20),
+                        column![
+                            text("HCs Amount").size(24),
+                            self.hc_launch_num.view(self).map(Message::HcInputChanged),
+                            button("LAUNCH HCs")
+                                .padding(10)
+                                .on_press(Message::LaunchHCs()),
+                        ]
+                        .align_x(Horizontal::Center)
+                        .spacing(4),
+                        horizontal().width(20),
+                        column![
+                            button("LAUNCH ALL")
+                                .padding(10)
+                                .on_press(Message::LaunchAll()),
+                            button("Export Preset")
+                                .padding(10)
+                                .on_press(Message::ExportPreset()),
+                        ]
+                        .align_x(Horizontal::Center)
+                        .spacing(4),
                     ]
-                    .align_x(Horizontal::Center)
-                    .spacing(4)
+                    .align_y(Vertical::Center)
+                    .spacing(100)
+                    .padding(15)
+                    .width(Length::Fill),
+                    rule::horizontal(2),
                 ]
-                .align_y(Vertical::Center)
-                .spacing(100)
-                .padding(15)
-                .width(Length::Fill)
-            ])
+                .push(
+                    self.launch_all
+                        .view(self)
+                        .map(|view| view.map(Message::LaunchAllUpdate)),
+                )
+                .push(
+                    self.scheduler
+                        .view(self)
+                        .map(|view| view.map(Message::SchedulerUpdate)),
+                )
+                .push(self.process_list.view(self).map(Message::ProcessListUpdate)),
+            )
+            .push(
+                self.launch_preview
+                    .view(self)
+                    .map(|view| view.map(Message::LaunchPreviewUpdate)),
+            )
+            .push(
+                self.log_viewer
+                    .view(self)
+                    .map(|view| view.map(Message::LogViewerUpdate)),
+            )
+            .push(
+                self.profile_settings
+                    .view(self)
+                    .map(|view| view.map(Message::ProfileSettingsUpdate)),
+            )
             .push(welcome_view)
             .push(self.errors.view(self, Message::ClearErrors))
             .into()
     }
 
+    /// Periodically check the status of launched processes, and the progress of launch all
+    pub fn subscription(&self) -> Subscription<Message> {
+        let refresh = iced::time::every(Duration::from_secs(2))
+            .map(|_| Message::ProcessListUpdate(ui::process_list::Message::Refresh()));
+
+        let mut subscriptions = vec![refresh];
+        if self.launch_all.is_active() {
+            subscriptions.push(
+                iced::time::every(Duration::from_secs(1))
+                    .map(|_| Message::LaunchAllUpdate(ui::launch_all::Message::Tick())),
+            );
+        }
+        if self.log_viewer.open {
+            subscriptions.push(
+                iced::time::every(Duration::from_secs(1))
+                    .map(|_| Message::LogViewerUpdate(ui::log_viewer::Message::Tick())),
+            );
+        }
+        if self.scheduler.has_jobs() {
+            subscriptions.push(
+                iced::time::every(Duration::from_secs(10))
+                    .map(|_| Message::SchedulerUpdate(ui::scheduler::Message::Tick())),
+            );
+        }
+        Subscription::batch(subscriptions)
+    }
+
     /// Modifies the app's state
     pub fn update(&mut self, message: Message) -> Task<Message> {
         match message {
@@ -175,32 +420,87 @@ impl App {
             Message::Error(err) => {
                 self.errors.push(err);
             }
+            Message::SelectionBoxUpdate(
+                index,
+                ui::selection_listbox::Message::ToggleDetails(modlist),
+            ) => {
+                let c = self.config.clone();
+                let config = c.read().unwrap();
+                let a3_root = config.instance_a3_root(self.instance_chooser.selected().as_deref());
+                if let Some(listbox) = self.selection_listboxes.get_mut(index) {
+                    return listbox
+                        .toggle_details(modlist, &a3_root)
+                        .map(move |msg| Message::SelectionBoxUpdate(index, msg));
+                }
+            }
             Message::SelectionBoxUpdate(index, listbox_msg) => {
                 if let Some(listbox) = self.selection_listboxes.get_mut(index) {
                     let _ = listbox.update(listbox_msg);
                 }
+                self.update_sizes();
             }
             Message::HcInputChanged(msg) => {
                 return self.hc_launch_num.update(msg).map(Message::HcInputChanged);
             }
+            Message::InstanceChanged(msg) => {
+                let root_of = |app: &App| {
+                    let selected = app.instance_chooser.selected();
+                    app.config
+                        .read()
+                        .unwrap()
+                        .instance_a3_root(selected.as_deref())
+                };
+                let previous_root = root_of(self);
+                let task = self.instance_chooser.update(msg);
+                // presets are matched against the mods of the A3 root
+                if root_of(self) != previous_root {
+                    self.reload_modlists();
+                }
+                self.apply_instance();
+                self.update_server_config_label();
+                // the instance can have its own A3 root, with other mod folders
+                return task
+                    .map(Message::InstanceChanged)
+                    .chain(self.refresh_sizes());
+            }
             Message::ServerProfileChanged(msg) => {
-                return self
-                    .server_profile_chooser
-                    .update(msg)
-                    .map(Message::ServerProfileChanged);
+                let task = self.server_profile_chooser.update(msg);
+                self.update_server_config_label();
+                return task.map(Message::ServerProfileChanged);
+            }
+            Message::ProfileSettingsUpdate(msg) => {
+                return match msg {
+                    ui::profile_settings::Message::Error(error) => {
+                        Task::done(Message::Error(error))
+                    }
+                    ui::profile_settings::Message::Save() => {
+                        // saving can change the server config of the profile
+                        let task = self.profile_settings.update(msg);
+                        self.update_server_config_label();
+                        task.map(Message::ProfileSettingsUpdate)
+                    }
+                    _ => self
+                        .profile_settings
+                        .update(msg)
+                        .map(Message::ProfileSettingsUpdate),
+                };
             }
             Message::WelcomeViewMessage(msg) => {
                 // handle specific reload message that has to run in parent view
                 if msg == ui::welcome_message::Message::ReloadViews() {
                     // Reload views depending on config values, such as the listboxes
-                    let c = self.config.clone();
-                    let modlist = load_modlists(&c.read().unwrap().folder_modlists);
-                    let clientside = load_modlists(&c.read().unwrap().folder_clientside);
-                    let servermods = load_modlists(&c.read().unwrap().folder_servermods);
-
-                    self.selection_listboxes.get_mut(0).unwrap().elements = modlist;
-                    self.selection_listboxes.get_mut(1).unwrap().elements = clientside;
-                    self.selection_listboxes.get_mut(2).unwrap().elements = servermods;
+                    self.reload_modlists();
+
+                    self.update_server_config_label();
+                    // mods may have changed on disk since they were counted
+                    self.mod_sizes.clear();
+                    self.sizing.clear();
+                    return Task::batch([
+                        self.refresh_sizes(),
+                        self.welcome_view
+                            .update(msg)
+                            .map(Message::WelcomeViewMessage),
+                    ]);
                 };
 
                 // Handle error or pass message on, has to return here as otherwise we would never get messages initiated in WelcomeViewMessage update()
@@ -215,72 +515,317 @@ impl App {
             Message::ChangePortNumber(new_port) => {
                 self.port_num = new_port;
             }
-            Message::LaunchServer() => {
-                // combine selected mods
-                let everyone_mods: Vec<PathBuf> = self
-                    .selection_listboxes
-                    .first()
-                    .unwrap()
-                    .elements
-                    .iter()
-                    .filter(|e| e.selected)
-                    .flat_map(|e| e.mods.clone())
-                    .collect();
-                let clientside_mods: Vec<PathBuf> = self
-                    .selection_listboxes
-                    .get(1)
-                    .unwrap()
-                    .elements
-                    .iter()
-                    .filter(|e| e.selected)
-                    .flat_map(|e| e.mods.clone())
-                    .collect();
-                let server_mods: Vec<PathBuf> = self
-                    .selection_listboxes
-                    .get(2)
-                    .unwrap()
-                    .elements
+            Message::ToggleAutoPort(auto_port) => {
+                self.auto_port = auto_port;
+            }
+            Message::LaunchServer() | Message::LaunchAll() => {
+                let launch_all = matches!(message, Message::LaunchAll());
+
+                // move to free ports, so the preview shows the port that is used
+                if self.auto_port {
+                    let reserved = self.process_list.registry.server_ports(&[]);
+                    match parse_port(&self.port_num)
+                        .and_then(|port| next_free_block(port, &reserved))
+                    {
+                        Ok(port) => self.port_num = port.to_string(),
+                        Err(err) => return Task::done(Message::Error(err.to_string())),
+                    }
+                }
+
+                let c = self.config.clone();
+                let config = c.read().unwrap();
+                let profile = self.server_profile_chooser.get_selected_profile();
+                let settings = config.profile_settings(&profile);
+                let instance = self.instance_chooser.selected();
+                let a3_root = config.instance_a3_root(instance.as_deref());
+
+                // combine selected mods once each, with the paths as they are on disk
+                let selected = combine_mods(
+                    &a3_root,
+                    &self.selection_listboxes[0].elements,
+                    &self.selection_listboxes[1].elements,
+                    &self.selection_listboxes[2].elements,
+                );
+                if let Err(err) = check_conflicts(&config.conflicts, &selected) {
+                    return Task::done(Message::Error(err.to_string()));
+                }
+                let selected = match order_selected(&config.load_order, selected) {
+                    Ok(selected) => selected,
+                    Err(err) => return Task::done(Message::Error(err.to_string())),
+                };
+
+                let mut download = selected.mods.clone();
+                download.extend(selected.clientsides.iter().cloned());
+                self.launch_preview.download_size =
+                    total_size(&self.mod_sizes, &a3_root, &download);
+                download.extend(selected.server_mods.iter().cloned());
+                self.launch_preview.selected_size =
+                    total_size(&self.mod_sizes, &a3_root, &download);
+
+                let launch = ServerLaunch::builder()
+                    .a3_root(a3_root.clone())
+                    .a3_executable(config.instance_executable(instance.as_deref()))
+                    .port(self.port_num.clone())
+                    .maybe_instance(instance)
+                    .parameters(settings.launch_parameters)
+                    .maybe_server_config(settings.server_config)
+                    .maybe_basic_config(settings.basic_config)
+                    .key_settings(config.keys.clone())
+                    .profile(profile)
+                    .mods(selected.mods)
+                    .clientsides(selected.clientsides)
+                    .server_mods(selected.server_mods)
+                    .build();
+
+                // halt on missing or empty mod folders, before the keys folder is touched
+                let lists: Vec<&ServerModList> = self.selection_listboxes[0..3]
                     .iter()
-                    .filter(|e| e.selected)
-                    .flat_map(|e| e.mods.clone())
+                    .flat_map(|listbox| listbox.elements.iter())
                     .collect();
+                let mut mod_warnings = match check_modlists(&launch.a3_root, &lists) {
+                    Ok(warnings) => warnings,
+                    Err(err) => return Task::done(Message::Error(err.to_string())),
+                };
+                mod_warnings.extend(selected.warnings);
 
+                // work out what the launch will do, and let the user confirm it before anything is changed
+                let reserved = self.process_list.registry.server_ports(&[]);
+                match LaunchPlan::build(&launch, &reserved) {
+                    Ok(mut plan) => {
+                        plan.warnings.extend(mod_warnings);
+                        let changes_keys = plan.keys.changes_folder();
+                        self.launch_preview.keys_in_use = None;
+                        self.launch_preview.scanning = changes_keys;
+                        self.launch_preview.pending = Some((launch, plan));
+                        self.launch_preview.launch_all = launch_all;
+
+                        // ask before changing keys a running server uses, servers started by
+                        // hand are looked for in the background as it takes a while
+                        if changes_keys {
+                            return background(
+                                move || scan_servers(&a3_root),
+                                |scanned| {
+                                    Message::LaunchPreviewUpdate(
+                                        ui::launch_preview::Message::Scanned(scanned),
+                                    )
+                                },
+                            );
+                        }
+                    }
+                    // handle error
+                    Err(err) => return Task::done(Message::Error(err.to_string())),
+                };
+            }
+            Message::LaunchPreviewUpdate(ui::launch_preview::Message::Scanned(scanned)) => {
+                // the launch may have been cancelled meanwhile
+                let Some((launch, plan)) = &self.launch_preview.pending else {
+                    return Task::none();
+                };
+                self.launch_preview.keys_in_use =
+                    check_keys_in_use(&self.process_list.registry, launch, plan, &[], &scanned)
+                        .err()
+                        .map(|err| err.to_string());
+                self.launch_preview.scanning = false;
+            }
+            Message::LaunchPreviewUpdate(ui::launch_preview::Message::Confirm()) => {
+                let Some((launch, plan)) = self.launch_preview.pending.take() else {
+                    return Task::none();
+                };
+                self.launch_preview.keys_in_use = None;
+                self.launch_preview.scanning = false;
+
+                // watch for the log of this launch, not an older one
                 let c = self.config.clone();
+                let config = c.read().unwrap();
+                let watch = ReadinessWatch::new(
+                    launch.a3_root.join(&launch.profile),
+                    config.hcs.ready_timeout(),
+                );
 
                 // launch server
-                let launch_result = launch_server(
-                    &c.read().unwrap().a3_root,
-                    &c.read().unwrap().a3_server_executable,
-                    &self.port_num,
-                    &self.server_profile_chooser.get_selected_profile(),
-                    everyone_mods,
-                    clientside_mods,
-                    server_mods,
-                );
-                // handle error
-                if let Err(err) = launch_result {
+                let child = match plan.execute(&self.process_list.registry.server_ports(&[])) {
+                    Ok(child) => child,
+                    // handle error
+                    Err(err) => return Task::done(Message::Error(err.to_string())),
+                };
+
+                let pid = child.id();
+                let record = ProcessRecord::server(pid, &launch);
+                if let Err(err) = self.process_list.registry.register(child, record) {
                     return Task::done(Message::Error(err.to_string()));
                 }
+
+                if self.launch_preview.launch_all {
+                    let hcs = PendingHcs::for_launch(
+                        &launch,
+                        self.hc_launch_num.value,
+                        config.hcs.stagger(),
+                    );
+                    self.launch_all.start(pid, watch, hcs);
+                }
+            }
+            Message::LaunchPreviewUpdate(msg) => {
+                return self
+                    .launch_preview
+                    .update(msg)
+                    .map(Message::LaunchPreviewUpdate);
             }
             Message::LaunchHCs() => {
+                if let Err(err) = parse_port(&self.port_num) {
+                    return Task::done(Message::Error(err.to_string()));
+                }
+
                 // get config
                 let c = self.config.clone();
+                let config = c.read().unwrap();
+                let server_config = config
+                    .profile_settings(&self.server_profile_chooser.get_selected_profile())
+                    .server_config;
+                let instance = self.instance_chooser.selected();
+                let a3_root = config.instance_a3_root(instance.as_deref());
+                let a3_executable = config.instance_executable(instance.as_deref());
 
                 // launch HCs
                 for i in 0..self.hc_launch_num.value {
                     let launch_result = launch_hc(
-                        &c.read().unwrap().a3_root,
-                        &c.read().unwrap().a3_server_executable,
+                        &a3_root,
+                        &a3_executable,
                         &self.port_num,
                         i,
+                        server_config.as_deref(),
+                        instance.as_deref(),
                     );
 
                     // handle error
-                    if let Err(err) = launch_result {
+                    let child = match launch_result {
+                        Ok(child) => child,
+                        Err(err) => return Task::done(Message::Error(err.to_string())),
+                    };
+
+                    let record = ProcessRecord::headless_client(
+                        child.id(),
+                        i,
+                        &a3_root,
+                        &a3_executable,
+                        &self.port_num,
+                        server_config.as_deref(),
+                        instance.as_deref(),
+                    );
+                    if let Err(err) = self.process_list.registry.register(child, record) {
                         return Task::done(Message::Error(err.to_string()));
                     }
                 }
             }
+            Message::SizesComputed(sizes) => {
+                for folder in sizes.keys() {
+                    self.sizing.remove(folder);
+                }
+                self.mod_sizes.extend(sizes);
+                self.update_sizes();
+            }
+            Message::ExportPreset() => {
+                let modpacks: Vec<&str> = self.selection_listboxes[0]
+                    .elements
+                    .iter()
+                    .filter(|e| e.selected)
+                    .map(|e| e.name.as_str())
+                    .collect();
+
+                let mut mods = selected_mods(&self.selection_listboxes[0].elements);
+                mods.extend(selected_mods(&self.selection_listboxes[1].elements));
+                if mods.is_empty() {
+                    return Task::done(Message::Error(
+                        "Select modpacks or clientsides to export".to_string(),
+                    ));
+                }
+
+                // we don't need async as nothing else in app has to run while picking
+                let Some(path) = rfd::FileDialog::new()
+                    .add_filter("Arma 3 Launcher preset", &["html"])
+                    .set_file_name(match modpacks.is_empty() {
+                        true => "preset.html".to_string(),
+                        false => format!("{}.html", modpacks.join(" + ")),
+                    })
+                    .save_file()
+                else {
+                    return Task::none();
+                };
+                let name = Preset::default_name(&modpacks, &path);
+
+                let c = self.config.clone();
+                let config = c.read().unwrap();
+                let a3_root = config.instance_a3_root(self.instance_chooser.selected().as_deref());
+                let preset = Preset::from_mods(&name, &a3_root, &mods);
+                if let Err(err) = preset.save(&path) {
+                    return Task::done(Message::Error(format!(
+                        "Failed to write preset {:?}: {}",
+                        path, err
+                    )));
+                }
+                log::info!("Exported preset {} to {:?}", name, path);
+            }
+            Message::LaunchAllUpdate(ui::launch_all::Message::Tick()) => {
+                if let Err(err) = self.launch_all.tick(&mut self.process_list.registry) {
+                    return Task::done(Message::Error(err.to_string()));
+                }
+            }
+            Message::LaunchAllUpdate(msg) => {
+                return self.launch_all.update(msg).map(Message::LaunchAllUpdate);
+            }
+            Message::SchedulerUpdate(ui::scheduler::Message::Tick()) => {
+                return self
+                    .scheduler
+                    .tick(self.launch_all.is_active())
+                    .map(Message::SchedulerUpdate);
+            }
+            Message::SchedulerUpdate(ui::scheduler::Message::Scanned(scanned)) => {
+                return self
+                    .scheduler
+                    .prepare(&mut self.process_list.registry, scanned)
+                    .map(Message::SchedulerUpdate);
+            }
+            Message::SchedulerUpdate(ui::scheduler::Message::Stopped(stopped)) => {
+                let started = self
+                    .scheduler
+                    .finish_job(&mut self.process_list.registry, stopped);
+                match started {
+                    // same as launch all, HCs follow once the server is ready
+                    Ok(Some((job, count))) => {
+                        let stagger = self.config.read().unwrap().hcs.stagger();
+                        let hcs = PendingHcs::for_launch(&job.launch, count, stagger);
+                        self.launch_all.start(job.pid, job.watch, hcs);
+                    }
+                    Ok(None) => {}
+                    Err(err) => return Task::done(Message::Error(format!("{:#}", err))),
+                }
+            }
+            Message::SchedulerUpdate(ui::scheduler::Message::Error(err)) => {
+                return Task::done(Message::Error(err));
+            }
+            Message::LogViewerUpdate(ui::log_viewer::Message::Tick()) => {
+                if let Err(err) = self.log_viewer.poll(&self.process_list.registry) {
+                    return Task::done(Message::Error(err.to_string()));
+                }
+            }
+            Message::LogViewerUpdate(msg) => {
+                let task = self.log_viewer.update(msg).map(Message::LogViewerUpdate);
+                // read the log right away instead of waiting for the next tick
+                return task.chain(Task::done(Message::LogViewerUpdate(
+                    ui::log_viewer::Message::Tick(),
+                )));
+            }
+            Message::ProcessListUpdate(msg) => {
+                return match msg {
+                    ui::process_list::Message::Error(error) => Task::done(Message::Error(error)),
+                    ui::process_list::Message::ShowLog(pid) => {
+                        Task::done(Message::LogViewerUpdate(ui::log_viewer::Message::Open(pid)))
+                    }
+                    _ => self
+                        .process_list
+                        .update(msg)
+                        .map(Message::ProcessListUpdate),
+                };
+            }
             Message::ClearErrors() => {
                 // clear errors
                 self.errors.errors.clear();
@@ -0,0 +1,72 @@
+//! Choose which named instance the launch controls are for, with what is running for it
+use crate::Config;
+use crate::arma::instances::{instance_status, sharing_a3_root};
+use iced::alignment::Horizontal;
+use iced::widget::{column, pick_list, text};
+use iced::{Color, Element, Task};
+use std::sync::{Arc, RwLock};
+
+#[derive(Debug)]
+pub struct InstanceChooser {
+    config: Arc<RwLock<Config>>,
+    selected: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub enum Message {
+    /// also sets profile, port and HCs of the instance, handled in main app as it owns those
+    InstanceSelected(String),
+}
+
+impl InstanceChooser {
+    pub fn new(config: Arc<RwLock<Config>>) -> InstanceChooser {
+        let selected = config
+            .read()
+            .unwrap()
+            .instances
+            .first()
+            .map(|i| i.name.clone());
+        Self { config, selected }
+    }
+
+    /// The selected instance, none if there are no instances in the config
+    pub fn selected(&self) -> Option<String> {
+        self.selected.clone()
+    }
+
+    pub fn update(&mut self, message: Message) -> Task<Message> {
+        match message {
+            Message::InstanceSelected(name) => self.selected = Some(name),
+        }
+        Task::none()
+    }
+
+    pub fn view<'app>(&'app self, app: &'app super::App) -> Option<Element<'app, Message>> {
+        let config = self.config.read().unwrap();
+        if config.instances.is_empty() {
+            return None;
+        }
+
+        let names: Vec<String> = config.instances.iter().map(|i| i.name.clone()).collect();
+        let instance = self.selected.as_deref();
+        let shared = sharing_a3_root(&config, instance);
+
+        let mut content = column![
+            text("Instance").size(24),
+            pick_list(names, self.selected.clone(), Message::InstanceSelected),
+            text(instance_status(&app.process_list.registry, instance)).size(14),
+        ]
+        .align_x(Horizontal::Center)
+        .spacing(4);
+
+        if !shared.is_empty() {
+            content = content.push(
+                text(format!("Shares its keys folder with {}", shared.join(", ")))
+                    .size(14)
+                    .color(Color::from_rgb8(250, 200, 90)),
+            );
+        }
+
+        Some(content.into())
+    }
+}
@@ -0,0 +1,224 @@
+//! Launch all: start the server, wait for its RPT log to say it is ready, then start the headless
+//! clients one after another. Each step is listed so the admin can follow along
+use crate::arma::processes::{ProcessRecord, ProcessRegistry};
+use crate::arma::readiness::{Readiness, ReadinessWatch};
+use crate::arma::server_launch::{ServerLaunch, launch_hc};
+use anyhow::Result;
+use iced::alignment::Vertical;
+use iced::widget::{Column, button, column, row, text};
+use iced::{Element, Task};
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+#[derive(Debug, Default)]
+pub struct LaunchAll {
+    /// what has happened so far
+    pub steps: Vec<String>,
+    stage: Stage,
+}
+
+/// Headless clients to start once the server is ready
+#[derive(Debug, Clone)]
+pub struct PendingHcs {
+    pub count: u64,
+    pub a3_root: PathBuf,
+    pub a3_executable: PathBuf,
+    pub instance: Option<String>,
+    pub port: String,
+    pub server_config: Option<PathBuf>,
+    pub stagger: Duration,
+}
+
+impl PendingHcs {
+    /// HCs joining the server of the launch
+    pub fn for_launch(launch: &ServerLaunch, count: u64, stagger: Duration) -> PendingHcs {
+        PendingHcs {
+            count,
+            a3_root: launch.a3_root.clone(),
+            a3_executable: launch.a3_executable.clone(),
+            instance: launch.instance.clone(),
+            port: launch.port.clone(),
+            server_config: launch.server_config.clone(),
+            stagger,
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+enum Stage {
+    #[default]
+    Idle,
+    WaitingForServer {
+        pid: u32,
+        watch: ReadinessWatch,
+        hcs: PendingHcs,
+    },
+    LaunchingHcs {
+        hcs: PendingHcs,
+        next: u64,
+        next_at: Instant,
+    },
+}
+
+#[derive(Debug, Clone)]
+pub enum Message {
+    /// check the server log or start the next HC, handled in main app as it owns the process list
+    Tick(),
+    /// stop waiting, HCs that are not started yet are not started
+    Cancel(),
+    /// clear the steps of a finished launch
+    Clear(),
+}
+
+impl LaunchAll {
+    pub fn is_active(&self) -> bool {
+        !matches!(self.stage, Stage::Idle)
+    }
+
+    /// Wait for the server that was just started, then start the HCs
+    pub fn start(&mut self, pid: u32, watch: ReadinessWatch, hcs: PendingHcs) {
+        self.steps = vec![format!(
+            "Started server (pid {}), waiting for it to be ready",
+            pid
+        )];
+        self.stage = Stage::WaitingForServer { pid, watch, hcs };
+    }
+
+    pub fn update(&mut self, message: Message) -> Task<Message> {
+        match message {
+            // needs the process registry, so the app calls tick
+            Message::Tick() => {}
+            Message::Cancel() => {
+                if self.is_active() {
+                    self.steps.push("Cancelled".to_string());
+                }
+                self.stage = Stage::Idle;
+            }
+            Message::Clear() => self.steps.clear(),
+        }
+        Task::none()
+    }
+
+    /// Move the launch along. Started HCs are added to the registry
+    pub fn tick(&mut self, registry: &mut ProcessRegistry) -> Result<()> {
+        match std::mem::take(&mut self.stage) {
+            Stage::Idle => {}
+            Stage::WaitingForServer {
+                pid,
+                mut watch,
+                hcs,
+            } => {
+                let running = registry
+                    .records
+                    .iter()
+                    .any(|r| r.pid == pid && r.is_running());
+                if !running {
+                    self.steps.push(
+                        "Server exited before it was ready, headless clients not started"
+                            .to_string(),
+                    );
+                    return Ok(());
+                }
+
+                match watch.poll() {
+                    Ok(Readiness::Waiting) => {
+                        self.stage = Stage::WaitingForServer { pid, watch, hcs };
+                    }
+                    Ok(Readiness::Ready(line)) => {
+                        self.steps.push(format!("Server ready: {}", line));
+                        match hcs.count {
+                            0 => self.steps.push("Done".to_string()),
+                            _ => {
+                                self.stage = Stage::LaunchingHcs {
+                                    hcs,
+                                    next: 0,
+                                    next_at: Instant::now(),
+                                }
+                            }
+                        }
+                    }
+                    Ok(Readiness::TimedOut) => self.steps.push(format!(
+                        "Server not ready after {} seconds, headless clients not started",
+                        watch.timeout().as_secs()
+                    )),
+                    Err(err) => {
+                        self.steps.push("Failed to read the server log".to_string());
+                        return Err(err);
+                    }
+                }
+            }
+            Stage::LaunchingHcs { hcs, next, next_at } => {
+                if Instant::now() < next_at {
+                    self.stage = Stage::LaunchingHcs { hcs, next, next_at };
+                    return Ok(());
+                }
+
+                let child = match launch_hc(
+                    &hcs.a3_root,
+                    &hcs.a3_executable,
+                    &hcs.port,
+                    next,
+                    hcs.server_config.as_deref(),
+                    hcs.instance.as_deref(),
+                ) {
+                    Ok(child) => child,
+                    Err(err) => {
+                        self.steps.push(format!("Failed to start HC{}", next));
+                        return Err(err);
+                    }
+                };
+                self.steps
+                    .push(format!("Started HC{} (pid {})", next, child.id()));
+
+                let record = ProcessRecord::headless_client(
+                    child.id(),
+                    next,
+                    &hcs.a3_root,
+                    &hcs.a3_executable,
+                    &hcs.port,
+                    hcs.server_config.as_deref(),
+                    hcs.instance.as_deref(),
+                );
+                registry.register(child, record)?;
+
+                match next + 1 < hcs.count {
+                    true => {
+                        self.stage = Stage::LaunchingHcs {
+                            next: next + 1,
+                            next_at: Instant::now() + hcs.stagger,
+                            hcs,
+                        }
+                    }
+                    false => self.steps.push("Done".to_string()),
+                }
+            }
+        }
+        Ok(())
+    }
+
+    pub fn view<'app>(&'app self, _app: &'app super::App) -> Option<Element<'app, Message>> {
+        if self.steps.is_empty() {
+            return None;
+        }
+
+        let control = match self.is_active() {
+            true => button("Cancel").on_press(Message::Cancel()),
+            false => button("Clear").on_press(Message::Clear()),
+        };
+
+        Some(
+            column![
+                row![text("Launch All").size(24), control]
+                    .spacing(10)
+                    .align_y(Vertical::Center),
+                self.steps
+                    .iter()
+                    .map(|step| text(step).size(14).into())
+                    .collect::<Column<_>>(),
+            ]
+            .spacing(4)
+            .padding(15)
+            .into(),
+        )
+    }
+}
@@ -0,0 +1,232 @@
+//! Confirmation popup showing what a server launch is going to do, before anything is changed
+
+use crate::arma::keys::ModKey;
+use crate::arma::launch_plan::{LaunchPlan, mod_list};
+use crate::arma::mod_size::ModSize;
+use crate::arma::server_launch::{ServerLaunch, mods_launch_string};
+use crate::ui::popup::{backdrop, popup_style};
+use iced::alignment::{Horizontal, Vertical};
+use iced::widget::space::vertical;
+use iced::widget::{Column, button, column, container, row, scrollable, text};
+use iced::{Color, Element, Length, Task};
+
+#[derive(Debug, Default)]
+pub struct LaunchPreview {
+    /// the launch waiting for confirmation
+    pub pending: Option<(ServerLaunch, LaunchPlan)>,
+    /// start the HCs once the server is ready
+    pub launch_all: bool,
+    /// servers on the same A3 root that would have their keys changed, launching needs to be
+    /// confirmed again
+    pub keys_in_use: Option<String>,
+    /// running servers are still looked for, the launch can not be confirmed yet
+    pub scanning: bool,
+    /// size of the modpacks and clientsides players have to download, `None` while it is counted
+    pub download_size: Option<ModSize>,
+    /// size of all selected mods, server mods included, `None` while it is counted
+    pub selected_size: Option<ModSize>,
+}
+
+#[derive(Debug, Clone)]
+pub enum Message {
+    /// launch the pending plan, handled in main app as it owns the process list
+    Confirm(),
+    /// discard the pending plan
+    Cancel(),
+    /// the servers running from the A3 root of the launch were looked for. Handled in main app as
+    /// it owns the process list
+    Scanned(Vec<u32>),
+    /// move the mod at the index one place earlier in the load order
+    MoveUp(usize),
+    /// move the mod at the index one place later in the load order
+    MoveDown(usize),
+}
+
+impl LaunchPreview {
+    pub fn update(&mut self, message: Message) -> Task<Message> {
+        match message {
+            // the app builds the launch from the plan
+            Message::Confirm() | Message::Scanned(_) => Task::none(),
+            Message::Cancel() => {
+                self.pending = None;
+                self.keys_in_use = None;
+                self.scanning = false;
+                Task::none()
+            }
+            Message::MoveUp(index) if index > 0 => self.swap_mods(index - 1, index),
+            Message::MoveDown(index) => self.swap_mods(index, index + 1),
+            Message::MoveUp(_) => Task::none(),
+        }
+    }
+
+    /// Swap two mods in the load order, and the parameter file with them
+    fn swap_mods(&mut self, a: usize, b: usize) -> Task<Message> {
+        if let Some((launch, plan)) = &mut self.pending
+            && b < launch.mods.len()
+        {
+            launch.mods.swap(a, b);
+            plan.par_contents = mods_launch_string(&launch.mods);
+        }
+        Task::none()
+    }
+
+    pub fn view<'app>(&'app self, _app: &'app super::App) -> Option<Element<'app, Message>> {
+        let (launch, plan) = self.pending.as_ref()?;
+
+        let mut details = column![
+            section_title("Command"),
+            text(plan.command_line()).size(14),
+            section_title(&format!(
+                "Parameter file {}",
+                plan.par_file.to_string_lossy()
+            )),
+            text(&plan.par_contents).size(14),
+            section_title("Load order"),
+            load_order(&launch.mods),
+            section_title(&format!(
+                "Server config {}",
+                plan.config_file.to_string_lossy()
+            )),
+            text(plan.config_summary()).size(14),
+            section_title(&format!(
+                "Keys to remove from {}",
+                plan.keys_folder.to_string_lossy()
+            )),
+            path_list(&plan.keys.remove),
+            section_title("Keys to copy"),
+            key_list(&plan.keys.copy),
+            text(format!(
+                "Keys already in place: {}",
+                plan.keys.unchanged.len()
+            ))
+            .size(14),
+            section_title("Mod sizes"),
+            text(format!(
+                "Players download: {}",
+                size_label(self.download_size)
+            ))
+            .size(14),
+            text(format!(
+                "All selected mods: {}",
+                size_label(self.selected_size)
+            ))
+            .size(14),
+            section_title("Key sources"),
+            plan.key_sources
+                .iter()
+                .map(|(name, mods)| text(format!("{}: {}", name, mod_list(mods)))
+                    .size(14)
+                    .into())
+                .collect::<Column<_>>(),
+        ]
+        .spacing(4);
+
+        if !plan.warnings.is_empty() {
+            details = details.push(section_title("Warnings")).push(
+                plan.warnings
+                    .iter()
+                    .map(|warning| text(warning).size(14).into())
+                    .collect::<Column<_>>(),
+            );
+        }
+
+        if let Some(keys_in_use) = &self.keys_in_use {
+            details = details.push(section_title("Keys folder in use")).push(
+                text(keys_in_use)
+                    .size(14)
+                    .color(Color::from_rgb8(255, 80, 80)),
+            );
+        }
+
+        let launch = match (self.scanning, &self.keys_in_use) {
+            (true, _) => button("Checking for running servers"),
+            (false, Some(_)) => button("Launch anyway").on_press(Message::Confirm()),
+            (false, None) => button("Launch").on_press(Message::Confirm()),
+        };
+
+        let popup = container(column![
+            text(match self.launch_all {
+                true => "Launch Server and HCs?",
+                false => "Launch Server?",
+            })
+            .size(30)
+            .align_x(Horizontal::Center)
+            .width(Length::Fill),
+            vertical().height(10.0),
+            scrollable(details).height(Length::Fill),
+            vertical().height(10.0),
+            container(row![launch, button("Cancel").on_press(Message::Cancel()),].spacing(10))
+                .center_x(Length::Fill)
+        ])
+        .padding(20)
+        .width(Length::Fixed(900.0))
+        .height(Length::Fixed(600.0))
+        .style(popup_style);
+
+        Some(backdrop(popup))
+    }
+}
+
+fn section_title(title: &str) -> Element<'static, Message> {
+    text(title.to_string()).size(20).into()
+}
+
+fn path_list(paths: &[std::path::PathBuf]) -> Element<'_, Message> {
+    match paths.is_empty() {
+        true => text("none").size(14).into(),
+        false => paths
+            .iter()
+            .map(|path| text(path.to_string_lossy()).size(14).into())
+            .collect::<Column<_>>()
+            .into(),
+    }
+}
+
+/// The mods in the order they are loaded, with buttons to move them
+fn load_order(mods: &[std::path::PathBuf]) -> Element<'_, Message> {
+    match mods.is_empty() {
+        true => text("none").size(14).into(),
+        false => mods
+            .iter()
+            .enumerate()
+            .map(|(i, path)| {
+                row![
+                    button(text("Up").size(12))
+                        .padding([2, 6])
+                        .on_press_maybe((i > 0).then_some(Message::MoveUp(i))),
+                    button(text("Down").size(12))
+                        .padding([2, 6])
+                        .on_press_maybe((i + 1 < mods.len()).then_some(Message::MoveDown(i))),
+                    text(format!("{}. {}", i + 1, path.to_string_lossy())).size(14),
+                ]
+                .spacing(6)
+                .align_y(Vertical::Center)
+                .into()
+            })
+            .collect::<Column<_>>()
+            .spacing(2)
+            .into(),
+    }
+}
+
+fn key_list(keys: &[ModKey]) -> Element<'_, Message> {
+    match keys.is_empty() {
+        true => text("none").size(14).into(),
+        false => keys
+            .iter()
+            .map(|key| {
+                text(format!("{} -> {}", key.source.to_string_lossy(), key.name))
+                    .size(14)
+                    .into()
+            })
+            .collect::<Column<_>>()
+            .into(),
+    }
+}
+
+fn size_label(size: Option<ModSize>) -> String {
+    match size {
+        Some(size) => size.to_string(),
+        None => "still counting...".to_string(),
+    }
+}
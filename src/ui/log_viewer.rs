@@ -0,0 +1,202 @@
+//! Popup following the RPT log of the server or a headless client, with search and severity filter
+use crate::arma::processes::{ProcessRecord, ProcessRegistry};
+use crate::arma::rpt::{RptTail, Severity, newest_rpt};
+use crate::ui::popup::{backdrop, popup_style};
+use anyhow::Result;
+use iced::alignment::{Horizontal, Vertical};
+use iced::widget::space::vertical;
+use iced::widget::{
+    Column, button, column, container, pick_list, row, scrollable, text, text_input,
+};
+use iced::{Color, Element, Length, Task};
+use std::collections::HashMap;
+use std::time::SystemTime;
+
+/// Lines kept per log, older lines are dropped
+const MAX_LINES: usize = 5000;
+/// Lines shown at once, the newest that match the filter
+const SHOWN_LINES: usize = 500;
+
+#[derive(Debug, Default)]
+pub struct LogViewer {
+    /// show the popup
+    pub open: bool,
+    /// pid of the process whose log is shown
+    selected: Option<u32>,
+    /// processes to choose from, updated on every poll
+    sources: Vec<LogSource>,
+    logs: HashMap<u32, InstanceLog>,
+    search: String,
+    min_severity: Severity,
+}
+
+#[derive(Debug, Default)]
+struct InstanceLog {
+    /// found once the process has written its RPT
+    tail: Option<RptTail>,
+    lines: Vec<(Severity, String)>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct LogSource {
+    pid: u32,
+    label: String,
+}
+
+impl std::fmt::Display for LogSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.label)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum Message {
+    /// open popup with the log of the process with pid
+    Open(u32),
+    Close(),
+    Select(LogSource),
+    Search(String),
+    Filter(Severity),
+    /// read new lines, handled in main app as it owns the process list
+    Tick(),
+}
+
+impl LogViewer {
+    pub fn update(&mut self, message: Message) -> Task<Message> {
+        match message {
+            Message::Open(pid) => {
+                self.selected = Some(pid);
+                self.open = true;
+            }
+            Message::Close() => self.open = false,
+            Message::Select(source) => self.selected = Some(source.pid),
+            Message::Search(search) => self.search = search,
+            Message::Filter(severity) => self.min_severity = severity,
+            Message::Tick() => {}
+        }
+        Task::none()
+    }
+
+    /// Read new lines from the log of the selected process
+    pub fn poll(&mut self, registry: &ProcessRegistry) -> Result<()> {
+        self.sources = registry.records.iter().map(log_source).collect();
+        // forget logs of processes that were removed from the list
+        self.logs
+            .retain(|pid, _| registry.records.iter().any(|r| r.pid == *pid));
+
+        let Some(record) = registry
+            .records
+            .iter()
+            .find(|r| Some(r.pid) == self.selected)
+        else {
+            return Ok(());
+        };
+
+        let log = self.logs.entry(record.pid).or_default();
+        if log.tail.is_none() {
+            // the RPT of this run was written to after the process started
+            let started = SystemTime::from(record.started);
+            log.tail = newest_rpt(&record.profile_folder(), Some(started)).map(RptTail::from_end);
+        }
+
+        if let Some(tail) = &mut log.tail {
+            let new_lines = tail.read_new_lines()?;
+            log.lines.extend(
+                new_lines
+                    .into_iter()
+                    .map(|line| (Severity::of(&line), line)),
+            );
+            if log.lines.len() > MAX_LINES {
+                log.lines.drain(..log.lines.len() - MAX_LINES);
+            }
+        }
+        Ok(())
+    }
+
+    pub fn view<'app>(&'app self, _app: &'app super::App) -> Option<Element<'app, Message>> {
+        if !self.open {
+            return None;
+        }
+
+        let log = self.selected.and_then(|pid| self.logs.get(&pid));
+        let search = self.search.to_lowercase();
+        let mut matching: Vec<&(Severity, String)> = log
+            .map(|log| {
+                log.lines
+                    .iter()
+                    .filter(|(severity, line)| {
+                        *severity >= self.min_severity
+                            && (search.is_empty() || line.to_lowercase().contains(&search))
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+        let hidden = matching.len().saturating_sub(SHOWN_LINES);
+        matching.drain(..hidden);
+
+        let status = match log.and_then(|log| log.tail.as_ref()) {
+            Some(tail) => tail.path.to_string_lossy().to_string(),
+            None => "No RPT found for this process yet".to_string(),
+        };
+
+        let lines = matching
+            .into_iter()
+            .map(|(severity, line)| text(line).size(13).color(severity_color(*severity)).into())
+            .collect::<Column<_>>();
+
+        let selected = self
+            .sources
+            .iter()
+            .find(|s| Some(s.pid) == self.selected)
+            .cloned();
+
+        let popup = container(column![
+            text("Logs")
+                .size(30)
+                .align_x(Horizontal::Center)
+                .width(Length::Fill),
+            row![
+                pick_list(self.sources.clone(), selected, Message::Select),
+                text("Show at least"),
+                pick_list(Severity::ALL, Some(self.min_severity), Message::Filter),
+                text_input("Search", &self.search).on_input(Message::Search),
+            ]
+            .spacing(10)
+            .align_y(Vertical::Center),
+            text(match hidden {
+                0 => status,
+                hidden => format!("{} ({} older lines not shown)", status, hidden),
+            })
+            .size(13),
+            vertical().height(10.0),
+            scrollable(lines)
+                .anchor_bottom()
+                .height(Length::Fill)
+                .width(Length::Fill),
+            vertical().height(10.0),
+            container(button("Close").on_press(Message::Close())).center_x(Length::Fill)
+        ])
+        .padding(20)
+        .width(Length::Fixed(1100.0))
+        .height(Length::Fixed(700.0))
+        .style(popup_style);
+
+        Some(backdrop(popup))
+    }
+}
+
+fn log_source(record: &ProcessRecord) -> LogSource {
+    LogSource {
+        pid: record.pid,
+        label: format!("{} (pid {})", record.label(), record.pid),
+    }
+}
+
+fn severity_color(severity: Severity) -> Color {
+    match severity {
+        Severity::Info => Color::WHITE,
+        Severity::Warning => Color::from_rgb8(250, 200, 90),
+        Severity::Error => Color::from_rgb8(250, 130, 90),
+        Severity::ScriptError => Color::from_rgb8(255, 80, 80),
+    }
+}
@@ -2,10 +2,39 @@
 
 pub mod app;
 mod errors;
+pub(crate) mod instance_chooser;
+pub(crate) mod launch_all;
+pub(crate) mod launch_preview;
+pub(crate) mod log_viewer;
 pub(crate) mod number_input;
+mod popup;
+pub(crate) mod process_list;
 pub(crate) mod profile_chooser;
+pub(crate) mod profile_settings;
+pub(crate) mod scheduler;
 pub(crate) mod selection_listbox;
 pub(crate) mod welcome_message;
 
 pub use app::App;
 use errors::Errors;
+use iced::Task;
+use iced::futures::channel::oneshot;
+use std::thread;
+
+/// Run blocking work like waiting for a process or walking mod folders on its own thread, so the
+/// window keeps responding. The result is turned into a message once the work is done
+pub(crate) fn background<T, M>(
+    work: impl FnOnce() -> T + Send + 'static,
+    to_message: impl Fn(T) -> M + Send + 'static,
+) -> Task<M>
+where
+    T: Send + 'static,
+    M: Send + 'static,
+{
+    let (sender, receiver) = oneshot::channel();
+    thread::spawn(move || {
+        let _ = sender.send(work());
+    });
+    // the sender is only dropped without sending if the work panicked
+    Task::perform(receiver, Result::ok).and_then(move |result| Task::done(to_message(result)))
+}
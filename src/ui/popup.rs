@@ -0,0 +1,28 @@
+//! Look of the popups shown on top of the main window
+use iced::widget::container;
+use iced::{Background, Color, Element, Length, Theme};
+
+/// Style of the popup itself
+pub(crate) fn popup_style(_: &Theme) -> container::Style {
+    container::Style {
+        text_color: None,
+        background: Some(Background::Color(Color::from_rgba8(114, 119, 130, 1.0))),
+        border: iced::Border::default().rounded(6.0).width(1.5),
+        shadow: iced::Shadow::default(),
+        snap: false,
+    }
+}
+
+/// Center the popup on a dimmed backdrop covering the window
+pub(crate) fn backdrop<'a, M: 'a>(popup: impl Into<Element<'a, M>>) -> Element<'a, M> {
+    container(popup)
+        .center(Length::Fill)
+        .style(|_| container::Style {
+            background: Some(Background::Color(Color::from_rgba8(43, 45, 49, 0.4))),
+            text_color: None,
+            border: iced::Border::default().rounded(6.0).width(1.5),
+            shadow: iced::Shadow::default(),
+            snap: false,
+        })
+        .into()
+}
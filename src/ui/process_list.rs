@@ -0,0 +1,223 @@
+//! List of the server and HC processes started by the launcher, with controls to stop and restart them
+use crate::Config;
+use crate::arma::processes::{
+    ProcessRecord, ProcessRegistry, Relaunch, StopRequest, scan_for_restart,
+};
+use crate::arma::watchdog::{PendingRestart, handle_exits, restart_crashed};
+use crate::ui::background;
+use iced::alignment::Vertical;
+use iced::widget::{button, checkbox, column, row, scrollable, text};
+use iced::{Element, Length, Task};
+use std::path::Path;
+use std::sync::{Arc, RwLock};
+
+#[derive(Debug, Default)]
+pub struct ProcessList {
+    pub registry: ProcessRegistry,
+    config: Arc<RwLock<Config>>,
+}
+
+#[derive(Debug, Clone)]
+pub enum Message {
+    /// check which processes are still running
+    Refresh(),
+    /// stop the process with pid
+    Stop(u32),
+    /// stop and launch the process with pid again
+    Restart(u32),
+    /// servers running from the A3 root of the process to restart were looked for
+    Scanned(u32, Vec<u32>),
+    /// servers running from the A3 root of the crashed process were looked for, it is launched
+    /// again
+    CrashScanned(Box<PendingRestart>, Vec<u32>),
+    /// the process with pid stopped or failed to, launched again if it was restarted
+    Stopped(u32, Result<Option<i32>, String>, Option<Box<Relaunch>>),
+    /// remove an exited process from the list
+    Remove(u32),
+    /// show the log of the process with pid, handled in main app as it owns the log viewer
+    ShowLog(u32),
+    /// turn restarting of crashed processes on or off
+    ToggleWatchdog(bool),
+    /// Error message for catching in main app
+    Error(String),
+}
+
+impl ProcessList {
+    pub fn new(path: &Path, config: Arc<RwLock<Config>>) -> ProcessList {
+        Self {
+            registry: ProcessRegistry::load(path),
+            config,
+        }
+    }
+
+    pub fn update(&mut self, message: Message) -> Task<Message> {
+        let result = match message {
+            Message::Refresh() => {
+                let exited = self.registry.refresh();
+                let config = self.config.read().unwrap();
+                let (reports, pending) = handle_exits(&mut self.registry, exited, &config.watchdog);
+                // crashes are shown like errors, so they are noticed
+                let reports = reports.into_iter().map(|r| Task::done(Message::Error(r)));
+                // restarts look for running servers first, same as restarts from the list
+                let restarts = pending.into_iter().map(|pending| {
+                    let record = pending.record.clone();
+                    let key_settings = config.keys.clone();
+                    background(
+                        move || scan_for_restart(&record, &key_settings),
+                        move |scanned| Message::CrashScanned(Box::new(pending.clone()), scanned),
+                    )
+                });
+                return Task::batch(reports.chain(restarts));
+            }
+            Message::CrashScanned(pending, scanned) => {
+                let key_settings = self.config.read().unwrap().keys.clone();
+                let report = restart_crashed(&mut self.registry, *pending, &key_settings, &scanned);
+                return Task::done(Message::Error(report));
+            }
+            Message::Stop(pid) => {
+                return match self.registry.begin_stop(pid) {
+                    Ok(request) => wait_for_stop(request, None),
+                    Err(err) => Task::done(Message::Error(err.to_string())),
+                };
+            }
+            Message::Restart(pid) => {
+                let Some(record) = self.registry.records.iter().find(|r| r.pid == pid).cloned()
+                else {
+                    return Task::done(Message::Error(format!("No process with pid {}", pid)));
+                };
+                // a new server changes the keys folder, looking for servers using it takes a while
+                let key_settings = self.config.read().unwrap().keys.clone();
+                return background(
+                    move || scan_for_restart(&record, &key_settings),
+                    move |scanned| Message::Scanned(pid, scanned),
+                );
+            }
+            Message::Scanned(pid, scanned) => {
+                let key_settings = self.config.read().unwrap().keys.clone();
+                let relaunch = match self.registry.prepare_restart(pid, &key_settings, &scanned) {
+                    Ok(relaunch) => relaunch,
+                    Err(err) => return Task::done(Message::Error(err.to_string())),
+                };
+                match relaunch.record.is_running() {
+                    true => {
+                        return match self.registry.begin_stop(pid) {
+                            Ok(request) => wait_for_stop(request, Some(Box::new(relaunch))),
+                            Err(err) => Task::done(Message::Error(err.to_string())),
+                        };
+                    }
+                    false => self.registry.relaunch(relaunch).map(|_| ()),
+                }
+            }
+            Message::Stopped(pid, result, relaunch) => self
+                .registry
+                .finish_stop(pid, result.map_err(anyhow::Error::msg))
+                .and_then(|_| match relaunch {
+                    Some(relaunch) => self.registry.relaunch(*relaunch).map(|_| ()),
+                    None => Ok(()),
+                }),
+            Message::ToggleWatchdog(enabled) => {
+                let mut config = self.config.write().unwrap();
+                config.watchdog.enabled = enabled;
+                config.save()
+            }
+            Message::Remove(pid) => self.registry.remove(pid),
+            Message::ShowLog(_) => Ok(()),
+            Message::Error(_) => Ok(()),
+        };
+
+        match result {
+            Ok(_) => Task::none(),
+            Err(err) => Task::done(Message::Error(err.to_string())),
+        }
+    }
+
+    pub fn view<'app>(&'app self, _app: &'app super::App) -> Element<'app, Message> {
+        let processes = self
+            .registry
+            .records
+            .iter()
+            .fold(column![].spacing(4), |col, record| {
+                col.push(process_row(record))
+            });
+
+        let watchdog = self.config.read().unwrap().watchdog.enabled;
+
+        column![
+            row![
+                text("Processes").size(24),
+                checkbox(watchdog)
+                    .label("Restart crashed processes")
+                    .on_toggle(Message::ToggleWatchdog),
+            ]
+            .spacing(20)
+            .align_y(Vertical::Center),
+            scrollable(processes).width(Length::Fill)
+        ]
+        .spacing(4)
+        .padding(15)
+        .into()
+    }
+}
+
+/// Wait for the process to exit away from the UI thread, it can take a while to shut down
+fn wait_for_stop(request: StopRequest, relaunch: Option<Box<Relaunch>>) -> Task<Message> {
+    let pid = request.pid;
+    background(
+        move || request.wait().map_err(|err| err.to_string()),
+        move |result| Message::Stopped(pid, result, relaunch.clone()),
+    )
+}
+
+fn process_row(record: &ProcessRecord) -> Element<'_, Message> {
+    let exited = match record.crashed() {
+        true => "Crashed",
+        false => "Exited",
+    };
+    let mut status = match (record.exited, record.exit_code) {
+        (None, _) => "Running".to_string(),
+        (Some(time), Some(code)) => {
+            format!("{} {} (code {})", exited, time.format("%H:%M:%S"), code)
+        }
+        (Some(time), None) => format!("{} {}", exited, time.format("%H:%M:%S")),
+    };
+    if record.restarts > 0 {
+        status.push_str(&format!(", restarted {}x", record.restarts));
+    }
+
+    if record.is_running() && record.stopped {
+        status = "Stopping".to_string();
+    }
+
+    let controls = match record.is_running() {
+        // the stop is still in progress
+        true if record.stopped => row![button("Log").on_press(Message::ShowLog(record.pid))],
+        true => row![
+            button("Stop").on_press(Message::Stop(record.pid)),
+            button("Restart").on_press(Message::Restart(record.pid)),
+            button("Log").on_press(Message::ShowLog(record.pid)),
+        ],
+        false => row![
+            button("Restart").on_press(Message::Restart(record.pid)),
+            button("Remove").on_press(Message::Remove(record.pid)),
+            button("Log").on_press(Message::ShowLog(record.pid)),
+        ],
+    };
+
+    row![
+        text(record.label()).width(Length::FillPortion(2)),
+        text(format!("pid {}", record.pid)).width(Length::FillPortion(1)),
+        text(format!("port {}", record.port)).width(Length::FillPortion(1)),
+        text(&record.profile).width(Length::FillPortion(2)),
+        text(format!("{} mods", record.mods.len())).width(Length::FillPortion(1)),
+        text(format!(
+            "started {}",
+            record.started.format("%Y-%m-%d %H:%M:%S")
+        ))
+        .width(Length::FillPortion(2)),
+        text(status).width(Length::FillPortion(2)),
+        controls.spacing(4).width(Length::FillPortion(2)),
+    ]
+    .align_y(Vertical::Center)
+    .spacing(8)
+    .into()
+}
@@ -47,6 +47,13 @@ impl ProfileChooser {
         }
     }
 
+    /// Select a profile, if it is one of the options
+    pub fn select(&mut self, profile: &str) {
+        if self.options.iter().any(|p| p == profile) {
+            self.selected = Some(profile.to_string());
+        }
+    }
+
     pub fn get_selected_profile(&self) -> String {
         self.selected.clone().expect("No selected profile")
     }
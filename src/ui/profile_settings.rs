@@ -0,0 +1,265 @@
+//! Popup to edit the launch parameters and config files of a server profile
+
+use crate::Config;
+use crate::arma::server_launch::{find_basic_config, find_config, server_configs};
+use crate::configs::profile::{LaunchFlag, LaunchParameters, LaunchValue, ProfileSettings};
+use crate::ui::popup::{backdrop, popup_style};
+use iced::alignment::{Horizontal, Vertical};
+use iced::widget::space::vertical;
+use iced::widget::{button, checkbox, column, container, row, text, text_input};
+use iced::{Element, Length, Task};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::sync::{Arc, RwLock};
+
+#[derive(Debug, Default)]
+pub struct ProfileSettingsView {
+    /// show the popup
+    pub open: bool,
+    profile: String,
+    /// A3 root of the selected instance, the config files are relative to it
+    a3_root: PathBuf,
+    parameters: LaunchParameters,
+    /// raw text of the numeric inputs, parsed on save
+    values: BTreeMap<LaunchValue, String>,
+    /// extra parameters, separated by whitespace
+    extra: String,
+    /// server and basic config files relative to the A3 root, empty for the default
+    server_config: String,
+    basic_config: String,
+    /// .cfg files found in the A3 root, to pick the server config from
+    available_configs: Vec<String>,
+    config: Arc<RwLock<Config>>,
+}
+
+#[derive(Debug, Clone)]
+pub enum Message {
+    /// open popup for the given profile, with the selected instance
+    Open(String, Option<String>),
+    /// close popup without saving
+    Close(),
+    ToggleFlag(LaunchFlag, bool),
+    ChangeValue(LaunchValue, String),
+    ChangeExtra(String),
+    ChangeServerConfig(String),
+    ChangeBasicConfig(String),
+    /// save parameters to config and close
+    Save(),
+    /// Error message for catching in main app
+    Error(String),
+}
+
+impl ProfileSettingsView {
+    pub fn new(config: Arc<RwLock<Config>>) -> ProfileSettingsView {
+        Self {
+            config,
+            ..Default::default()
+        }
+    }
+
+    pub fn update(&mut self, message: Message) -> Task<Message> {
+        match message {
+            Message::Open(profile, instance) => {
+                let config = self.config.read().unwrap();
+                let settings = config.profile_settings(&profile);
+                let parameters = settings.launch_parameters;
+                self.a3_root = config.instance_a3_root(instance.as_deref());
+
+                self.available_configs = server_configs(&self.a3_root)
+                    .unwrap_or_default()
+                    .iter()
+                    .map(|c| {
+                        c.file_name()
+                            .unwrap_or_default()
+                            .to_string_lossy()
+                            .to_string()
+                    })
+                    .collect();
+                self.server_config = path_input(&settings.server_config);
+                self.basic_config = path_input(&settings.basic_config);
+
+                self.values = LaunchValue::ALL
+                    .iter()
+                    .map(|v| {
+                        let value = parameters.value(*v).map(|n| n.to_string());
+                        (*v, value.unwrap_or_default())
+                    })
+                    .collect();
+                self.extra = parameters.extra.join(" ");
+                self.parameters = parameters;
+                self.profile = profile;
+                self.open = true;
+                Task::none()
+            }
+            Message::Close() => {
+                self.open = false;
+                Task::none()
+            }
+            Message::ToggleFlag(flag, enabled) => {
+                self.parameters.set_flag(flag, enabled);
+                Task::none()
+            }
+            Message::ChangeValue(value, input) => {
+                self.values.insert(value, input);
+                Task::none()
+            }
+            Message::ChangeExtra(input) => {
+                self.extra = input;
+                Task::none()
+            }
+            Message::ChangeServerConfig(input) => {
+                self.server_config = input;
+                Task::none()
+            }
+            Message::ChangeBasicConfig(input) => {
+                self.basic_config = input;
+                Task::none()
+            }
+            Message::Save() => {
+                let mut parameters = self.parameters.clone();
+
+                // empty input means the parameter is not passed to the server
+                for (value, input) in &self.values {
+                    let input = input.trim();
+                    if input.is_empty() {
+                        parameters.set_value(*value, None);
+                        continue;
+                    }
+                    match input.parse::<u32>() {
+                        Ok(n) => parameters.set_value(*value, Some(n)),
+                        Err(_) => {
+                            return Task::done(Message::Error(format!(
+                                "Invalid value for {}: {}",
+                                value.name(),
+                                input
+                            )));
+                        }
+                    }
+                }
+                parameters.extra = self.extra.split_whitespace().map(String::from).collect();
+
+                let settings = ProfileSettings {
+                    launch_parameters: parameters.clone(),
+                    server_config: input_path(&self.server_config),
+                    basic_config: input_path(&self.basic_config),
+                };
+
+                // a chosen file that does not exist would only fail at launch
+                let mut config = self.config.write().unwrap();
+                let result = find_config(&self.a3_root, settings.server_config.as_deref())
+                    .and_then(|_| {
+                        find_basic_config(
+                            &self.a3_root,
+                            &self.profile,
+                            settings.basic_config.as_deref(),
+                        )
+                    })
+                    .and_then(|_| config.set_profile_settings(&self.profile, settings));
+                if let Err(err) = result {
+                    return Task::done(Message::Error(err.to_string()));
+                }
+
+                self.parameters = parameters;
+                self.open = false;
+                Task::none()
+            }
+            Message::Error(_) => Task::none(),
+        }
+    }
+
+    pub fn view<'app>(&'app self, _app: &'app super::App) -> Option<Element<'app, Message>> {
+        if !self.open {
+            return None;
+        }
+
+        let flags = LaunchFlag::ALL
+            .iter()
+            .fold(column![].spacing(6), |col, flag| {
+                let flag = *flag;
+                col.push(
+                    checkbox(self.parameters.flag(flag))
+                        .label(format!("-{}", flag.name()))
+                        .on_toggle(move |enabled| Message::ToggleFlag(flag, enabled)),
+                )
+            });
+
+        let values = LaunchValue::ALL
+            .iter()
+            .fold(column![].spacing(6), |col, value| {
+                let value = *value;
+                col.push(
+                    row![
+                        text(format!("-{}", value.name())).width(Length::FillPortion(2)),
+                        text_input(
+                            "not set",
+                            self.values.get(&value).map(String::as_str).unwrap_or("")
+                        )
+                        .on_input(move |input| Message::ChangeValue(value, input))
+                        .width(Length::FillPortion(1)),
+                    ]
+                    .align_y(Vertical::Center),
+                )
+            });
+
+        let popup = container(column![
+            text(format!("Launch Parameters: {}", self.profile))
+                .size(30)
+                .align_x(Horizontal::Center)
+                .width(Length::Fill),
+            vertical().height(15.0),
+            row![
+                flags.width(Length::FillPortion(1)),
+                values.width(Length::FillPortion(2))
+            ]
+            .spacing(20),
+            vertical().height(10.0),
+            text("Extra parameters"),
+            text_input("-noSound -autoInit", &self.extra).on_input(Message::ChangeExtra),
+            vertical().height(10.0),
+            text(format!(
+                "Server config, relative to the A3 root. Found: {}",
+                match self.available_configs.is_empty() {
+                    true => "none".to_string(),
+                    false => self.available_configs.join(", "),
+                }
+            )),
+            text_input("only .cfg in the A3 root", &self.server_config)
+                .on_input(Message::ChangeServerConfig),
+            text("Basic network config, relative to the A3 root"),
+            text_input(
+                &format!("{}/Users/server/Arma3.cfg", self.profile),
+                &self.basic_config
+            )
+            .on_input(Message::ChangeBasicConfig),
+            vertical().height(15.0),
+            container(
+                row![
+                    button("Save").on_press(Message::Save()),
+                    button("Cancel").on_press(Message::Close()),
+                ]
+                .spacing(10)
+            )
+            .center_x(Length::Fill)
+        ])
+        .padding(20)
+        .center_y(Length::Fixed(600.0))
+        .center_x(Length::Fixed(620.0))
+        .style(popup_style);
+
+        Some(backdrop(popup))
+    }
+}
+
+fn path_input(path: &Option<PathBuf>) -> String {
+    path.as_ref()
+        .map(|p| p.to_string_lossy().to_string())
+        .unwrap_or_default()
+}
+
+/// empty input means the default is used
+fn input_path(input: &str) -> Option<PathBuf> {
+    match input.trim() {
+        "" => None,
+        input => Some(PathBuf::from(input)),
+    }
+}
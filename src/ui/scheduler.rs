@@ -0,0 +1,230 @@
+//! Runs the scheduled launches from the config and lists when they run next
+use crate::Config;
+use crate::arma::dependencies::cache_patches;
+use crate::arma::mod_size::mod_folder;
+use crate::arma::processes::{ProcessRegistry, StopRequest, scan_servers};
+use crate::arma::scheduler::{ReadyJob, StartedJob, due, prepare_job, start_job, upcoming};
+use crate::arma::selection::select_launch;
+use crate::ui::background;
+use anyhow::Result;
+use chrono::{DateTime, Local};
+use iced::widget::{Column, column, row, text};
+use iced::{Element, Length, Task};
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, RwLock};
+
+#[derive(Debug)]
+pub struct Scheduler {
+    config: Arc<RwLock<Config>>,
+    /// jobs due after this are run on the next tick
+    last_check: DateTime<Local>,
+    /// what happened on the last run of each job, by name
+    results: HashMap<String, String>,
+    /// names of due jobs, run one after another so their launches do not overlap
+    queue: VecDeque<String>,
+    /// job waiting for the servers on its A3 root to be looked for
+    scanning: Option<String>,
+    /// job waiting for the processes it replaces to stop
+    stopping: Option<ReadyJob>,
+}
+
+#[derive(Debug, Clone)]
+pub enum Message {
+    /// queue jobs that are due and start the next one
+    Tick(),
+    /// the servers running from the A3 root of the job were looked for. Handled in main app as it
+    /// owns the process list
+    Scanned(Vec<u32>),
+    /// the processes replaced by the running job stopped, with their exit codes. Handled in main
+    /// app as it owns the process list
+    Stopped(Vec<(u32, Result<Option<i32>, String>)>),
+    /// Error message for catching in main app
+    Error(String),
+}
+
+impl Scheduler {
+    pub fn new(config: Arc<RwLock<Config>>) -> Scheduler {
+        Self {
+            config,
+            // jobs missed while the launcher was closed are not run
+            last_check: Local::now(),
+            results: HashMap::new(),
+            queue: VecDeque::new(),
+            scanning: None,
+            stopping: None,
+        }
+    }
+
+    pub fn has_jobs(&self) -> bool {
+        !self.config.read().unwrap().schedules.is_empty()
+    }
+
+    /// Queue the jobs that became due since the last tick, and start the next one once nothing
+    /// else is launching. `launching` is whether the HCs of an earlier launch are still pending
+    pub fn tick(&mut self, launching: bool) -> Task<Message> {
+        let config = self.config.read().unwrap();
+        let now = Local::now();
+        for job in due(&config.schedules, &self.last_check, &now) {
+            if !self.queue.contains(&job.name) {
+                self.queue.push_back(job.name.clone());
+            }
+        }
+        self.last_check = now;
+
+        if launching || self.scanning.is_some() || self.stopping.is_some() {
+            return Task::none();
+        }
+        // the job may have been removed from the config since
+        let Some(job) = self
+            .queue
+            .pop_front()
+            .and_then(|name| config.schedules.iter().find(|job| job.name == name))
+        else {
+            return Task::none();
+        };
+
+        log::info!("Running scheduled launch {}", job.name);
+        self.scanning = Some(job.name.clone());
+        // servers started by hand use the keys folder too. Looking for them takes a while, and so
+        // does reading the configs of the mods for the dependency check
+        let shared = self.config.clone();
+        let selection = job.selection.clone();
+        background(
+            move || {
+                let config = shared.read().unwrap();
+                if let Ok((launch, _)) = select_launch(&config, &selection) {
+                    for mod_path in launch.mods.iter().chain(launch.server_mods.iter()) {
+                        cache_patches(&mod_folder(&launch.a3_root, mod_path));
+                    }
+                }
+                scan_servers(&config.instance_a3_root(selection.instance.as_deref()))
+            },
+            Message::Scanned,
+        )
+    }
+
+    /// Check the launch of the job, and stop what runs on its port. `scanned` are the servers
+    /// found running from its A3 root
+    pub fn prepare(&mut self, registry: &mut ProcessRegistry, scanned: Vec<u32>) -> Task<Message> {
+        let c = self.config.clone();
+        let config = c.read().unwrap();
+        // the job may have been removed from the config since
+        let Some(job) = self
+            .scanning
+            .take()
+            .and_then(|name| config.schedules.iter().find(|job| job.name == name))
+        else {
+            return Task::none();
+        };
+
+        match prepare_job(job, &config, registry, &scanned) {
+            Ok((ready, stops)) => {
+                self.stopping = Some(ready);
+                wait_for_stops(stops)
+            }
+            Err(err) => {
+                self.set_result(&job.name, "failed");
+                Task::done(Message::Error(format!(
+                    "{:#}",
+                    err.context(format!("Scheduled launch {} failed", job.name))
+                )))
+            }
+        }
+    }
+
+    /// Launch the server of the job once the processes it replaces stopped. Returns the started
+    /// server with the number of HCs to start for it
+    pub fn finish_job(
+        &mut self,
+        registry: &mut ProcessRegistry,
+        stopped: Vec<(u32, Result<Option<i32>, String>)>,
+    ) -> Result<Option<(StartedJob, u64)>> {
+        let Some(job) = self.stopping.take() else {
+            return Ok(None);
+        };
+        let name = job.name.clone();
+        let hcs = job.hcs;
+
+        // a process that did not stop keeps its port, so the server is not launched
+        let mut result = Ok(());
+        for (pid, stop) in stopped {
+            let finished = registry.finish_stop(pid, stop.map_err(anyhow::Error::msg));
+            result = result.and(finished);
+        }
+        let result = result.and_then(|_| start_job(job, registry));
+
+        let summary = match &result {
+            Ok(started) => format!("started server (pid {})", started.pid),
+            Err(_) => "failed".to_string(),
+        };
+        self.set_result(&name, &summary);
+        result
+            .map(|started| Some((started, hcs)))
+            .map_err(|err| err.context(format!("Scheduled launch {} failed", name)))
+    }
+
+    fn set_result(&mut self, name: &str, summary: &str) {
+        self.results.insert(
+            name.to_string(),
+            format!("{} {}", Local::now().format("%Y-%m-%d %H:%M"), summary),
+        );
+    }
+
+    pub fn view<'app>(&'app self, _app: &'app super::App) -> Option<Element<'app, Message>> {
+        let config = self.config.read().unwrap();
+        if config.schedules.is_empty() {
+            return None;
+        }
+
+        let jobs = upcoming(&config.schedules, &Local::now())
+            .into_iter()
+            .map(|(job, next)| {
+                let next = match next {
+                    Ok(time) => format!("next {}", time.format("%a %Y-%m-%d %H:%M")),
+                    Err(err) => err.to_string(),
+                };
+                let last = match self.results.get(&job.name) {
+                    Some(result) => format!("last {}", result),
+                    None => String::new(),
+                };
+                row![
+                    text(job.name.clone()).width(Length::FillPortion(2)),
+                    text(match (&job.selection.instance, &job.selection.port) {
+                        (_, Some(port)) => format!("port {}", port),
+                        (Some(instance), None) => instance.clone(),
+                        (None, None) => "port 2302".to_string(),
+                    })
+                    .width(Length::FillPortion(1)),
+                    text(next).width(Length::FillPortion(3)),
+                    text(last).width(Length::FillPortion(3)),
+                ]
+                .spacing(8)
+                .into()
+            })
+            .collect::<Column<_>>()
+            .spacing(4);
+
+        Some(
+            column![text("Schedule").size(24), jobs]
+                .spacing(4)
+                .padding(15)
+                .into(),
+        )
+    }
+}
+
+/// Wait for the processes to stop on another thread, a server takes a while to shut down
+fn wait_for_stops(stops: Vec<StopRequest>) -> Task<Message> {
+    background(
+        move || {
+            stops
+                .into_iter()
+                .map(|request| {
+                    let pid = request.pid;
+                    (pid, request.wait().map_err(|err| err.to_string()))
+                })
+                .collect()
+        },
+        Message::Stopped,
+    )
+}
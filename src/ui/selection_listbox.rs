@@ -1,19 +1,41 @@
 //! A listbox consisting of title, and a scrollable box with available presets. Reused across All_mods, Server_mods and Clientside mods
 use crate::ServerModList;
+use crate::arma::mod_meta::ModDetails;
+use crate::arma::mod_size::ModSize;
+use crate::ui::background;
 use iced::alignment::{Horizontal, Vertical};
-use iced::widget::{button, checkbox, column, row, scrollable, text};
-use iced::{Element, Length, Task, Theme};
+use iced::widget::image::Handle;
+use iced::widget::space::horizontal;
+use iced::widget::{button, checkbox, column, image, row, scrollable, text};
+use iced::{Color, Element, Length, Task, Theme};
+use std::path::Path;
+
+/// Size logos are drawn at in the details
+const LOGO_SIZE: f32 = 32.0;
+
+/// What was read about a mod, with its logo ready to draw
+type ModEntry = (ModDetails, Option<Handle>);
 
 #[derive(Debug)]
 pub struct SelectionListbox {
     pub id: usize,
     pub title: String,
     pub elements: Vec<ServerModList>,
+    /// modlist whose mods are shown, with what was read about each mod
+    pub expanded: Option<(usize, Vec<ModEntry>)>,
+    /// modlist whose mods are being read, to be shown once they are
+    loading: Option<usize>,
+    /// size of the mods in the selected modlists, `None` until it is worked out
+    pub selected_size: Option<ModSize>,
 }
 
 #[derive(Clone, Debug)]
 pub enum Message {
     ToggleSelection(usize, bool),
+    /// show or hide the mods of a modlist, handled in main app as the A3 root is needed
+    ToggleDetails(usize),
+    /// the mods of the modlist at the index were read in the background
+    DetailsLoaded(usize, Vec<ModEntry>),
 }
 
 impl SelectionListbox {
@@ -22,6 +44,9 @@ impl SelectionListbox {
             id,
             title,
             elements,
+            expanded: None,
+            loading: None,
+            selected_size: None,
         }
     }
 
@@ -33,9 +58,65 @@ impl SelectionListbox {
                 }
                 Task::none()
             }
+            // needs the A3 root, so the app calls toggle_details
+            Message::ToggleDetails(_) => Task::none(),
+            Message::DetailsLoaded(index, details) => {
+                // hidden again, or the modlists were replaced, while the mods were read
+                if self.loading == Some(index) {
+                    self.loading = None;
+                    self.expanded = Some((index, details));
+                }
+                Task::none()
+            }
         }
     }
 
+    /// Show the mods of the modlist at `index`, or hide them if they are shown or being read
+    pub fn toggle_details(&mut self, index: usize, a3root: &Path) -> Task<Message> {
+        if self.expanded.as_ref().is_some_and(|(i, _)| *i == index) || self.loading == Some(index) {
+            self.expanded = None;
+            self.loading = None;
+            return Task::none();
+        }
+        let Some(modlist) = self.elements.get(index) else {
+            return Task::none();
+        };
+        self.loading = Some(index);
+
+        // reading the mod.cpp, meta.cpp and logo of every mod takes a while
+        let a3root = a3root.to_path_buf();
+        let mods = modlist.mods.clone();
+        background(
+            move || {
+                mods.iter()
+                    .map(|mod_path| {
+                        let details = ModDetails::load(&a3root, mod_path);
+                        // made once, so the logo is not uploaded again on every redraw
+                        let logo = details.logo.as_ref().map(|logo| {
+                            Handle::from_rgba(logo.width(), logo.height(), logo.as_raw().clone())
+                        });
+                        (details, logo)
+                    })
+                    .collect()
+            },
+            move |details| Message::DetailsLoaded(index, details),
+        )
+    }
+
+    /// Replace the modlists, after the folders were read again. Modlists that are still there stay
+    /// selected
+    pub fn set_elements(&mut self, mut elements: Vec<ServerModList>) {
+        for element in &mut elements {
+            element.selected = self
+                .elements
+                .iter()
+                .any(|old| old.selected && old.path == element.path);
+        }
+        self.elements = elements;
+        self.expanded = None;
+        self.loading = None;
+    }
+
     // /// helper function to handle toggle event on checkbox, identical behavior as the button on_press
     // pub fn checkbox_toggled(id: u64, checked: bool) -> Message::ToggleSelection {
     //     Message::ToggleSelection(id as usize, checked);
@@ -48,26 +129,43 @@ impl SelectionListbox {
                 .iter()
                 .enumerate()
                 .fold(column![].spacing(6), |col, (i, modlist)| {
-                    col.push(row![
-                        button(row![
-                            text(&modlist.name).width(Length::FillPortion(6)),
-                            text(format!("[{}]", &modlist.mods.len()))
+                    let expanded = self.expanded.as_ref().filter(|(index, _)| *index == i);
+                    col.push(
+                        row![
+                            button(row![
+                                column![text(&modlist.name)]
+                                    .push(modlist.size.map(|size| text(size.to_string()).size(12)))
+                                    .width(Length::FillPortion(6)),
+                                text(match modlist.unresolved.len() {
+                                    0 => format!("[{}]", &modlist.mods.len()),
+                                    n => format!("[{}, {} missing]", &modlist.mods.len(), n),
+                                })
                                 .width(Length::FillPortion(2)),
-                            checkbox(modlist.selected),
-                        ])
-                        .padding(8)
-                        .style(|theme: &Theme, status| {
-                            let palette = theme.extended_palette();
-                            match modlist.selected {
-                                false => button::Style::default()
-                                    .with_background(palette.secondary.base.color),
-                                _ => button::primary(theme, status),
-                            }
-                        })
-                        .width(Length::Fill)
-                        .on_press(Message::ToggleSelection(i, !modlist.selected)),
-                        // Space::with_width(15)
-                    ])
+                                checkbox(modlist.selected),
+                            ])
+                            .padding(8)
+                            .style(|theme: &Theme, status| {
+                                let palette = theme.extended_palette();
+                                match modlist.selected {
+                                    false => button::Style::default()
+                                        .with_background(palette.secondary.base.color),
+                                    _ => button::primary(theme, status),
+                                }
+                            })
+                            .width(Length::Fill)
+                            .on_press(Message::ToggleSelection(i, !modlist.selected)),
+                            button(text(match (expanded, self.loading) {
+                                (Some(_), _) => "-",
+                                (None, Some(index)) if index == i => "...",
+                                (None, _) => "+",
+                            }))
+                            .padding(8)
+                            .on_press(Message::ToggleDetails(i)),
+                            // Space::with_width(15)
+                        ]
+                        .spacing(4),
+                    )
+                    .push(expanded.map(|(_, details)| details_view(details)))
                 });
 
         let scrollable: Element<Message> = scrollable(selection_list)
@@ -83,7 +181,48 @@ impl SelectionListbox {
                 .align_y(Vertical::Top),
             scrollable
         ]
+        .push(self.elements.iter().any(|e| e.selected).then(|| {
+            text(match self.selected_size {
+                Some(size) => format!("Selected: {}", size),
+                None => "Selected: counting size...".to_string(),
+            })
+            .size(14)
+        }))
         .padding(10)
         .into()
     }
 }
+
+/// The mods of a modlist, with their logo, name and what else is known about them
+fn details_view(details: &[ModEntry]) -> Element<'_, Message> {
+    details
+        .iter()
+        .fold(column![].spacing(4), |col, (mod_details, logo)| {
+            let logo: Element<Message> = match logo {
+                Some(logo) => image(logo.clone())
+                    .width(LOGO_SIZE)
+                    .height(LOGO_SIZE)
+                    .into(),
+                None => horizontal().width(LOGO_SIZE).into(),
+            };
+            let path = text(mod_details.path.to_string_lossy().to_string()).size(12);
+
+            col.push(
+                row![
+                    logo,
+                    column![
+                        text(mod_details.name()).size(16),
+                        text(mod_details.summary()).size(12),
+                        match mod_details.installed {
+                            true => path,
+                            false => path.color(Color::from_rgb8(220, 60, 60)),
+                        },
+                    ]
+                ]
+                .spacing(8)
+                .align_y(Vertical::Center),
+            )
+        })
+        .padding([4, 16])
+        .into()
+}